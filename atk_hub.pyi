@@ -0,0 +1,180 @@
+"""Type stubs for the atk_hub module, see src/python.rs."""
+
+from enum import Enum
+from os import PathLike
+from typing import Optional, Union
+
+class AtkError(Exception): ...
+class DeviceError(AtkError): ...
+class ProtocolError(AtkError): ...
+class InvalidValueError(AtkError): ...
+class VerificationError(AtkError): ...
+class ReplayError(AtkError): ...
+class TransactionError(AtkError): ...
+
+class LedEffectMode(Enum):
+    Static = 0x1
+    Breathing = 0x2
+
+class LedBreathingRate(Enum):
+    Slow = 0x1
+    Medium = 0x3
+    Fast = 0x5
+
+class LedBrightnessLevel(Enum):
+    Low = 0x10
+    Medium = 0x80
+    High = 0xFF
+
+class PollingRate(Enum):
+    Hz1000 = 0x1
+    Hz500 = 0x2
+    Hz250 = 0x4
+    Hz125 = 0x8
+    Hz2000 = 0x10
+    Hz4000 = 0x20
+    Hz8000 = 0x40
+
+class SilentHeightMode(Enum):
+    Off = 0
+    OneMm = 1
+    TwoMm = 2
+
+class ConnectionType(Enum):
+    Dongle1K = 0
+    Dongle4K = 1
+    Wired1K = 2
+    Wired8K = 3
+    Dongle2K = 4
+    Dongle8K = 5
+
+class Preset(Enum):
+    Preset1 = 0
+    Preset2 = 1
+    Preset3 = 2
+    Preset4 = 3
+    Preset5 = 4
+    Preset6 = 5
+    Preset7 = 6
+    Preset8 = 7
+
+class Dpi:
+    def __init__(self, value: int) -> None: ...
+    @property
+    def value(self) -> int: ...
+    def __int__(self) -> int: ...
+
+class Color:
+    def __init__(self, red: int, green: int, blue: int) -> None: ...
+    @staticmethod
+    def parse(text: str) -> Color: ...
+    @staticmethod
+    def distinct(count: int) -> list[Color]: ...
+    @property
+    def red(self) -> int: ...
+    @property
+    def green(self) -> int: ...
+    @property
+    def blue(self) -> int: ...
+
+class LedSettings:
+    mode: LedEffectMode
+    brightness: LedBrightnessLevel
+    breathing_rate: LedBreathingRate
+    enabled: bool
+    def __init__(
+        self,
+        mode: LedEffectMode,
+        brightness: LedBrightnessLevel,
+        breathing_rate: LedBreathingRate,
+        enabled: bool,
+    ) -> None: ...
+
+class MousePerformance:
+    stabilization_time_ms: int
+    motion_sync: bool
+    close_led_time_ms: int
+    linear_correction: bool
+    ripple_control: bool
+    def __init__(
+        self,
+        stabilization_time_ms: int,
+        motion_sync: bool,
+        close_led_time_ms: int,
+        linear_correction: bool,
+        ripple_control: bool,
+    ) -> None: ...
+
+class SensorPerformance:
+    move_close_led: bool
+    sensor_sleep: bool
+    sensor_sleep_time_ms: int
+    performance_mode: bool
+    rf_tx_time_ms: int
+    def __init__(
+        self,
+        move_close_led: bool,
+        sensor_sleep: bool,
+        sensor_sleep_time_ms: int,
+        performance_mode: bool,
+        rf_tx_time_ms: int,
+    ) -> None: ...
+
+class Profile:
+    @property
+    def polling_rate(self) -> PollingRate: ...
+    @property
+    def active_preset(self) -> Optional[Preset]: ...
+    @property
+    def dpi(self) -> list[Dpi]: ...
+    @property
+    def colors(self) -> list[Color]: ...
+    @property
+    def led(self) -> LedSettings: ...
+    @property
+    def mouse_performance(self) -> MousePerformance: ...
+    @property
+    def sensor_performance(self) -> SensorPerformance: ...
+    @property
+    def far_distance_mode(self) -> bool: ...
+    @property
+    def silent_height(self) -> SilentHeightMode: ...
+
+class BatteryStatus:
+    level: int
+    charge: int
+    voltage: float
+
+class DeviceInfo:
+    vendor_id: int
+    product_id: int
+    usage_page: int
+    usage: int
+    manufacturer: str
+    product: str
+    def open(self) -> MouseManager: ...
+
+def discover() -> list[DeviceInfo]: ...
+
+class MouseManager:
+    @staticmethod
+    def open(vendor_id: int, product_id: int, usage_page: int, usage: int) -> MouseManager: ...
+    @staticmethod
+    def replay(path: Union[str, PathLike[str]]) -> MouseManager: ...
+    @staticmethod
+    def simulated() -> MouseManager: ...
+    def load_profile(self) -> None: ...
+    @property
+    def profile(self) -> Profile: ...
+    def apply(self, profile: Profile) -> list[str]: ...
+    @property
+    def battery(self) -> BatteryStatus: ...
+    @property
+    def connection_type(self) -> ConnectionType: ...
+    def set_dpi(self, preset: Preset, dpi: Union[Dpi, int]) -> None: ...
+    def set_color(self, preset: Preset, color: Union[Color, str]) -> None: ...
+    led: LedSettings
+    mouse_performance: MousePerformance
+    sensor_performance: SensorPerformance
+    far_distance_mode: bool
+    silent_height: SilentHeightMode
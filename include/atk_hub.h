@@ -0,0 +1,353 @@
+#ifndef ATK_HUB_H
+#define ATK_HUB_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Number of DPI presets in [`AtkProfile`].
+ */
+#define ATK_PRESET_COUNT 8
+
+/**
+ * Length of the strings in [`AtkDeviceInfo`], including the terminating NUL.
+ */
+#define ATK_NAME_LEN 64
+
+typedef enum AtkStatus {
+  ATK_STATUS_OK = 0,
+  /**
+   * A pointer argument was NULL or a value is not accepted by the API.
+   */
+  ATK_STATUS_INVALID_ARGUMENT = 1,
+  /**
+   * Opening or talking to the HID device failed.
+   */
+  ATK_STATUS_DEVICE = 2,
+  /**
+   * The device answered with a malformed frame.
+   */
+  ATK_STATUS_PROTOCOL = 3,
+  /**
+   * A value is out of range, or data read from the device does not parse.
+   */
+  ATK_STATUS_INVALID_VALUE = 4,
+  /**
+   * Read-back verification of a write failed.
+   */
+  ATK_STATUS_VERIFICATION = 5,
+  /**
+   * The request does not match the replayed trace.
+   */
+  ATK_STATUS_REPLAY = 6,
+  /**
+   * Reading or writing a file failed.
+   */
+  ATK_STATUS_IO = 7,
+  /**
+   * The output buffer is too small, the required size was still written.
+   */
+  ATK_STATUS_BUFFER_TOO_SMALL = 8,
+  /**
+   * The library panicked, this is a bug.
+   */
+  ATK_STATUS_PANIC = 9,
+  ATK_STATUS_OTHER = 10,
+} AtkStatus;
+
+/**
+ * A connected mouse. Create with one of the `atk_manager_open*` functions and release
+ * with [`atk_manager_free`].
+ */
+typedef struct AtkManager AtkManager;
+
+/**
+ * A HID interface that may be an ATK mouse, see [`atk_discover`].
+ */
+typedef struct AtkDeviceInfo {
+  uint16_t vendor_id;
+  uint16_t product_id;
+  uint16_t usage_page;
+  uint16_t usage;
+  /**
+   * NUL terminated, truncated to fit.
+   */
+  char manufacturer[ATK_NAME_LEN];
+  /**
+   * NUL terminated, truncated to fit.
+   */
+  char product[ATK_NAME_LEN];
+} AtkDeviceInfo;
+
+typedef struct AtkColor {
+  uint8_t red;
+  uint8_t green;
+  uint8_t blue;
+} AtkColor;
+
+/**
+ * Values are the raw protocol values: mode 1 static, 2 breathing; brightness 0x10, 0x80
+ * or 0xff; breathing rate 1, 3 or 5.
+ */
+typedef struct AtkLedSettings {
+  uint8_t mode;
+  uint8_t brightness;
+  uint8_t breathing_rate;
+  bool enabled;
+} AtkLedSettings;
+
+/**
+ * Times are in milliseconds and must be whole units of the underlying setting.
+ */
+typedef struct AtkMousePerformance {
+  uint32_t stabilization_time_ms;
+  bool motion_sync;
+  /**
+   * Whole multiples of 10 seconds.
+   */
+  uint32_t close_led_time_ms;
+  bool linear_correction;
+  bool ripple_control;
+} AtkMousePerformance;
+
+/**
+ * Times are in milliseconds and must be whole units of the underlying setting.
+ */
+typedef struct AtkSensorPerformance {
+  bool move_close_led;
+  bool sensor_sleep;
+  /**
+   * Whole multiples of 10 seconds.
+   */
+  uint32_t sensor_sleep_time_ms;
+  bool performance_mode;
+  uint32_t rf_tx_time_ms;
+} AtkSensorPerformance;
+
+/**
+ * The configuration cached by the manager, see [`atk_manager_get_profile`].
+ */
+typedef struct AtkProfile {
+  uint32_t polling_rate_hz;
+  /**
+   * As stored on the device.
+   */
+  uint8_t num_profile;
+  /**
+   * As stored on the device.
+   */
+  uint8_t active_profile;
+  uint16_t dpi[ATK_PRESET_COUNT];
+  struct AtkColor colors[ATK_PRESET_COUNT];
+  struct AtkLedSettings led;
+  struct AtkMousePerformance mouse_performance;
+  struct AtkSensorPerformance sensor_performance;
+  bool far_distance_mode;
+  /**
+   * 0 off, 1 for 1mm, 2 for 2mm.
+   */
+  uint8_t silent_height;
+} AtkProfile;
+
+typedef struct AtkBattery {
+  /**
+   * Percent.
+   */
+  uint8_t level;
+  uint8_t charge;
+  float voltage;
+} AtkBattery;
+
+typedef struct AtkPairingStatus {
+  uint8_t status;
+  /**
+   * Seconds.
+   */
+  uint8_t time_left;
+} AtkPairingStatus;
+
+/**
+ * Returns the message of the last failed call on this thread, or NULL if the last call
+ * succeeded. The string is valid until the next call on this thread.
+ */
+const char *atk_last_error_message(void);
+
+/**
+ * Lists HID interfaces that may be ATK mice.
+ *
+ * Writes up to `capacity` entries to `out` and the number of interfaces found to `count`.
+ * Returns `ATK_STATUS_BUFFER_TOO_SMALL` if `count` exceeds `capacity`.
+ *
+ * # Safety
+ *
+ * `out` must be valid for `capacity` writes, it may be NULL if `capacity` is 0. `count`
+ * must be valid for writes.
+ */
+enum AtkStatus atk_discover(struct AtkDeviceInfo *out, size_t capacity, size_t *count);
+
+/**
+ * Opens the HID interface with the given identifiers and reads its configuration.
+ *
+ * # Safety
+ *
+ * `out` must be valid for writes. On success it holds a manager to be released with
+ * [`atk_manager_free`].
+ */
+enum AtkStatus atk_manager_open(uint16_t vendor_id,
+                                uint16_t product_id,
+                                uint16_t usage_page,
+                                uint16_t usage,
+                                struct AtkManager **out);
+
+/**
+ * Answers commands from a trace recorded with `--trace`, see the `trace` module.
+ *
+ * # Safety
+ *
+ * `path` must be a NUL terminated string. `out` must be valid for writes. On success it
+ * holds a manager to be released with [`atk_manager_free`].
+ */
+enum AtkStatus atk_manager_open_replay(const char *path, struct AtkManager **out);
+
+/**
+ * Opens a simulated mouse, for testing code against the API without hardware.
+ *
+ * # Safety
+ *
+ * `out` must be valid for writes. On success it holds a manager to be released with
+ * [`atk_manager_free`].
+ */
+enum AtkStatus atk_manager_open_simulated(struct AtkManager **out);
+
+/**
+ * Closes the device. Passing NULL does nothing.
+ *
+ * # Safety
+ *
+ * `manager` must be NULL or returned by an `atk_manager_open*` function and not freed.
+ */
+void atk_manager_free(struct AtkManager *manager);
+
+/**
+ * Reads the configuration from the device again.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_load_profile(const struct AtkManager *manager);
+
+/**
+ * Copies the cached configuration to `out`. Does not talk to the device.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+ * must be valid for writes.
+ */
+enum AtkStatus atk_manager_get_profile(const struct AtkManager *manager, struct AtkProfile *out);
+
+/**
+ * Sets the DPI of preset 0 to 7.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_set_dpi(const struct AtkManager *manager,
+                                   uint8_t preset_index,
+                                   uint16_t dpi);
+
+/**
+ * Sets the colour of preset 0 to 7. The manager's colour correction is applied.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_set_color(const struct AtkManager *manager,
+                                     uint8_t preset_index,
+                                     struct AtkColor color);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+ * must be valid for reads.
+ */
+enum AtkStatus atk_manager_set_led(const struct AtkManager *manager,
+                                   const struct AtkLedSettings *settings);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+ * must be valid for reads.
+ */
+enum AtkStatus atk_manager_set_mouse_performance(const struct AtkManager *manager,
+                                                 const struct AtkMousePerformance *settings);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+ * must be valid for reads.
+ */
+enum AtkStatus atk_manager_set_sensor_performance(const struct AtkManager *manager,
+                                                  const struct AtkSensorPerformance *settings);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_set_far_distance_mode(const struct AtkManager *manager, bool enabled);
+
+/**
+ * Sets the lift-off height: 0 off, 1 for 1mm, 2 for 2mm.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_set_silent_height(const struct AtkManager *manager, uint8_t height);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+ * must be valid for writes.
+ */
+enum AtkStatus atk_manager_battery(const struct AtkManager *manager, struct AtkBattery *out);
+
+/**
+ * Puts the dongle in pairing mode, poll [`atk_manager_pairing_status`] for progress.
+ *
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_start_pairing(const struct AtkManager *manager);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+ * must be valid for writes.
+ */
+enum AtkStatus atk_manager_pairing_status(const struct AtkManager *manager,
+                                          struct AtkPairingStatus *out);
+
+/**
+ * # Safety
+ *
+ * `manager` must be returned by an `atk_manager_open*` function and not freed.
+ */
+enum AtkStatus atk_manager_exit_pairing(const struct AtkManager *manager);
+
+#endif  /* ATK_HUB_H */
@@ -0,0 +1,524 @@
+//! Switches the mouse to a saved profile or DPI preset while an application runs.
+//!
+//! Running processes are read from `/proc` and matched against [`Rule`]s. When the best
+//! matching rule changes, the settings in effect before the first switch are combined
+//! with the rule's target and only the blocks that differ from the device are written.
+//! Once no rule matches anymore those settings are restored the same way.
+
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::prelude::*,
+    library::{Library, ProfileSnapshot},
+    manager::{transaction::Block, MouseManager},
+};
+use libatk_rs::prelude::*;
+
+/// Version of the rules file format, bumped on incompatible changes.
+pub const RULES_FORMAT: u32 = 1;
+
+/// A process as seen through `/proc/<pid>`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Process {
+    pub pid: u32,
+    /// Target of `/proc/<pid>/exe`, only readable for processes of the same user.
+    pub exe: Option<PathBuf>,
+    /// Kernel name of the process, truncated to 15 bytes.
+    pub comm: String,
+    /// Arguments separated by spaces.
+    pub cmdline: String,
+}
+
+impl Process {
+    /// Reads `/proc/<pid>`, `None` if the process exited in the meantime.
+    pub fn read(dir: &Path) -> Option<Self> {
+        let pid = dir.file_name()?.to_str()?.parse().ok()?;
+        let comm = std::fs::read_to_string(dir.join("comm")).ok()?;
+        let cmdline = std::fs::read(dir.join("cmdline")).ok()?;
+        let exe = std::fs::read_link(dir.join("exe")).ok().map(|exe| {
+            match exe.to_str().and_then(|exe| exe.strip_suffix(" (deleted)")) {
+                Some(replaced) => PathBuf::from(replaced),
+                None => exe,
+            }
+        });
+
+        Some(Process {
+            pid,
+            exe,
+            comm: comm.trim_end_matches('\n').to_string(),
+            cmdline: cmdline
+                .split(|&byte| byte == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(String::from_utf8_lossy)
+                .collect::<Vec<_>>()
+                .join(" "),
+        })
+    }
+
+    /// Names the process goes by: the executable's file name, the kernel name and the file
+    /// name of the first argument. The last one is what Wine and other launchers run.
+    pub fn names(&self) -> Vec<&str> {
+        let exe = self
+            .exe
+            .as_deref()
+            .and_then(Path::file_name)
+            .and_then(|name| name.to_str());
+        let argv0 = self
+            .cmdline
+            .split(' ')
+            .next()
+            .and_then(|arg| arg.rsplit(['/', '\\']).next())
+            .filter(|name| !name.is_empty());
+
+        exe.into_iter()
+            .chain(Some(self.comm.as_str()))
+            .chain(argv0)
+            .collect()
+    }
+}
+
+/// Lists the processes under `root`, normally `/proc`.
+pub fn processes(root: &Path) -> Result<Vec<Process>, std::io::Error> {
+    let mut processes = std::fs::read_dir(root)?
+        .filter_map(Result::ok)
+        .filter_map(|entry| Process::read(&entry.path()))
+        .collect::<Vec<_>>();
+    processes.sort_by_key(|process| process.pid);
+
+    Ok(processes)
+}
+
+/// What the mouse is switched to while a rule matches.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// A profile saved in the [`Library`].
+    Profile(String),
+    /// A DPI preset, 1 to 8, on top of the settings in effect before switching.
+    Preset(u8),
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Target::Profile(name) => write!(f, "profile {}", name),
+            Target::Preset(preset) => write!(f, "preset {}", preset),
+        }
+    }
+}
+
+/// Matches processes by every criterion that is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rule {
+    /// File name of the executable, ignoring case, e.g. `cs2` or `Wow.exe`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    /// Full path of the executable.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    /// Regular expression searched for in the command line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    #[serde(flatten)]
+    pub target: Target,
+    /// The rule with the highest priority wins when several match, the first one on ties.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// The contents of `rules.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    pub format: u32,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl Rules {
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let rules: Rules = serde_json::from_str(json)?;
+        if rules.format != RULES_FORMAT {
+            return Err(format!(
+                "Unsupported rules format {}, expected {}",
+                rules.format, RULES_FORMAT
+            )
+            .into());
+        }
+
+        Ok(rules)
+    }
+}
+
+/// The preset numbered `number` counting from 1, as printed on the mouse's LEDs.
+fn preset(number: u8) -> Result<Preset, Error> {
+    number
+        .checked_sub(1)
+        .ok_or_else(|| Error::ParseError("Preset: Presets are numbered from 1".to_string()))
+        .and_then(Preset::try_from)
+}
+
+struct Matcher {
+    rule: Rule,
+    cmdline: Option<Regex>,
+}
+
+impl Matcher {
+    fn new(rule: Rule, library: &Library) -> Result<Self, Error> {
+        if rule.exe.is_none() && rule.path.is_none() && rule.cmdline.is_none() {
+            return Err(Error::ParseError(format!(
+                "Rule: The rule for {} matches nothing, set exe, path or cmdline",
+                rule.target
+            )));
+        }
+        match &rule.target {
+            Target::Profile(name) => {
+                library.get(name)?;
+            }
+            Target::Preset(number) => {
+                preset(*number)?;
+            }
+        }
+
+        let cmdline = rule
+            .cmdline
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| Error::ParseError(format!("Rule: Invalid cmdline pattern: {}", e)))?;
+
+        Ok(Matcher { rule, cmdline })
+    }
+
+    fn matches(&self, process: &Process) -> bool {
+        let exe = self.rule.exe.as_deref().is_none_or(|exe| {
+            process
+                .names()
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(exe))
+        });
+        let path = self
+            .rule
+            .path
+            .as_deref()
+            .is_none_or(|path| process.exe.as_deref() == Some(path));
+        let cmdline = self
+            .cmdline
+            .as_ref()
+            .is_none_or(|cmdline| cmdline.is_match(&process.cmdline));
+
+        exe && path && cmdline
+    }
+}
+
+/// Settings the switcher writes.
+#[derive(Debug, Clone, PartialEq)]
+struct State {
+    settings: ProfileSnapshot,
+    preset: Option<Preset>,
+}
+
+fn active_preset(
+    manager: &MouseManager,
+) -> Result<Option<Preset>, Box<dyn std::error::Error + Send + Sync>> {
+    let info = manager.execute(Command::<MouseInfo>::query())?.config();
+    Ok(Preset::try_from(info.active_profile()).ok())
+}
+
+/// The outcome of a [`Switcher::update`] that changed the active rule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Switch {
+    /// The rule now in effect and the process it matched, `None` after reverting.
+    pub rule: Option<(Rule, Process)>,
+    /// Blocks written, empty if the device already had the settings.
+    pub blocks: Vec<Block>,
+}
+
+impl std::fmt::Display for Switch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.rule {
+            Some((rule, process)) => write!(
+                f,
+                "{} ({}) is running, switched to {}",
+                process.comm, process.pid, rule.target
+            )?,
+            None => write!(f, "No rule matches, restored the previous settings")?,
+        }
+        write!(f, ", {} blocks written", self.blocks.len())
+    }
+}
+
+/// Tracks which rule is in effect and what to restore once none is.
+pub struct Switcher {
+    matchers: Vec<Matcher>,
+    library: Library,
+    active: Option<usize>,
+    /// The settings before the first switch.
+    baseline: Option<State>,
+}
+
+impl Switcher {
+    /// Checks every rule, profile targets must exist in `library`.
+    pub fn new(rules: Vec<Rule>, library: Library) -> Result<Self, Error> {
+        let matchers = rules
+            .into_iter()
+            .map(|rule| Matcher::new(rule, &library))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Switcher {
+            matchers,
+            library,
+            active: None,
+            baseline: None,
+        })
+    }
+
+    /// The rule in effect, if any.
+    pub fn active(&self) -> Option<&Rule> {
+        self.active.map(|index| &self.matchers[index].rule)
+    }
+
+    /// The best rule matching any of `processes` and the first process it matches.
+    pub fn select<'a>(&self, processes: &'a [Process]) -> Option<(usize, &'a Process)> {
+        let mut best: Option<(usize, &Process)> = None;
+        for (index, matcher) in self.matchers.iter().enumerate() {
+            let beats_best = best
+                .is_none_or(|(best, _)| matcher.rule.priority > self.matchers[best].rule.priority);
+            if !beats_best {
+                continue;
+            }
+            if let Some(process) = processes.iter().find(|process| matcher.matches(process)) {
+                best = Some((index, process));
+            }
+        }
+        best
+    }
+
+    fn target(&self, baseline: &State, index: usize) -> Result<State, Error> {
+        Ok(match &self.matchers[index].rule.target {
+            Target::Profile(name) => State {
+                settings: self.library.get(name)?.settings.clone(),
+                preset: baseline.preset,
+            },
+            Target::Preset(number) => State {
+                settings: baseline.settings.clone(),
+                preset: Some(preset(*number)?),
+            },
+        })
+    }
+
+    /// Switches to the best rule matching `processes` if it changed since the last call.
+    ///
+    /// Settings changed by hand while a rule is in effect are overwritten when reverting.
+    pub fn update(
+        &mut self,
+        manager: &MouseManager,
+        processes: &[Process],
+    ) -> Result<Option<Switch>, Box<dyn std::error::Error + Send + Sync>> {
+        let selected = self.select(processes);
+        if selected.map(|(index, _)| index) == self.active {
+            return Ok(None);
+        }
+
+        let baseline = match &self.baseline {
+            Some(baseline) => baseline.clone(),
+            None => State {
+                settings: ProfileSnapshot::from(&*manager.profile()),
+                preset: active_preset(manager)?,
+            },
+        };
+        let target = match selected {
+            Some((index, _)) => self.target(&baseline, index)?,
+            None => baseline.clone(),
+        };
+
+        let mut blocks = target.settings.apply(manager)?;
+        if let Some(preset) = target.preset {
+            if active_preset(manager)? != Some(preset) {
+                manager.set_active_preset(preset)?;
+                blocks.push(Block::MouseInfo);
+            }
+        }
+
+        self.active = selected.map(|(index, _)| index);
+        self.baseline = selected.map(|_| baseline);
+
+        Ok(Some(Switch {
+            rule: selected
+                .map(|(index, process)| (self.matchers[index].rule.clone(), process.clone())),
+            blocks,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{library::SavedProfile, transport::simulated::SimulatedMouse};
+
+    fn process(pid: u32, exe: &str, cmdline: &str) -> Process {
+        let exe = PathBuf::from(exe);
+        Process {
+            pid,
+            comm: exe.file_name().unwrap().to_str().unwrap().to_string(),
+            exe: Some(exe),
+            cmdline: cmdline.to_string(),
+        }
+    }
+
+    fn rule(exe: Option<&str>, cmdline: Option<&str>, target: Target, priority: i32) -> Rule {
+        Rule {
+            exe: exe.map(str::to_string),
+            path: None,
+            cmdline: cmdline.map(str::to_string),
+            target,
+            priority,
+        }
+    }
+
+    /// A library with a `fps` profile that differs from the simulated mouse in one DPI.
+    fn library(manager: &MouseManager) -> Library {
+        let mut settings = ProfileSnapshot::from(&*manager.profile());
+        settings.presets[1].dpi = 1200;
+
+        let mut library = Library::default();
+        library
+            .insert("fps", SavedProfile::new(settings, None), false)
+            .unwrap();
+        library
+    }
+
+    #[test]
+    fn reads_proc() {
+        let root = std::env::temp_dir().join(format!("atk-hub-proc-{}", std::process::id()));
+        let dir = root.join("42");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::create_dir_all(root.join("self")).unwrap();
+        std::fs::write(dir.join("comm"), "wine64-preload\n").unwrap();
+        std::fs::write(dir.join("cmdline"), b"C:\\Games\\Wow.exe\0-console\0").unwrap();
+        std::os::unix::fs::symlink("/usr/bin/wine64-preloader (deleted)", dir.join("exe")).unwrap();
+
+        let processes = processes(&root).unwrap();
+        std::fs::remove_dir_all(root).unwrap();
+
+        assert_eq!(
+            processes,
+            vec![Process {
+                pid: 42,
+                exe: Some(PathBuf::from("/usr/bin/wine64-preloader")),
+                comm: "wine64-preload".to_string(),
+                cmdline: "C:\\Games\\Wow.exe -console".to_string(),
+            }]
+        );
+        assert_eq!(
+            processes[0].names(),
+            vec!["wine64-preloader", "wine64-preload", "Wow.exe"]
+        );
+    }
+
+    #[test]
+    fn highest_priority_wins() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let switcher = Switcher::new(
+            vec![
+                rule(Some("steam"), None, Target::Preset(1), 0),
+                rule(None, Some(r"-game\s+csgo"), Target::Preset(2), 5),
+                rule(Some("WOW.EXE"), None, Target::Preset(3), 5),
+                rule(Some("cs2"), None, Target::Profile("fps".to_string()), 5),
+            ],
+            library(&manager),
+        )
+        .unwrap();
+
+        let steam = process(1, "/usr/bin/steam", "steam -silent");
+        let csgo = process(2, "/opt/hl2_linux", "hl2_linux -game csgo");
+        let wow = process(3, "/usr/bin/wine", "C:\\Games\\Wow.exe");
+        let cs2 = process(4, "/opt/cs2", "cs2");
+
+        assert_eq!(switcher.select(&[]), None);
+        assert_eq!(switcher.select(std::slice::from_ref(&steam)).unwrap().0, 0);
+        assert_eq!(switcher.select(&[steam.clone(), wow.clone()]).unwrap().0, 2);
+        assert_eq!(switcher.select(&[cs2, wow.clone(), csgo]).unwrap().0, 1);
+
+        let processes = [steam, wow.clone()];
+        assert_eq!(switcher.select(&processes).unwrap().1, &wow);
+    }
+
+    #[test]
+    fn switches_and_reverts() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let before = ProfileSnapshot::from(&*manager.profile());
+        let mut switcher = Switcher::new(
+            vec![
+                rule(Some("cs2"), None, Target::Profile("fps".to_string()), 0),
+                rule(Some("wow"), None, Target::Preset(4), 1),
+            ],
+            library(&manager),
+        )
+        .unwrap();
+
+        let cs2 = process(7, "/opt/cs2", "cs2");
+        let switch = switcher
+            .update(&manager, std::slice::from_ref(&cs2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(switch.blocks, vec![Block::Dpi(Pair::Pair1)]);
+        assert_eq!(
+            manager.profile().dpi_profile(Pair::Pair1).1.dpi().dpi(),
+            1200
+        );
+        assert_eq!(
+            switcher
+                .update(&manager, std::slice::from_ref(&cs2))
+                .unwrap(),
+            None
+        );
+
+        let wow = process(8, "/opt/wow", "wow");
+        let switch = switcher.update(&manager, &[cs2, wow]).unwrap().unwrap();
+        assert_eq!(
+            switch.blocks,
+            vec![Block::Dpi(Pair::Pair1), Block::MouseInfo]
+        );
+        assert_eq!(ProfileSnapshot::from(&*manager.profile()), before);
+        assert_eq!(manager.profile().mouse_info().active_profile(), 3);
+
+        let switch = switcher.update(&manager, &[]).unwrap().unwrap();
+        assert_eq!(switch.rule, None);
+        assert_eq!(switch.blocks, vec![Block::MouseInfo]);
+        assert_eq!(manager.profile().mouse_info().active_profile(), 0);
+        assert_eq!(switcher.active(), None);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let invalid = [
+            rule(None, None, Target::Preset(1), 0),
+            rule(Some("cs2"), None, Target::Preset(9), 0),
+            rule(Some("cs2"), None, Target::Preset(0), 0),
+            rule(Some("cs2"), None, Target::Profile("mmo".to_string()), 0),
+            rule(None, Some("(unclosed"), Target::Preset(1), 0),
+        ];
+        for rule in invalid {
+            assert!(Switcher::new(vec![rule.clone()], library(&manager)).is_err());
+        }
+
+        let rules = Rules::from_json(
+            r#"{"format": 1, "rules": [{"exe": "cs2", "profile": "fps", "priority": 2}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            rules.rules,
+            vec![rule(
+                Some("cs2"),
+                None,
+                Target::Profile("fps".to_string()),
+                2
+            )]
+        );
+        assert!(Rules::from_json(r#"{"format": 2, "rules": []}"#).is_err());
+    }
+}
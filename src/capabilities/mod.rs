@@ -0,0 +1,664 @@
+//! What each mouse model supports, keyed by CID/MID and firmware version.
+//!
+//! The built-in registry only knows the generic ATK mouse, which allows everything the
+//! protocol can encode. Models are added in `capabilities.json` in the
+//! [`config_dir`](crate::library::config_dir), using the format of `builtin.json`. Fields
+//! left out of an entry take the generic value, entries without `cid` or `mid` match any.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commands::prelude::*,
+    eeprom::DeviceIdentity,
+    library,
+    manager::{transaction::Change, MouseManager, Profile},
+    types::{Decaseconds, DurationRange, Milliseconds, TimeUnit},
+};
+use libatk_rs::prelude::*;
+
+/// Version of the registry file format, bumped on incompatible changes.
+pub const REGISTRY_FORMAT: u32 = 1;
+
+const BUILTIN: &str = include_str!("builtin.json");
+
+const CHECKED: &str = "timing limits are checked when the registry is parsed";
+
+/// A setting that not every model has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    DpiLed,
+    StabilizationTime,
+    MotionSync,
+    CloseLedTime,
+    LinearCorrection,
+    RippleControl,
+    MoveCloseLed,
+    /// Sensor sleep and the sensor sleep time.
+    SensorSleep,
+    PerformanceMode,
+    RfTxTime,
+    FarDistanceMode,
+    SilentHeight,
+}
+
+const FEATURES: [Feature; 12] = [
+    Feature::DpiLed,
+    Feature::StabilizationTime,
+    Feature::MotionSync,
+    Feature::CloseLedTime,
+    Feature::LinearCorrection,
+    Feature::RippleControl,
+    Feature::MoveCloseLed,
+    Feature::SensorSleep,
+    Feature::PerformanceMode,
+    Feature::RfTxTime,
+    Feature::FarDistanceMode,
+    Feature::SilentHeight,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LedCapabilities {
+    pub modes: Vec<LedEffectMode>,
+    pub brightness: Vec<LedBrightnessLevel>,
+    pub breathing_rates: Vec<LedBreathingRate>,
+}
+
+impl Default for LedCapabilities {
+    fn default() -> Self {
+        LedCapabilities {
+            modes: vec![LedEffectMode::Static, LedEffectMode::Breathing],
+            brightness: vec![
+                LedBrightnessLevel::Low,
+                LedBrightnessLevel::Medium,
+                LedBrightnessLevel::High,
+            ],
+            breathing_rates: vec![
+                LedBreathingRate::Slow,
+                LedBreathingRate::Medium,
+                LedBreathingRate::Fast,
+            ],
+        }
+    }
+}
+
+/// Limits of a timing setting in milliseconds, for models accepting less than the protocol
+/// can encode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimingLimits {
+    pub min_ms: u32,
+    pub max_ms: u32,
+    /// Defaults to the smallest unit the setting is stored in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub step_ms: Option<u32>,
+}
+
+/// Timing limits of a model, settings left out accept the whole encodable range.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimingCapabilities {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stabilization_time: Option<TimingLimits>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub close_led_time: Option<TimingLimits>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sensor_sleep_time: Option<TimingLimits>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rf_tx_time: Option<TimingLimits>,
+}
+
+fn narrow<T: TimeUnit>(
+    range: &DurationRange<T>,
+    limits: Option<TimingLimits>,
+) -> Result<DurationRange<T>, Error> {
+    match limits {
+        Some(limits) => range.narrow(
+            limits.min_ms,
+            limits.max_ms,
+            limits.step_ms.unwrap_or(range.step().as_millis()),
+        ),
+        None => Ok(range.clone()),
+    }
+}
+
+/// What a model supports.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Capabilities {
+    pub name: String,
+    pub max_dpi: u16,
+    /// Also the lowest DPI.
+    pub dpi_step: u16,
+    pub presets: u8,
+    /// Connection types that are left out support every polling rate.
+    pub polling_rates: BTreeMap<ConnectionType, Vec<PollingRate>>,
+    pub led: LedCapabilities,
+    pub features: BTreeSet<Feature>,
+    pub timings: TimingCapabilities,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        let rates = [
+            PollingRate::Hz125,
+            PollingRate::Hz250,
+            PollingRate::Hz500,
+            PollingRate::Hz1000,
+            PollingRate::Hz2000,
+            PollingRate::Hz4000,
+            PollingRate::Hz8000,
+        ];
+        let up_to = |count: usize| rates[..count].to_vec();
+
+        Capabilities {
+            name: "Generic ATK mouse".to_string(),
+            max_dpi: 51200,
+            dpi_step: 50,
+            presets: 8,
+            polling_rates: BTreeMap::from([
+                (ConnectionType::Dongle1K, up_to(4)),
+                (ConnectionType::Dongle2K, up_to(5)),
+                (ConnectionType::Dongle4K, up_to(6)),
+                (ConnectionType::Dongle8K, up_to(7)),
+                (ConnectionType::Wired1K, up_to(4)),
+                (ConnectionType::Wired8K, up_to(7)),
+            ]),
+            led: LedCapabilities::default(),
+            features: FEATURES.into_iter().collect(),
+            timings: TimingCapabilities::default(),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Fails if a timing limit does not fit the range the protocol can encode.
+    pub fn check_timings(&self) -> Result<(), Error> {
+        let timings = &self.timings;
+        narrow(&STABILIZATION_TIME_RANGE, timings.stabilization_time)?;
+        narrow(&CLOSE_LED_TIME_RANGE, timings.close_led_time)?;
+        narrow(&SENSOR_SLEEP_TIME_RANGE, timings.sensor_sleep_time)?;
+        narrow(&RF_TX_TIME_RANGE, timings.rf_tx_time)?;
+
+        Ok(())
+    }
+
+    pub fn stabilization_time_range(&self) -> DurationRange<Milliseconds> {
+        narrow(&STABILIZATION_TIME_RANGE, self.timings.stabilization_time).expect(CHECKED)
+    }
+
+    pub fn close_led_time_range(&self) -> DurationRange<Decaseconds> {
+        narrow(&CLOSE_LED_TIME_RANGE, self.timings.close_led_time).expect(CHECKED)
+    }
+
+    pub fn sensor_sleep_time_range(&self) -> DurationRange<Decaseconds> {
+        narrow(&SENSOR_SLEEP_TIME_RANGE, self.timings.sensor_sleep_time).expect(CHECKED)
+    }
+
+    pub fn rf_tx_time_range(&self) -> DurationRange<Milliseconds> {
+        narrow(&RF_TX_TIME_RANGE, self.timings.rf_tx_time).expect(CHECKED)
+    }
+
+    pub fn unsupported(&self, what: impl std::fmt::Display) -> Error {
+        Error::ParseError(format!(
+            "Capabilities: {} is not supported by {}",
+            what, self.name
+        ))
+    }
+
+    pub fn supports(&self, feature: Feature) -> bool {
+        self.features.contains(&feature)
+    }
+
+    /// Fails if `feature` is missing and `changed` is set, writing a setting the model does
+    /// not have is harmless as long as its value stays the same.
+    fn require(&self, feature: Feature, changed: bool) -> Result<(), Error> {
+        match changed && !self.supports(feature) {
+            true => Err(self.unsupported(format!("{:?}", feature))),
+            false => Ok(()),
+        }
+    }
+
+    fn require_value<T: PartialEq + std::fmt::Debug>(
+        &self,
+        supported: &[T],
+        value: Option<T>,
+        current: T,
+    ) -> Result<(), Error> {
+        match value {
+            Some(value) if value != current && !supported.contains(&value) => {
+                Err(self.unsupported(format!("{:?}", value)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn check_preset(&self, preset: Preset) -> Result<(), Error> {
+        match (preset as u8) < self.presets {
+            true => Ok(()),
+            false => Err(self.unsupported(format!("{:?}", preset))),
+        }
+    }
+
+    pub fn check_dpi(&self, dpi: Dpi) -> Result<(), Error> {
+        let dpi = dpi.dpi();
+        if (self.dpi_step..=self.max_dpi).contains(&dpi) && dpi.is_multiple_of(self.dpi_step) {
+            return Ok(());
+        }
+
+        Err(Error::ParseError(format!(
+            "DPI: {} is not a multiple of {} up to {} as supported by {}",
+            dpi, self.dpi_step, self.max_dpi, self.name
+        )))
+    }
+
+    pub fn check_polling_rate(
+        &self,
+        connection: ConnectionType,
+        rate: PollingRate,
+    ) -> Result<(), Error> {
+        match self.polling_rates.get(&connection) {
+            Some(rates) if !rates.contains(&rate) => {
+                Err(self.unsupported(format!("{} over {:?}", rate, connection)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that `change` only touches what the model supports, given the cached `profile`.
+    pub fn check(&self, change: &Change, profile: &Profile) -> Result<(), Error> {
+        match change {
+            Change::MousePerformance(patch) => {
+                let current = profile.mouse_performance_settings();
+                if let Some(value) = patch
+                    .stabilization_time
+                    .filter(|value| *value != current.stabilization_time())
+                {
+                    self.stabilization_time_range().check(value)?;
+                }
+                if let Some(value) = patch
+                    .close_led_time
+                    .filter(|value| *value != current.close_led_time().convert())
+                {
+                    self.close_led_time_range().check(value)?;
+                }
+                self.require(
+                    Feature::StabilizationTime,
+                    patch
+                        .stabilization_time
+                        .is_some_and(|value| value != current.stabilization_time()),
+                )?;
+                self.require(
+                    Feature::MotionSync,
+                    patch
+                        .motion_sync
+                        .is_some_and(|value| value != current.motion_sync()),
+                )?;
+                self.require(
+                    Feature::CloseLedTime,
+                    patch
+                        .close_led_time
+                        .is_some_and(|value| value != current.close_led_time().convert()),
+                )?;
+                self.require(
+                    Feature::LinearCorrection,
+                    patch
+                        .linear_correction
+                        .is_some_and(|value| value != current.linear_correction()),
+                )?;
+                self.require(
+                    Feature::RippleControl,
+                    patch
+                        .ripple_control
+                        .is_some_and(|value| value != current.ripple_control()),
+                )
+            }
+            Change::SensorPerformance(patch) => {
+                let current = profile.sensor_performance_settings();
+                if let Some(value) = patch
+                    .sensor_sleep_time
+                    .filter(|value| *value != current.sensor_sleep_time().convert())
+                {
+                    self.sensor_sleep_time_range().check(value)?;
+                }
+                if let Some(value) = patch
+                    .rf_tx_time
+                    .filter(|value| *value != current.rf_tx_time())
+                {
+                    self.rf_tx_time_range().check(value)?;
+                }
+                self.require(
+                    Feature::MoveCloseLed,
+                    patch
+                        .move_close_led
+                        .is_some_and(|value| value != current.move_close_led()),
+                )?;
+                self.require(
+                    Feature::SensorSleep,
+                    patch
+                        .sensor_sleep
+                        .is_some_and(|value| value != current.sensor_sleep())
+                        || patch
+                            .sensor_sleep_time
+                            .is_some_and(|value| value != current.sensor_sleep_time().convert()),
+                )?;
+                self.require(
+                    Feature::PerformanceMode,
+                    patch
+                        .performance_mode
+                        .is_some_and(|value| value != current.performance_mode()),
+                )?;
+                self.require(
+                    Feature::RfTxTime,
+                    patch
+                        .rf_tx_time
+                        .is_some_and(|value| value != current.rf_tx_time()),
+                )
+            }
+            Change::DpiLed(patch) => {
+                let current = profile.dpi_led_settings();
+                self.require(Feature::DpiLed, current.apply(patch) != *current)?;
+                self.require_value(&self.led.modes, patch.mode, current.mode())?;
+                self.require_value(&self.led.brightness, patch.brightness, current.brightness())?;
+                self.require_value(
+                    &self.led.breathing_rates,
+                    patch.breathing_rate,
+                    current.breathing_rate(),
+                )
+            }
+            Change::FarDistanceMode(mode) => self.require(
+                Feature::FarDistanceMode,
+                *mode != profile.far_distance_mode().far_distance_mode(),
+            ),
+            Change::SilentHeight(height) => self.require(
+                Feature::SilentHeight,
+                *height != profile.silent_height().silent_height(),
+            ),
+            Change::Dpi(preset, dpi) => {
+                self.check_preset(*preset)?;
+                self.check_dpi(*dpi)
+            }
+            Change::Color(preset, _) => self.check_preset(*preset),
+        }
+    }
+}
+
+/// An entry of the registry file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mid: Option<u8>,
+    /// Lowest firmware the entry applies to, as `[major, minor]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_firmware: Option<[u8; 2]>,
+    /// Highest firmware the entry applies to, as `[major, minor]`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_firmware: Option<[u8; 2]>,
+    #[serde(flatten)]
+    pub capabilities: Capabilities,
+}
+
+impl Entry {
+    pub fn matches(&self, device: &DeviceIdentity) -> bool {
+        let firmware = [device.firmware_major, device.firmware_minor];
+        self.cid.is_none_or(|cid| cid == device.cid)
+            && self.mid.is_none_or(|mid| mid == device.mid)
+            && self.min_firmware.is_none_or(|min| firmware >= min)
+            && self.max_firmware.is_none_or(|max| firmware <= max)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RegistryFile {
+    format: u32,
+    #[serde(default)]
+    devices: Vec<Entry>,
+}
+
+/// Capabilities of every known model, the first matching entry wins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registry {
+    pub entries: Vec<Entry>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry::builtin()
+    }
+}
+
+impl Registry {
+    fn parse(json: &str) -> Result<Vec<Entry>, Box<dyn std::error::Error + Send + Sync>> {
+        let file: RegistryFile = serde_json::from_str(json)?;
+        if file.format != REGISTRY_FORMAT {
+            return Err(format!(
+                "Unsupported capability registry format {}, expected {}",
+                file.format, REGISTRY_FORMAT
+            )
+            .into());
+        }
+        for entry in &file.devices {
+            entry.capabilities.check_timings()?;
+        }
+
+        Ok(file.devices)
+    }
+
+    pub fn builtin() -> Self {
+        Registry {
+            entries: Registry::parse(BUILTIN).expect("builtin.json is valid"),
+        }
+    }
+
+    /// The built-in registry extended with the entries in `path`, which take precedence.
+    pub fn with_file(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let json = std::fs::read_to_string(path)?;
+        let mut entries =
+            Registry::parse(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+        entries.extend(Registry::builtin().entries);
+
+        Ok(Registry { entries })
+    }
+
+    /// The built-in registry, extended with `capabilities.json` in the config directory if
+    /// it exists.
+    pub fn load() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let path = library::config_dir()?.join("capabilities.json");
+        match path.exists() {
+            true => Registry::with_file(&path),
+            false => Ok(Registry::builtin()),
+        }
+    }
+
+    pub fn lookup(&self, device: &DeviceIdentity) -> Capabilities {
+        self.entries
+            .iter()
+            .find(|entry| entry.matches(device))
+            .map(|entry| entry.capabilities.clone())
+            .unwrap_or_default()
+    }
+
+    /// Identifies the mouse behind `manager` and returns its capabilities.
+    pub fn detect(
+        &self,
+        manager: &MouseManager,
+    ) -> Result<Capabilities, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.lookup(&DeviceIdentity::query(manager)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        manager::transaction::Block, transport::simulated::SimulatedMouse, types::Duration,
+    };
+
+    fn identity(cid: u8, mid: u8, firmware: [u8; 2]) -> DeviceIdentity {
+        DeviceIdentity {
+            cid,
+            mid,
+            firmware_major: firmware[0],
+            firmware_minor: firmware[1],
+        }
+    }
+
+    #[test]
+    fn builtin_is_the_generic_mouse() {
+        assert_eq!(Registry::builtin().entries.len(), 1);
+        assert_eq!(
+            Registry::builtin().lookup(&identity(1, 2, [3, 4])),
+            Capabilities::default()
+        );
+    }
+
+    #[test]
+    fn user_entries_take_precedence() {
+        let path =
+            std::env::temp_dir().join(format!("atk-hub-capabilities-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{"format": 1, "devices": [
+                {"cid": 3, "mid": 12, "max_firmware": [1, 9], "name": "Old", "presets": 5},
+                {"cid": 3, "mid": 12, "name": "New", "features": ["dpi_led"]}
+            ]}"#,
+        )
+        .unwrap();
+        let registry = Registry::with_file(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        let old = registry.lookup(&identity(3, 12, [1, 2]));
+        assert_eq!(old.name, "Old");
+        assert_eq!(old.presets, 5);
+        assert_eq!(old.max_dpi, Capabilities::default().max_dpi);
+
+        let new = registry.lookup(&identity(3, 12, [2, 0]));
+        assert_eq!(new.name, "New");
+        assert!(new.supports(Feature::DpiLed));
+        assert!(!new.supports(Feature::FarDistanceMode));
+
+        assert_eq!(
+            registry.lookup(&identity(3, 13, [1, 0])).name,
+            "Generic ATK mouse"
+        );
+    }
+
+    #[test]
+    fn timing_limits_narrow_the_encodable_ranges() {
+        let entries = Registry::parse(
+            r#"{"format": 1, "devices": [{"name": "Slow", "timings": {
+                "stabilization_time": {"min_ms": 2, "max_ms": 20, "step_ms": 2},
+                "sensor_sleep_time": {"min_ms": 10000, "max_ms": 600000}
+            }}]}"#,
+        )
+        .unwrap();
+        let capabilities = &entries[0].capabilities;
+
+        let stabilization = capabilities.stabilization_time_range();
+        assert_eq!(
+            (
+                stabilization.min(),
+                stabilization.max(),
+                stabilization.step()
+            ),
+            (Duration::new(2), Duration::new(20), Duration::new(2))
+        );
+        assert_eq!(
+            capabilities.sensor_sleep_time_range().max(),
+            Duration::from_millis(600_000)
+        );
+        assert_eq!(capabilities.rf_tx_time_range(), RF_TX_TIME_RANGE);
+
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        manager.set_capabilities(capabilities.clone());
+        assert!(manager
+            .set_mouse_performance_settings(
+                MousePerfPatch::new().stabilization_time(Duration::new(7))
+            )
+            .is_err());
+        assert!(manager
+            .set_mouse_performance_settings(
+                MousePerfPatch::new().stabilization_time(Duration::new(22))
+            )
+            .is_err());
+        manager
+            .set_mouse_performance_settings(
+                MousePerfPatch::new().stabilization_time(Duration::new(8)),
+            )
+            .unwrap();
+
+        for limits in [
+            r#"{"stabilization_time": {"min_ms": 0, "max_ms": 300}}"#,
+            r#"{"close_led_time": {"min_ms": 5000, "max_ms": 60000}}"#,
+            r#"{"rf_tx_time": {"min_ms": 8, "max_ms": 4, "step_ms": 2}}"#,
+            r#"{"rf_tx_time": {"min_ms": 4, "max_ms": 8, "step_ms": 0}}"#,
+        ] {
+            let json = format!(r#"{{"format": 1, "devices": [{{"timings": {}}}]}}"#, limits);
+            assert!(Registry::parse(&json).is_err(), "{}", limits);
+        }
+    }
+
+    #[test]
+    fn manager_rejects_unsupported_changes() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let registry = Registry {
+            entries: vec![Entry {
+                cid: Some(0x03),
+                mid: None,
+                min_firmware: None,
+                max_firmware: None,
+                capabilities: Capabilities {
+                    name: "Test mouse".to_string(),
+                    max_dpi: 12800,
+                    presets: 4,
+                    polling_rates: BTreeMap::from([(
+                        ConnectionType::Dongle1K,
+                        vec![PollingRate::Hz500, PollingRate::Hz1000],
+                    )]),
+                    features: BTreeSet::from([Feature::DpiLed, Feature::MotionSync]),
+                    ..Capabilities::default()
+                },
+            }],
+        };
+        manager.set_capabilities(registry.detect(&manager).unwrap());
+        assert_eq!(manager.capabilities().name, "Test mouse");
+
+        assert!(manager.set_far_distance_mode(true).is_err());
+        // Unchanged values of missing settings can still be written.
+        manager.set_far_distance_mode(false).unwrap();
+        assert!(manager
+            .set_dpi_profile_dpi(Preset::Preset1, Dpi::new(16000))
+            .is_err());
+        assert!(manager
+            .set_dpi_profile_dpi(Preset::Preset5, Dpi::new(800))
+            .is_err());
+        assert!(manager.set_active_preset(Preset::Preset6).is_err());
+        assert!(manager.set_polling_rate(PollingRate::Hz2000).is_err());
+        manager.set_polling_rate(PollingRate::Hz500).unwrap();
+        assert_eq!(
+            manager.profile().mouse_info().poll_rate(),
+            PollingRate::Hz500
+        );
+        assert!(manager
+            .set_mouse_performance_settings(MousePerfPatch::new().ripple_control(true))
+            .is_err());
+        manager
+            .set_mouse_performance_settings(MousePerfPatch::new().motion_sync(true))
+            .unwrap();
+
+        let error = manager
+            .transaction()
+            .dpi(Preset::Preset2, Dpi::new(1600))
+            .silent_height(SilentHeightMode::TwoMm)
+            .commit()
+            .unwrap_err();
+        assert_eq!(error.failed(), Block::SilentHeight);
+    }
+}
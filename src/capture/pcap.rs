@@ -0,0 +1,212 @@
+use libatk_rs::prelude::*;
+
+use super::{Direction, Packet};
+
+/// Linux usbmon, 48 byte header.
+const LINKTYPE_USB_LINUX: u32 = 189;
+/// Linux usbmon, 64 byte header, what `tcpdump -i usbmonX` and Wireshark produce.
+const LINKTYPE_USB_LINUX_MMAPPED: u32 = 220;
+/// USBPcap on Windows.
+const LINKTYPE_USBPCAP: u32 = 249;
+
+const PCAPNG_SECTION_HEADER: u32 = 0x0A0D0D0A;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1A2B3C4D;
+
+fn truncated(what: &str) -> Error {
+    Error::ParseError(format!("Capture: Truncated {}", what))
+}
+
+/// Reads integers in the byte order of the file being parsed.
+#[derive(Clone, Copy)]
+struct Endian {
+    big: bool,
+}
+
+impl Endian {
+    fn u16(self, bytes: &[u8], offset: usize) -> Result<u16, Error> {
+        let bytes: [u8; 2] = bytes
+            .get(offset..offset + 2)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| truncated("field"))?;
+        Ok(match self.big {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(self, bytes: &[u8], offset: usize) -> Result<u32, Error> {
+        let bytes: [u8; 4] = bytes
+            .get(offset..offset + 4)
+            .and_then(|b| b.try_into().ok())
+            .ok_or_else(|| truncated("field"))?;
+        Ok(match self.big {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+}
+
+pub fn is_pcapng(bytes: &[u8]) -> bool {
+    bytes.starts_with(&PCAPNG_SECTION_HEADER.to_le_bytes())
+}
+
+pub fn is_pcap(bytes: &[u8]) -> bool {
+    [0xa1b2c3d4u32, 0xa1b23c4d].iter().any(|magic| {
+        bytes.starts_with(&magic.to_le_bytes()) || bytes.starts_with(&magic.to_be_bytes())
+    })
+}
+
+struct Interface {
+    linktype: u32,
+    /// Seconds per timestamp tick.
+    resolution: f64,
+}
+
+/// Parses a pcapng file into the USB packets it contains.
+pub fn parse_pcapng(bytes: &[u8]) -> Result<Vec<Packet>, Error> {
+    let mut packets = Vec::new();
+    let mut interfaces: Vec<Interface> = Vec::new();
+    let mut endian = Endian { big: false };
+    let mut offset = 0;
+
+    while offset + 12 <= bytes.len() {
+        let block_type = endian.u32(bytes, offset)?;
+        if block_type == PCAPNG_SECTION_HEADER {
+            let magic = bytes
+                .get(offset + 8..offset + 12)
+                .ok_or_else(|| truncated("section"))?;
+            endian = Endian {
+                big: magic == PCAPNG_BYTE_ORDER_MAGIC.to_be_bytes(),
+            };
+            // Interface ids are scoped to their section.
+            interfaces.clear();
+        }
+
+        let len = endian.u32(bytes, offset + 4)? as usize;
+        let block = bytes
+            .get(offset..offset + len)
+            .filter(|_| len >= 12)
+            .ok_or_else(|| truncated("block"))?;
+        let body = &block[8..len - 4];
+
+        match block_type {
+            // Interface description
+            1 => interfaces.push(Interface {
+                linktype: endian.u16(body, 0)? as u32,
+                resolution: resolution(endian, body.get(8..).unwrap_or_default())?,
+            }),
+            // Enhanced packet
+            6 => {
+                let interface = interfaces
+                    .get(endian.u32(body, 0)? as usize)
+                    .ok_or_else(|| Error::ParseError("Capture: Unknown interface".to_string()))?;
+                let ticks = (endian.u32(body, 4)? as u64) << 32 | endian.u32(body, 8)? as u64;
+                let captured = endian.u32(body, 12)? as usize;
+                let data = body
+                    .get(20..20 + captured)
+                    .ok_or_else(|| truncated("packet"))?;
+
+                let timestamp = ticks as f64 * interface.resolution;
+                packets.extend(strip_link_header(interface.linktype, timestamp, data)?);
+            }
+            // Simple packet, always on the first interface and without a timestamp
+            3 => {
+                let interface = interfaces
+                    .first()
+                    .ok_or_else(|| Error::ParseError("Capture: Unknown interface".to_string()))?;
+                let data = body.get(4..).unwrap_or_default();
+                packets.extend(strip_link_header(interface.linktype, 0.0, data)?);
+            }
+            _ => {}
+        }
+
+        offset += len;
+    }
+
+    Ok(packets)
+}
+
+/// Reads the `if_tsresol` option of an interface description block.
+fn resolution(endian: Endian, mut options: &[u8]) -> Result<f64, Error> {
+    while options.len() >= 4 {
+        let code = endian.u16(options, 0)?;
+        let len = endian.u16(options, 2)? as usize;
+        if code == 0 {
+            break;
+        }
+        if code == 9 && len >= 1 {
+            let value = options[4];
+            return Ok(match value & 0x80 {
+                0 => 10f64.powi(-((value & 0x7f) as i32)),
+                _ => 2f64.powi(-((value & 0x7f) as i32)),
+            });
+        }
+        options = options
+            .get(4 + len.next_multiple_of(4)..)
+            .unwrap_or_default();
+    }
+
+    Ok(1e-6)
+}
+
+/// Parses a classic libpcap file into the USB packets it contains.
+pub fn parse_pcap(bytes: &[u8]) -> Result<Vec<Packet>, Error> {
+    let magic = bytes.get(0..4).ok_or_else(|| truncated("header"))?;
+    let big = magic == 0xa1b2c3d4u32.to_be_bytes() || magic == 0xa1b23c4du32.to_be_bytes();
+    let endian = Endian { big };
+    let nanos = endian.u32(bytes, 0)? == 0xa1b23c4d;
+    let linktype = endian.u32(bytes, 20)?;
+
+    let mut packets = Vec::new();
+    let mut offset = 24;
+    while offset + 16 <= bytes.len() {
+        let seconds = endian.u32(bytes, offset)? as f64;
+        let fraction = endian.u32(bytes, offset + 4)? as f64;
+        let captured = endian.u32(bytes, offset + 8)? as usize;
+        let data = bytes
+            .get(offset + 16..offset + 16 + captured)
+            .ok_or_else(|| truncated("packet"))?;
+
+        let timestamp = seconds + fraction * if nanos { 1e-9 } else { 1e-6 };
+        packets.extend(strip_link_header(linktype, timestamp, data)?);
+        offset += 16 + captured;
+    }
+
+    Ok(packets)
+}
+
+/// Removes the usbmon or USBPcap pseudo header, returning the payload with its direction.
+fn strip_link_header(linktype: u32, timestamp: f64, data: &[u8]) -> Result<Option<Packet>, Error> {
+    let little = Endian { big: false };
+
+    let (endpoint, payload) = match linktype {
+        LINKTYPE_USB_LINUX | LINKTYPE_USB_LINUX_MMAPPED => {
+            let header_len = if linktype == LINKTYPE_USB_LINUX {
+                48
+            } else {
+                64
+            };
+            let endpoint = *data.get(10).ok_or_else(|| truncated("usbmon header"))?;
+            (endpoint, data.get(header_len..).unwrap_or_default())
+        }
+        LINKTYPE_USBPCAP => {
+            let header_len = little.u16(data, 0)? as usize;
+            let endpoint = *data.get(21).ok_or_else(|| truncated("USBPcap header"))?;
+            (endpoint, data.get(header_len..).unwrap_or_default())
+        }
+        _ => return Ok(None),
+    };
+
+    if payload.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(Packet {
+        timestamp,
+        direction: match endpoint & 0x80 {
+            0 => Direction::Out,
+            _ => Direction::In,
+        },
+        payload: payload.to_vec(),
+    }))
+}
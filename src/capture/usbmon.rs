@@ -0,0 +1,54 @@
+use libatk_rs::prelude::*;
+
+use super::{Direction, Packet};
+
+/// Parses the text interface of usbmon (`/sys/kernel/debug/usb/usbmon/<bus>u`).
+///
+/// Each line looks like
+/// `ffff8800 1136279093 S Io:1:003:1 -115 17 = 08000000 0c080000 ...`,
+/// only lines carrying data (`=`) are kept.
+pub fn parse_text(text: &str) -> Result<Vec<Packet>, Error> {
+    let mut packets = Vec::new();
+
+    for (number, line) in text.lines().enumerate() {
+        let invalid = || {
+            Error::ParseError(format!(
+                "Capture: Invalid usbmon line {}: {}",
+                number + 1,
+                line
+            ))
+        };
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 4 {
+            continue;
+        }
+        let Some(data_start) = fields.iter().position(|field| *field == "=") else {
+            continue;
+        };
+
+        let timestamp: u64 = fields[1].parse().map_err(|_| invalid())?;
+        let direction = match fields[3].as_bytes().get(1) {
+            Some(b'i') => Direction::In,
+            Some(b'o') => Direction::Out,
+            _ => return Err(invalid()),
+        };
+
+        let hex: String = fields[data_start + 1..].concat();
+        let nibble = |digit: u8| char::from(digit).to_digit(16);
+        let payload = hex
+            .as_bytes()
+            .chunks_exact(2)
+            .map(|pair| Some((nibble(pair[0])? << 4 | nibble(pair[1])?) as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(invalid)?;
+
+        packets.push(Packet {
+            timestamp: timestamp as f64 * 1e-6,
+            direction,
+            payload,
+        });
+    }
+
+    Ok(packets)
+}
@@ -0,0 +1,187 @@
+use std::{ops::Range, path::PathBuf};
+
+use clap::Subcommand;
+use libatk_rs::prelude::*;
+
+use super::{parse_hex, Connection};
+use crate::eeprom::{diff::EepromDiff, ChunkPlan, EepromDump};
+
+#[derive(Subcommand)]
+pub enum EepromCommand {
+    /// Read the EEPROM into an annotated JSON dump and a flat binary image
+    Dump {
+        /// Path of the JSON dump, the image is written next to it with a `.bin` extension
+        output: PathBuf,
+
+        /// Hex address range START..END to read instead of every known address, repeatable
+        #[arg(long = "range", value_parser = parse_range)]
+        ranges: Vec<Range<u16>>,
+    },
+    /// Write a JSON dump back to the device byte for byte
+    Restore {
+        input: PathBuf,
+
+        /// Restore even if the dump was taken from a different model or firmware
+        #[arg(long)]
+        force: bool,
+
+        /// Only print what would be written
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Snapshot the EEPROM, wait while a setting is changed in the vendor software, then
+    /// print which bytes changed
+    Explore {
+        /// Also write the diff as JSON to this path
+        #[arg(long)]
+        output: Option<PathBuf>,
+
+        /// Hex address range START..END to watch instead of every known address, repeatable
+        #[arg(long = "range", value_parser = parse_range)]
+        ranges: Vec<Range<u16>>,
+    },
+    /// Compare two JSON dumps without touching the device
+    Diff {
+        before: PathBuf,
+        after: PathBuf,
+
+        /// Also write the diff as JSON to this path
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn parse_range(s: &str) -> Result<Range<u16>, Error> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| Error::ParseError(format!("Range: Expected START..END, got {}", s)))?;
+
+    let range = parse_hex(start)?..parse_hex(end)?;
+    if range.is_empty() {
+        return Err(Error::ParseError(format!("Range: {} is empty", s)));
+    }
+
+    Ok(range)
+}
+
+impl EepromCommand {
+    pub fn run(
+        self,
+        connection: &Connection,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            EepromCommand::Dump { output, ranges } => dump(connection, output, ranges),
+            EepromCommand::Restore {
+                input,
+                force,
+                dry_run,
+            } => restore(connection, input, force, dry_run),
+            EepromCommand::Explore { output, ranges } => explore(connection, output, ranges),
+            EepromCommand::Diff {
+                before,
+                after,
+                output,
+            } => diff(before, after, output),
+        }
+    }
+}
+
+fn plan(ranges: Vec<Range<u16>>) -> ChunkPlan {
+    let plan = if ranges.is_empty() {
+        ChunkPlan::known()
+    } else {
+        ChunkPlan::new(ranges)
+    };
+
+    for address in &plan.unreachable {
+        eprintln!("Skipping unreachable address {:#06x}", address);
+    }
+
+    plan
+}
+
+fn dump(
+    connection: &Connection,
+    output: PathBuf,
+    ranges: Vec<Range<u16>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let plan = plan(ranges);
+    let manager = connection.open()?;
+    let dump = EepromDump::capture(&manager, &plan)?;
+
+    std::fs::write(&output, dump.to_json()?)?;
+    std::fs::write(output.with_extension("bin"), dump.to_image()?)?;
+
+    println!("{}", dump.device);
+    println!("{}", dump.to_hex()?);
+
+    Ok(())
+}
+
+fn restore(
+    connection: &Connection,
+    input: PathBuf,
+    force: bool,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let dump = EepromDump::from_json(&std::fs::read_to_string(input)?)?;
+
+    if dry_run {
+        println!("{}", dump.device);
+        println!("{}", dump.to_hex()?);
+        return Ok(());
+    }
+
+    let manager = connection.open()?;
+    let written = dump.restore(&manager, force)?;
+    println!("Restored {} bytes", written);
+
+    manager
+        .load_profile()
+        .map_err(|e| format!("Restored configuration does not load: {}", e))?;
+
+    Ok(())
+}
+
+fn explore(
+    connection: &Connection,
+    output: Option<PathBuf>,
+    ranges: Vec<Range<u16>>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let plan = plan(ranges);
+    let manager = connection.open()?;
+
+    let before = EepromDump::capture(&manager, &plan)?;
+    println!(
+        "Captured {} bytes. Change a setting, then press Enter.",
+        before.bytes()?.len()
+    );
+    std::io::stdin().read_line(&mut String::new())?;
+    let after = EepromDump::capture(&manager, &plan)?;
+
+    report(&EepromDiff::between(&before, &after)?, output)
+}
+
+fn diff(
+    before: PathBuf,
+    after: PathBuf,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let before = EepromDump::from_json(&std::fs::read_to_string(before)?)?;
+    let after = EepromDump::from_json(&std::fs::read_to_string(after)?)?;
+
+    report(&EepromDiff::between(&before, &after)?, output)
+}
+
+fn report(
+    diff: &EepromDiff,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    println!("{}", diff);
+
+    if let Some(output) = output {
+        std::fs::write(output, diff.to_json()?)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,385 @@
+mod eeprom;
+mod profile;
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use clap::{Args, Parser, Subcommand};
+use libatk_rs::prelude::*;
+
+use crate::{
+    autoswitch::{self, Rules, Switcher},
+    capabilities::Registry,
+    capture, discovery,
+    introspection::{self, SettingValue},
+    library::{self, ProfileStore},
+    manager::{events::Event, MouseManager},
+    trace::{replay::Replay, TraceFormat, Tracer},
+};
+
+#[derive(Parser)]
+#[command(name = "atk-hub", version, about = "Configure ATK based mice")]
+pub struct Cli {
+    #[command(flatten)]
+    connection: Connection,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List HID interfaces that may be ATK mice, in the format expected by --device
+    List,
+    /// Print the configuration stored on the device
+    Show,
+    /// Print the capabilities detected for the device as JSON
+    Capabilities,
+    /// Print settings by dotted path: one setting such as perf.motion_sync, every setting
+    /// under a prefix such as led, or profile for all of them
+    Get {
+        path: String,
+
+        /// Print JSON, nested by the parts of each path
+        #[arg(long)]
+        json: bool,
+    },
+    /// Change settings given as path=value, e.g. led.mode=breathing dpi.3=1600, all
+    /// written in a single transaction
+    Set {
+        #[arg(required = true, value_name = "PATH=VALUE")]
+        assignments: Vec<String>,
+    },
+    /// Decode the ATK reports in a pcapng, pcap or usbmon text capture
+    Decode {
+        capture: PathBuf,
+
+        /// Print the reports as JSON instead of a transcript
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print device state changes as they happen
+    Watch {
+        /// Milliseconds between two polls of the device
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Edit the configuration in an interactive terminal UI
+    #[cfg(feature = "tui")]
+    Tui {
+        /// Milliseconds between two polls of the battery and online status
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Switch profiles while matching applications run, see rules.json in the profile
+    /// directory. Settings of the rule in effect stay on the mouse when interrupted
+    Autoswitch {
+        /// Rules file to use instead of rules.json in $XDG_CONFIG_HOME/atk-hub
+        #[arg(long)]
+        rules: Option<PathBuf>,
+
+        /// Milliseconds between two scans of the running processes
+        #[arg(long, default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Raw EEPROM access
+    #[command(subcommand)]
+    Eeprom(eeprom::EepromCommand),
+    /// Named profiles saved in $XDG_CONFIG_HOME/atk-hub
+    #[command(subcommand)]
+    Profile(profile::ProfileCommand),
+}
+
+/// How to reach the device, shared by every command.
+#[derive(Args)]
+pub struct Connection {
+    /// Device to open, as hex VID:PID:USAGE_PAGE:USAGE
+    #[arg(long, env = "ATK_DEVICE")]
+    device: Option<DeviceId>,
+
+    /// Answer commands from a recorded trace instead of a device
+    #[arg(long, conflicts_with = "device")]
+    replay: Option<PathBuf>,
+
+    /// Record every command and response to this file
+    #[arg(long, env = "ATK_TRACE")]
+    trace: Option<PathBuf>,
+
+    /// Format of the trace, `json` (one object per line) or `binary`
+    #[arg(long, default_value = "json", value_parser = clap::value_parser!(TraceFormat))]
+    trace_format: TraceFormat,
+}
+
+impl Connection {
+    /// Opens the device without reading its configuration, see
+    /// [`MouseManager::new_without_profile`].
+    ///
+    /// Asks the user to wake the mouse up whenever a command finds it asleep.
+    pub fn open(&self) -> Result<MouseManager, Box<dyn std::error::Error + Send + Sync>> {
+        let manager = self.open_quietly()?;
+
+        let events = manager.subscribe();
+        std::thread::spawn(move || {
+            for event in events {
+                if event == Event::Dormant {
+                    eprintln!("Mouse is offline. Move the mouse to wake it up.");
+                }
+            }
+        });
+
+        Ok(manager)
+    }
+
+    /// Like [`open`](Self::open), for callers that report [`Event::Dormant`] themselves.
+    pub fn open_quietly(&self) -> Result<MouseManager, Box<dyn std::error::Error + Send + Sync>> {
+        let manager = match (&self.replay, &self.device) {
+            (Some(path), _) => MouseManager::new_without_profile(Replay::open(path)?),
+            (None, Some(device)) => {
+                let manager = MouseManager::new_without_profile(device.open()?);
+                detect_capabilities(&manager);
+                manager
+            }
+            (None, None) => {
+                return Err(Error::ParseError(
+                    "No device given: pass --device or set ATK_DEVICE".to_string(),
+                )
+                .into())
+            }
+        };
+
+        match &self.trace {
+            Some(path) => {
+                let tracer = Tracer::create(path, self.trace_format)?.on_failure(|e| {
+                    eprintln!(
+                        "Warning: writing the trace failed, records are dropped: {}",
+                        e
+                    )
+                });
+                Ok(manager.with_tracer(tracer))
+            }
+            None => Ok(manager),
+        }
+    }
+
+    /// Whether a device or a trace to replay was given.
+    pub fn is_given(&self) -> bool {
+        self.device.is_some() || self.replay.is_some()
+    }
+
+    /// Serial number of the HID interface given with --device, `None` for a replay.
+    pub fn serial_number(&self) -> Option<String> {
+        let device = self.device.filter(|_| self.replay.is_none())?;
+        discovery::serial_number(
+            device.vendor_id,
+            device.product_id,
+            device.usage_page,
+            device.usage,
+        )
+        .ok()
+        .flatten()
+    }
+}
+
+/// Looks the mouse up in the capability registry. Replays keep the generic capabilities
+/// since they only answer the commands that were recorded, and detection runs before
+/// the tracer is attached so that traces stay replayable.
+fn detect_capabilities(manager: &MouseManager) {
+    match Registry::load().and_then(|registry| registry.detect(manager)) {
+        Ok(capabilities) => manager.set_capabilities(capabilities),
+        Err(e) => eprintln!("Warning: capability detection failed: {}", e),
+    }
+}
+
+/// The HID identifiers used to open a device.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceId {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usage_page: u16,
+    pub usage: u16,
+}
+
+impl std::str::FromStr for DeviceId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ids = s
+            .split(':')
+            .map(parse_hex)
+            .collect::<Result<Vec<u16>, Error>>()?;
+
+        match ids[..] {
+            [vendor_id, product_id, usage_page, usage] => Ok(DeviceId {
+                vendor_id,
+                product_id,
+                usage_page,
+                usage,
+            }),
+            _ => Err(Error::ParseError(format!(
+                "Device: Expected VID:PID:USAGE_PAGE:USAGE, got {}",
+                s
+            ))),
+        }
+    }
+}
+
+impl DeviceId {
+    pub fn open(&self) -> Result<Device, Error> {
+        Device::new(self.vendor_id, self.product_id, self.usage_page, self.usage)
+    }
+}
+
+/// Parses a hex number with an optional `0x` prefix.
+pub fn parse_hex(s: &str) -> Result<u16, Error> {
+    let digits = s.trim();
+    let digits = digits
+        .strip_prefix("0x")
+        .or_else(|| digits.strip_prefix("0X"))
+        .unwrap_or(digits);
+
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| Error::ParseError(format!("Invalid hex number: {}", s)))
+}
+
+pub fn run() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::List => {
+            for device in discovery::discover()? {
+                println!("{}", device);
+            }
+            Ok(())
+        }
+        Commands::Show => {
+            let manager = cli.connection.open()?;
+            manager.load_profile()?;
+            println!("{:#?}", manager.profile());
+            Ok(())
+        }
+        Commands::Capabilities => {
+            let manager = cli.connection.open()?;
+            println!("{}", serde_json::to_string_pretty(&manager.capabilities())?);
+            Ok(())
+        }
+        Commands::Get { path, json } => get(&cli.connection, &path, json),
+        Commands::Set { assignments } => set(&cli.connection, &assignments),
+        Commands::Decode { capture, json } => {
+            let reports = capture::read(capture)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&reports)?);
+            } else {
+                for report in &reports {
+                    println!("{}", report);
+                }
+            }
+            Ok(())
+        }
+        Commands::Watch { interval } => {
+            let manager = Arc::new(cli.connection.open_quietly()?);
+            let events = manager.subscribe();
+            let _watcher = manager.watch(Duration::from_millis(interval));
+
+            for event in events {
+                println!("{}", event);
+            }
+            Ok(())
+        }
+        #[cfg(feature = "tui")]
+        Commands::Tui { interval } => crate::tui::run(
+            cli.connection.open_quietly()?,
+            Duration::from_millis(interval),
+        ),
+        Commands::Autoswitch { rules, interval } => {
+            autoswitch(&cli.connection, rules, Duration::from_millis(interval))
+        }
+        Commands::Eeprom(command) => command.run(&cli.connection),
+        Commands::Profile(command) => command.run(&cli.connection),
+    }
+}
+
+fn get(
+    connection: &Connection,
+    path: &str,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let manager = connection.open()?;
+    manager.load_profile()?;
+    let settings = introspection::resolve(&manager.capabilities(), path)?;
+
+    let profile = manager.profile();
+    let values: Vec<SettingValue> = settings
+        .into_iter()
+        .map(|setting| SettingValue {
+            value: setting.get(&profile),
+            setting,
+        })
+        .collect();
+
+    match (values.as_slice(), json) {
+        ([single], _) if single.setting.id == path => match json {
+            true => println!("{}", serde_json::to_string(&single.value)?),
+            false => println!("{}", single.value),
+        },
+        (_, true) => println!(
+            "{}",
+            serde_json::to_string_pretty(&introspection::to_json(&values))?
+        ),
+        (_, false) => {
+            for SettingValue { setting, value } in &values {
+                println!("{}={}", setting.id, value);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn set(
+    connection: &Connection,
+    assignments: &[String],
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let manager = connection.open()?;
+    manager.load_profile()?;
+
+    // Parse everything first so a typo in the last assignment writes nothing.
+    let capabilities = manager.capabilities();
+    let values = assignments
+        .iter()
+        .map(|assignment| introspection::parse_assignment(&capabilities, assignment))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for block in introspection::set_all(&manager, &values)? {
+        println!("Wrote {}", block);
+    }
+
+    Ok(())
+}
+
+fn autoswitch(
+    connection: &Connection,
+    rules: Option<PathBuf>,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let rules = match rules {
+        Some(path) => path,
+        None => library::config_dir()?.join("rules.json"),
+    };
+    let rules = Rules::from_json(
+        &std::fs::read_to_string(&rules).map_err(|e| format!("{}: {}", rules.display(), e))?,
+    )?;
+    let mut switcher = Switcher::new(rules.rules, ProfileStore::open_default()?.load()?)?;
+
+    let manager = connection.open()?;
+    manager.load_profile()?;
+
+    loop {
+        let processes = autoswitch::processes(std::path::Path::new("/proc"))?;
+        match switcher.update(&manager, &processes) {
+            Ok(Some(switch)) => println!("{}", switch),
+            Ok(None) => {}
+            // Retried on the next scan, e.g. after the mouse is plugged back in.
+            Err(e) => eprintln!("Switching failed: {}", e),
+        }
+        std::thread::sleep(interval);
+    }
+}
@@ -0,0 +1,238 @@
+use std::path::{Path, PathBuf};
+
+use clap::Subcommand;
+use libatk_rs::prelude::*;
+
+use super::Connection;
+use crate::{
+    library::{schema, DeviceKey, Library, ProfileSnapshot, ProfileStore, SavedProfile},
+    manager::MouseManager,
+};
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    /// Save the configuration of the device under a name
+    Save {
+        name: String,
+
+        /// Replace an existing profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// List saved profiles and the devices they are the default of
+    List,
+    /// Print a saved profile as JSON
+    Show { name: String },
+    /// Delete a saved profile
+    Delete { name: String },
+    /// Rename a saved profile
+    Rename { from: String, to: String },
+    /// Print, set or clear the default profile of the device
+    Default {
+        name: Option<String>,
+
+        /// Unset the default instead
+        #[arg(long, conflicts_with = "name")]
+        clear: bool,
+
+        /// Apply to every mouse of this model rather than only this one
+        #[arg(long)]
+        model: bool,
+    },
+    /// Write a saved profile to the device, the default profile if no name is given
+    Activate { name: Option<String> },
+    /// Print a saved profile, or the configuration of the device, as a one line share code
+    Share { name: Option<String> },
+    /// Save a profile from a share code
+    Import {
+        name: String,
+        code: String,
+
+        /// Replace an existing profile with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Print the JSON Schema of profiles, as printed by show
+    Schema,
+    /// Check a profile or a whole profile library against the schema and, if a device is
+    /// given, against what the device supports
+    Validate { file: PathBuf },
+}
+
+impl ProfileCommand {
+    pub fn run(
+        self,
+        connection: &Connection,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Neither needs the library, which may not even exist where files are linted.
+        match &self {
+            ProfileCommand::Schema => {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&schema::profile_schema())?
+                );
+                return Ok(());
+            }
+            ProfileCommand::Validate { file } => return validate(connection, file),
+            _ => {}
+        }
+
+        let store = ProfileStore::open_default()?;
+        let mut library = store.load()?;
+
+        match self {
+            ProfileCommand::Save { name, force } => {
+                let (manager, device) = open(connection)?;
+                let settings = ProfileSnapshot::from(&*manager.profile());
+                library.insert(&name, SavedProfile::new(settings, Some(device)), force)?;
+                store.save(&library)?;
+                println!("Saved {} to {}", name, store.path().display());
+            }
+            ProfileCommand::List => list(&library),
+            ProfileCommand::Show { name } => {
+                println!("{}", serde_json::to_string_pretty(library.get(&name)?)?);
+            }
+            ProfileCommand::Delete { name } => {
+                library.remove(&name)?;
+                store.save(&library)?;
+            }
+            ProfileCommand::Rename { from, to } => {
+                library.rename(&from, &to)?;
+                store.save(&library)?;
+            }
+            ProfileCommand::Default { name, clear, model } => {
+                let (_, device) = open(connection)?;
+                let device = if model { device.model() } else { device };
+
+                match name {
+                    Some(name) => {
+                        library.set_default(&device, &name)?;
+                        store.save(&library)?;
+                    }
+                    None if clear => {
+                        if library.clear_default(&device).is_none() {
+                            println!("{} has no default profile", device);
+                        }
+                        store.save(&library)?;
+                    }
+                    None => match library.default_for(&device) {
+                        Some(name) => println!("{}", name),
+                        None => println!("{} has no default profile", device),
+                    },
+                }
+            }
+            ProfileCommand::Activate { name } => activate(connection, &library, name)?,
+            ProfileCommand::Share { name } => {
+                let settings = match name {
+                    Some(name) => library.get(&name)?.settings.clone(),
+                    None => ProfileSnapshot::from(&*open(connection)?.0.profile()),
+                };
+                println!("{}", settings.to_share_code()?);
+            }
+            ProfileCommand::Import { name, code, force } => {
+                let settings = ProfileSnapshot::from_share_code(&code)?;
+                library.insert(&name, SavedProfile::new(settings, None), force)?;
+                store.save(&library)?;
+                println!("Saved {} to {}", name, store.path().display());
+            }
+            ProfileCommand::Schema | ProfileCommand::Validate { .. } => unreachable!(),
+        }
+
+        Ok(())
+    }
+}
+
+/// Opens the device with its profile loaded and identifies it.
+fn open(
+    connection: &Connection,
+) -> Result<(MouseManager, DeviceKey), Box<dyn std::error::Error + Send + Sync>> {
+    let manager = connection.open()?;
+    manager.load_profile()?;
+    let device = DeviceKey::query(&manager, connection.serial_number())?;
+
+    Ok((manager, device))
+}
+
+fn validate(
+    connection: &Connection,
+    file: &Path,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let json = std::fs::read_to_string(file)?;
+    let device = match connection.is_given() {
+        true => {
+            let manager = connection.open()?;
+            manager.load_profile()?;
+            let current = ProfileSnapshot::from(&*manager.profile());
+            Some((manager.capabilities(), current))
+        }
+        false => None,
+    };
+
+    let violations = schema::validate(
+        &json,
+        device
+            .as_ref()
+            .map(|(capabilities, current)| (capabilities, current)),
+    )
+    .map_err(|e| format!("{}: {}", file.display(), e))?;
+
+    for violation in &violations {
+        println!("{}: {}", file.display(), violation);
+    }
+
+    match violations.len() {
+        0 => Ok(()),
+        count => Err(format!("{} problem(s) in {}", count, file.display()).into()),
+    }
+}
+
+fn list(library: &Library) {
+    for name in library.profiles.keys() {
+        let defaults = library.defaults_of(name);
+        if defaults.is_empty() {
+            println!("{}", name);
+        } else {
+            println!("{} (default for {})", name, defaults.join(", "));
+        }
+    }
+}
+
+fn activate(
+    connection: &Connection,
+    library: &Library,
+    name: Option<String>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (manager, device) = open(connection)?;
+    let name = match name {
+        Some(name) => name,
+        None => library
+            .default_for(&device)
+            .ok_or_else(|| {
+                Error::ParseError(format!(
+                    "Profile: {} has no default profile, pass a name",
+                    device
+                ))
+            })?
+            .to_string(),
+    };
+
+    let profile = library.get(&name)?;
+    if let Some(saved_from) = &profile.device {
+        if !saved_from.same_model(&device) {
+            eprintln!(
+                "Warning: {} was saved from a different model ({})",
+                name, saved_from
+            );
+        }
+    }
+
+    let blocks = profile.settings.apply(&manager)?;
+    if blocks.is_empty() {
+        println!("{} is already active", name);
+    }
+    for block in blocks {
+        println!("Wrote {}", block);
+    }
+
+    Ok(())
+}
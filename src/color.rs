@@ -0,0 +1,784 @@
+use libatk_rs::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color {
+    red: u8,
+    green: u8,
+    blue: u8,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color {
+            red: 0xFF,
+            green: 0xFF,
+            blue: 0xFF,
+        }
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+impl TryFrom<&[u8]> for Color {
+    type Error = Error;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        if data.len() != 4 {
+            return Err(Error::ParseError("Color: Invalid data length".to_string()));
+        }
+
+        let checksum = 0x55u8
+            .wrapping_sub(data[0])
+            .wrapping_sub(data[1])
+            .wrapping_sub(data[2]);
+
+        if checksum != data[3] {
+            return Err(Error::ParseError("Color: Invalid checksum".to_string()));
+        }
+
+        Ok(Self {
+            red: data[0],
+            green: data[1],
+            blue: data[2],
+        })
+    }
+}
+
+impl From<Color> for [u8; 4] {
+    fn from(color: Color) -> Self {
+        let checksum = 0x55u8
+            .wrapping_sub(color.red)
+            .wrapping_sub(color.green)
+            .wrapping_sub(color.blue);
+
+        [color.red, color.green, color.blue, checksum]
+    }
+}
+
+impl From<u32> for Color {
+    /// Builds a colour from a packed `0xRRGGBB` value, ignoring the top byte.
+    fn from(value: u32) -> Self {
+        let [_, red, green, blue] = value.to_be_bytes();
+        Color { red, green, blue }
+    }
+}
+
+impl From<Color> for u32 {
+    fn from(color: Color) -> Self {
+        u32::from_be_bytes([0, color.red, color.green, color.blue])
+    }
+}
+
+impl From<[u8; 3]> for Color {
+    fn from([red, green, blue]: [u8; 3]) -> Self {
+        Color { red, green, blue }
+    }
+}
+
+impl From<Color> for [u8; 3] {
+    fn from(color: Color) -> Self {
+        [color.red, color.green, color.blue]
+    }
+}
+
+/// Parses a colour from any of the following notations:
+///
+/// * `#rrggbb`, `rrggbb`, `#rgb` and `rgb` hex
+/// * CSS/X11 colour names such as `orange` or `Dark Slate Gray`
+/// * `rgb(255, 136, 0)`, components may also be percentages
+/// * `hsv(30, 100%, 100%)` and `hsl(30, 100%, 50%)`, hue in degrees
+impl std::str::FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let input = s.trim().to_ascii_lowercase();
+
+        if let Some(hex) = input.strip_prefix('#') {
+            return parse_hex(hex).ok_or_else(|| invalid(s));
+        }
+
+        if let Some((function, args)) = input
+            .strip_suffix(')')
+            .and_then(|rest| rest.split_once('('))
+        {
+            return parse_function(function.trim(), args).ok_or_else(|| invalid(s));
+        }
+
+        if let Some(color) = Color::from_name(&input) {
+            return Ok(color);
+        }
+
+        parse_hex(&input).ok_or_else(|| invalid(s))
+    }
+}
+
+fn invalid(input: &str) -> Error {
+    Error::ParseError(format!("Color: Invalid color: {}", input))
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match hex.len() {
+        3 => {
+            let nibble = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).map(|v| v * 0x11);
+            Some(Color::new(
+                nibble(0).ok()?,
+                nibble(1).ok()?,
+                nibble(2).ok()?,
+            ))
+        }
+        6 => Some(Color::from(u32::from_str_radix(hex, 16).ok()?)),
+        _ => None,
+    }
+}
+
+fn parse_function(function: &str, args: &str) -> Option<Color> {
+    let args: Vec<&str> = args
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    let [a, b, c] = args[..] else {
+        return None;
+    };
+
+    match function {
+        "rgb" => Some(Color::new(
+            parse_channel(a)?,
+            parse_channel(b)?,
+            parse_channel(c)?,
+        )),
+        "hsv" => Some(Color::from(Hsv::new(
+            parse_hue(a)?,
+            parse_fraction(b)?,
+            parse_fraction(c)?,
+        ))),
+        "hsl" => Some(Color::from(Hsl::new(
+            parse_hue(a)?,
+            parse_fraction(b)?,
+            parse_fraction(c)?,
+        ))),
+        _ => None,
+    }
+}
+
+/// A channel is either a plain `0..=255` value or a percentage.
+fn parse_channel(value: &str) -> Option<u8> {
+    match value.strip_suffix('%') {
+        Some(percent) => {
+            let percent: f32 = percent.parse().ok()?;
+            (0.0..=100.0)
+                .contains(&percent)
+                .then(|| (percent * 2.55).round() as u8)
+        }
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_hue(value: &str) -> Option<f32> {
+    let hue: f32 = value.strip_suffix("deg").unwrap_or(value).parse().ok()?;
+    hue.is_finite().then_some(hue)
+}
+
+/// Saturation, value and lightness are percentages, the `%` sign is optional.
+fn parse_fraction(value: &str) -> Option<f32> {
+    let percent: f32 = value.strip_suffix('%').unwrap_or(value).parse().ok()?;
+    (0.0..=100.0).contains(&percent).then_some(percent / 100.0)
+}
+
+impl Color {
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Color { red, green, blue }
+    }
+
+    pub fn red(&self) -> u8 {
+        self.red
+    }
+
+    pub fn green(&self) -> u8 {
+        self.green
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.blue
+    }
+
+    /// Looks up a CSS/X11 colour name. Case, spaces, dashes and underscores are ignored
+    /// so `DarkSlateGray`, `dark slate gray` and `dark-slate-gray` are all accepted.
+    pub fn from_name(name: &str) -> Option<Color> {
+        let name: String = name
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+
+        NAMED_COLORS
+            .iter()
+            .find(|(candidate, _)| *candidate == name)
+            .map(|(_, color)| *color)
+    }
+
+    /// Returns the first colour name matching this colour exactly, if any.
+    pub fn name(&self) -> Option<&'static str> {
+        NAMED_COLORS
+            .iter()
+            .find(|(_, color)| color == self)
+            .map(|(name, _)| *name)
+    }
+
+    pub fn to_hsv(self) -> Hsv {
+        Hsv::from(self)
+    }
+
+    pub fn to_hsl(self) -> Hsl {
+        Hsl::from(self)
+    }
+
+    /// Generates `count` fully saturated colours with evenly spaced hues, starting at red.
+    pub fn distinct(count: usize) -> Vec<Color> {
+        (0..count)
+            .map(|i| Color::from(Hsv::new(360.0 * i as f32 / count as f32, 1.0, 1.0)))
+            .collect()
+    }
+
+    pub fn corrected(self, correction: ColorCorrection) -> Color {
+        correction.apply(self)
+    }
+}
+
+/// Hue in degrees `[0, 360)`, saturation and value in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsv {
+    hue: f32,
+    saturation: f32,
+    value: f32,
+}
+
+impl Hsv {
+    pub fn new(hue: f32, saturation: f32, value: f32) -> Self {
+        Hsv {
+            hue: hue.rem_euclid(360.0),
+            saturation: saturation.clamp(0.0, 1.0),
+            value: value.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn hue(&self) -> f32 {
+        self.hue
+    }
+
+    pub fn saturation(&self) -> f32 {
+        self.saturation
+    }
+
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+}
+
+impl std::fmt::Display for Hsv {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hsv({:.0}, {:.0}%, {:.0}%)",
+            self.hue,
+            self.saturation * 100.0,
+            self.value * 100.0
+        )
+    }
+}
+
+impl From<Color> for Hsv {
+    fn from(color: Color) -> Self {
+        let (hue, max, min) = hue_max_min(color);
+        let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+
+        Hsv::new(hue, saturation, max)
+    }
+}
+
+impl From<Hsv> for Color {
+    fn from(hsv: Hsv) -> Self {
+        let chroma = hsv.value * hsv.saturation;
+        from_hue_chroma(hsv.hue, chroma, hsv.value - chroma)
+    }
+}
+
+/// Hue in degrees `[0, 360)`, saturation and lightness in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hsl {
+    hue: f32,
+    saturation: f32,
+    lightness: f32,
+}
+
+impl Hsl {
+    pub fn new(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Hsl {
+            hue: hue.rem_euclid(360.0),
+            saturation: saturation.clamp(0.0, 1.0),
+            lightness: lightness.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn hue(&self) -> f32 {
+        self.hue
+    }
+
+    pub fn saturation(&self) -> f32 {
+        self.saturation
+    }
+
+    pub fn lightness(&self) -> f32 {
+        self.lightness
+    }
+}
+
+impl std::fmt::Display for Hsl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "hsl({:.0}, {:.0}%, {:.0}%)",
+            self.hue,
+            self.saturation * 100.0,
+            self.lightness * 100.0
+        )
+    }
+}
+
+impl From<Color> for Hsl {
+    fn from(color: Color) -> Self {
+        let (hue, max, min) = hue_max_min(color);
+        let lightness = (max + min) / 2.0;
+        let saturation = if max == min {
+            0.0
+        } else {
+            (max - min) / (1.0 - (2.0 * lightness - 1.0).abs())
+        };
+
+        Hsl::new(hue, saturation, lightness)
+    }
+}
+
+impl From<Hsl> for Color {
+    fn from(hsl: Hsl) -> Self {
+        let chroma = (1.0 - (2.0 * hsl.lightness - 1.0).abs()) * hsl.saturation;
+        from_hue_chroma(hsl.hue, chroma, hsl.lightness - chroma / 2.0)
+    }
+}
+
+/// Returns the hue in degrees along with the largest and smallest normalized channel.
+fn hue_max_min(color: Color) -> (f32, f32, f32) {
+    let [r, g, b] = <[u8; 3]>::from(color).map(|c| c as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (hue, max, min)
+}
+
+fn from_hue_chroma(hue: f32, chroma: f32, offset: f32) -> Color {
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector.rem_euclid(2.0) - 1.0).abs());
+
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let channel = |c: f32| ((c + offset) * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color::new(channel(r), channel(g), channel(b))
+}
+
+/// Compensates for the DPI LED rendering colours differently from a monitor.
+///
+/// Each channel is normalized, raised to `gamma` and scaled by `brightness` before being
+/// written to the device. The identity correction (`gamma = 1`, `brightness = 1`) is the default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCorrection {
+    gamma: f32,
+    brightness: f32,
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        ColorCorrection {
+            gamma: 1.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl ColorCorrection {
+    pub fn new(gamma: f32, brightness: f32) -> Result<Self, Error> {
+        if !(gamma.is_finite() && gamma > 0.0) {
+            return Err(Error::ParseError(format!(
+                "ColorCorrection: Gamma must be positive: {}",
+                gamma
+            )));
+        }
+
+        if !(0.0..=1.0).contains(&brightness) {
+            return Err(Error::ParseError(format!(
+                "ColorCorrection: Brightness must be within 0..=1: {}",
+                brightness
+            )));
+        }
+
+        Ok(ColorCorrection { gamma, brightness })
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    pub fn brightness(&self) -> f32 {
+        self.brightness
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        let channel = |c: u8| {
+            let normalized = c as f32 / 255.0;
+            (normalized.powf(self.gamma) * self.brightness * 255.0)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+
+        Color::new(
+            channel(color.red),
+            channel(color.green),
+            channel(color.blue),
+        )
+    }
+}
+
+/// CSS colour keywords. Apart from a handful of greys and greens they match the X11 names.
+static NAMED_COLORS: &[(&str, Color)] = &[
+    ("aliceblue", Color::new(0xf0, 0xf8, 0xff)),
+    ("antiquewhite", Color::new(0xfa, 0xeb, 0xd7)),
+    ("aqua", Color::new(0x00, 0xff, 0xff)),
+    ("aquamarine", Color::new(0x7f, 0xff, 0xd4)),
+    ("azure", Color::new(0xf0, 0xff, 0xff)),
+    ("beige", Color::new(0xf5, 0xf5, 0xdc)),
+    ("bisque", Color::new(0xff, 0xe4, 0xc4)),
+    ("black", Color::new(0x00, 0x00, 0x00)),
+    ("blanchedalmond", Color::new(0xff, 0xeb, 0xcd)),
+    ("blue", Color::new(0x00, 0x00, 0xff)),
+    ("blueviolet", Color::new(0x8a, 0x2b, 0xe2)),
+    ("brown", Color::new(0xa5, 0x2a, 0x2a)),
+    ("burlywood", Color::new(0xde, 0xb8, 0x87)),
+    ("cadetblue", Color::new(0x5f, 0x9e, 0xa0)),
+    ("chartreuse", Color::new(0x7f, 0xff, 0x00)),
+    ("chocolate", Color::new(0xd2, 0x69, 0x1e)),
+    ("coral", Color::new(0xff, 0x7f, 0x50)),
+    ("cornflowerblue", Color::new(0x64, 0x95, 0xed)),
+    ("cornsilk", Color::new(0xff, 0xf8, 0xdc)),
+    ("crimson", Color::new(0xdc, 0x14, 0x3c)),
+    ("cyan", Color::new(0x00, 0xff, 0xff)),
+    ("darkblue", Color::new(0x00, 0x00, 0x8b)),
+    ("darkcyan", Color::new(0x00, 0x8b, 0x8b)),
+    ("darkgoldenrod", Color::new(0xb8, 0x86, 0x0b)),
+    ("darkgray", Color::new(0xa9, 0xa9, 0xa9)),
+    ("darkgreen", Color::new(0x00, 0x64, 0x00)),
+    ("darkgrey", Color::new(0xa9, 0xa9, 0xa9)),
+    ("darkkhaki", Color::new(0xbd, 0xb7, 0x6b)),
+    ("darkmagenta", Color::new(0x8b, 0x00, 0x8b)),
+    ("darkolivegreen", Color::new(0x55, 0x6b, 0x2f)),
+    ("darkorange", Color::new(0xff, 0x8c, 0x00)),
+    ("darkorchid", Color::new(0x99, 0x32, 0xcc)),
+    ("darkred", Color::new(0x8b, 0x00, 0x00)),
+    ("darksalmon", Color::new(0xe9, 0x96, 0x7a)),
+    ("darkseagreen", Color::new(0x8f, 0xbc, 0x8f)),
+    ("darkslateblue", Color::new(0x48, 0x3d, 0x8b)),
+    ("darkslategray", Color::new(0x2f, 0x4f, 0x4f)),
+    ("darkslategrey", Color::new(0x2f, 0x4f, 0x4f)),
+    ("darkturquoise", Color::new(0x00, 0xce, 0xd1)),
+    ("darkviolet", Color::new(0x94, 0x00, 0xd3)),
+    ("deeppink", Color::new(0xff, 0x14, 0x93)),
+    ("deepskyblue", Color::new(0x00, 0xbf, 0xff)),
+    ("dimgray", Color::new(0x69, 0x69, 0x69)),
+    ("dimgrey", Color::new(0x69, 0x69, 0x69)),
+    ("dodgerblue", Color::new(0x1e, 0x90, 0xff)),
+    ("firebrick", Color::new(0xb2, 0x22, 0x22)),
+    ("floralwhite", Color::new(0xff, 0xfa, 0xf0)),
+    ("forestgreen", Color::new(0x22, 0x8b, 0x22)),
+    ("fuchsia", Color::new(0xff, 0x00, 0xff)),
+    ("gainsboro", Color::new(0xdc, 0xdc, 0xdc)),
+    ("ghostwhite", Color::new(0xf8, 0xf8, 0xff)),
+    ("gold", Color::new(0xff, 0xd7, 0x00)),
+    ("goldenrod", Color::new(0xda, 0xa5, 0x20)),
+    ("gray", Color::new(0x80, 0x80, 0x80)),
+    ("green", Color::new(0x00, 0x80, 0x00)),
+    ("greenyellow", Color::new(0xad, 0xff, 0x2f)),
+    ("grey", Color::new(0x80, 0x80, 0x80)),
+    ("honeydew", Color::new(0xf0, 0xff, 0xf0)),
+    ("hotpink", Color::new(0xff, 0x69, 0xb4)),
+    ("indianred", Color::new(0xcd, 0x5c, 0x5c)),
+    ("indigo", Color::new(0x4b, 0x00, 0x82)),
+    ("ivory", Color::new(0xff, 0xff, 0xf0)),
+    ("khaki", Color::new(0xf0, 0xe6, 0x8c)),
+    ("lavender", Color::new(0xe6, 0xe6, 0xfa)),
+    ("lavenderblush", Color::new(0xff, 0xf0, 0xf5)),
+    ("lawngreen", Color::new(0x7c, 0xfc, 0x00)),
+    ("lemonchiffon", Color::new(0xff, 0xfa, 0xcd)),
+    ("lightblue", Color::new(0xad, 0xd8, 0xe6)),
+    ("lightcoral", Color::new(0xf0, 0x80, 0x80)),
+    ("lightcyan", Color::new(0xe0, 0xff, 0xff)),
+    ("lightgoldenrodyellow", Color::new(0xfa, 0xfa, 0xd2)),
+    ("lightgray", Color::new(0xd3, 0xd3, 0xd3)),
+    ("lightgreen", Color::new(0x90, 0xee, 0x90)),
+    ("lightgrey", Color::new(0xd3, 0xd3, 0xd3)),
+    ("lightpink", Color::new(0xff, 0xb6, 0xc1)),
+    ("lightsalmon", Color::new(0xff, 0xa0, 0x7a)),
+    ("lightseagreen", Color::new(0x20, 0xb2, 0xaa)),
+    ("lightskyblue", Color::new(0x87, 0xce, 0xfa)),
+    ("lightslategray", Color::new(0x77, 0x88, 0x99)),
+    ("lightslategrey", Color::new(0x77, 0x88, 0x99)),
+    ("lightsteelblue", Color::new(0xb0, 0xc4, 0xde)),
+    ("lightyellow", Color::new(0xff, 0xff, 0xe0)),
+    ("lime", Color::new(0x00, 0xff, 0x00)),
+    ("limegreen", Color::new(0x32, 0xcd, 0x32)),
+    ("linen", Color::new(0xfa, 0xf0, 0xe6)),
+    ("magenta", Color::new(0xff, 0x00, 0xff)),
+    ("maroon", Color::new(0x80, 0x00, 0x00)),
+    ("mediumaquamarine", Color::new(0x66, 0xcd, 0xaa)),
+    ("mediumblue", Color::new(0x00, 0x00, 0xcd)),
+    ("mediumorchid", Color::new(0xba, 0x55, 0xd3)),
+    ("mediumpurple", Color::new(0x93, 0x70, 0xdb)),
+    ("mediumseagreen", Color::new(0x3c, 0xb3, 0x71)),
+    ("mediumslateblue", Color::new(0x7b, 0x68, 0xee)),
+    ("mediumspringgreen", Color::new(0x00, 0xfa, 0x9a)),
+    ("mediumturquoise", Color::new(0x48, 0xd1, 0xcc)),
+    ("mediumvioletred", Color::new(0xc7, 0x15, 0x85)),
+    ("midnightblue", Color::new(0x19, 0x19, 0x70)),
+    ("mintcream", Color::new(0xf5, 0xff, 0xfa)),
+    ("mistyrose", Color::new(0xff, 0xe4, 0xe1)),
+    ("moccasin", Color::new(0xff, 0xe4, 0xb5)),
+    ("navajowhite", Color::new(0xff, 0xde, 0xad)),
+    ("navy", Color::new(0x00, 0x00, 0x80)),
+    ("oldlace", Color::new(0xfd, 0xf5, 0xe6)),
+    ("olive", Color::new(0x80, 0x80, 0x00)),
+    ("olivedrab", Color::new(0x6b, 0x8e, 0x23)),
+    ("orange", Color::new(0xff, 0xa5, 0x00)),
+    ("orangered", Color::new(0xff, 0x45, 0x00)),
+    ("orchid", Color::new(0xda, 0x70, 0xd6)),
+    ("palegoldenrod", Color::new(0xee, 0xe8, 0xaa)),
+    ("palegreen", Color::new(0x98, 0xfb, 0x98)),
+    ("paleturquoise", Color::new(0xaf, 0xee, 0xee)),
+    ("palevioletred", Color::new(0xdb, 0x70, 0x93)),
+    ("papayawhip", Color::new(0xff, 0xef, 0xd5)),
+    ("peachpuff", Color::new(0xff, 0xda, 0xb9)),
+    ("peru", Color::new(0xcd, 0x85, 0x3f)),
+    ("pink", Color::new(0xff, 0xc0, 0xcb)),
+    ("plum", Color::new(0xdd, 0xa0, 0xdd)),
+    ("powderblue", Color::new(0xb0, 0xe0, 0xe6)),
+    ("purple", Color::new(0x80, 0x00, 0x80)),
+    ("rebeccapurple", Color::new(0x66, 0x33, 0x99)),
+    ("red", Color::new(0xff, 0x00, 0x00)),
+    ("rosybrown", Color::new(0xbc, 0x8f, 0x8f)),
+    ("royalblue", Color::new(0x41, 0x69, 0xe1)),
+    ("saddlebrown", Color::new(0x8b, 0x45, 0x13)),
+    ("salmon", Color::new(0xfa, 0x80, 0x72)),
+    ("sandybrown", Color::new(0xf4, 0xa4, 0x60)),
+    ("seagreen", Color::new(0x2e, 0x8b, 0x57)),
+    ("seashell", Color::new(0xff, 0xf5, 0xee)),
+    ("sienna", Color::new(0xa0, 0x52, 0x2d)),
+    ("silver", Color::new(0xc0, 0xc0, 0xc0)),
+    ("skyblue", Color::new(0x87, 0xce, 0xeb)),
+    ("slateblue", Color::new(0x6a, 0x5a, 0xcd)),
+    ("slategray", Color::new(0x70, 0x80, 0x90)),
+    ("slategrey", Color::new(0x70, 0x80, 0x90)),
+    ("snow", Color::new(0xff, 0xfa, 0xfa)),
+    ("springgreen", Color::new(0x00, 0xff, 0x7f)),
+    ("steelblue", Color::new(0x46, 0x82, 0xb4)),
+    ("tan", Color::new(0xd2, 0xb4, 0x8c)),
+    ("teal", Color::new(0x00, 0x80, 0x80)),
+    ("thistle", Color::new(0xd8, 0xbf, 0xd8)),
+    ("tomato", Color::new(0xff, 0x63, 0x47)),
+    ("turquoise", Color::new(0x40, 0xe0, 0xd0)),
+    ("violet", Color::new(0xee, 0x82, 0xee)),
+    ("wheat", Color::new(0xf5, 0xde, 0xb3)),
+    ("white", Color::new(0xff, 0xff, 0xff)),
+    ("whitesmoke", Color::new(0xf5, 0xf5, 0xf5)),
+    ("yellow", Color::new(0xff, 0xff, 0x00)),
+    ("yellowgreen", Color::new(0x9a, 0xcd, 0x32)),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(input: &str) -> Color {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn names_ignore_case_and_separators() {
+        let slate = Color::new(0x2f, 0x4f, 0x4f);
+        assert_eq!(Color::from_name("darkslategray"), Some(slate));
+        assert_eq!(Color::from_name("Dark Slate Gray"), Some(slate));
+        assert_eq!(Color::from_name("dark_slate-gray"), Some(slate));
+        assert_eq!(Color::from_name("slate"), None);
+
+        assert_eq!(parse("  ORANGE "), Color::new(0xff, 0xa5, 0x00));
+        assert_eq!(Color::new(0xff, 0xa5, 0x00).name(), Some("orange"));
+        assert_eq!(Color::new(0x01, 0x02, 0x03).name(), None);
+    }
+
+    #[test]
+    fn notations_parse() {
+        let orange = Color::new(0xff, 0x88, 0x00);
+        assert_eq!(parse("#ff8800"), orange);
+        assert_eq!(parse("FF8800"), orange);
+        assert_eq!(parse("#f80"), orange);
+        assert_eq!(parse("rgb(255, 136, 0)"), orange);
+        assert_eq!(parse("RGB(255 136 0)"), orange);
+        assert_eq!(parse("rgb(100%, 0%, 0%)"), Color::new(0xff, 0, 0));
+        assert_eq!(parse("hsv(30, 100%, 100%)"), Color::new(0xff, 0x80, 0x00));
+        assert_eq!(parse("hsv(390deg, 100, 100)"), Color::new(0xff, 0x80, 0x00));
+        assert_eq!(parse("hsl(120, 100%, 25%)"), Color::new(0x00, 0x80, 0x00));
+        assert_eq!(parse("hsl(0, 0%, 100%)"), Color::new(0xff, 0xff, 0xff));
+    }
+
+    #[test]
+    fn malformed_colors_are_rejected() {
+        for input in [
+            "",
+            "#12345",
+            "#ff88001",
+            "#gg0000",
+            "rgb(255, 0)",
+            "rgb(256, 0, 0)",
+            "rgb(101%, 0%, 0%)",
+            "hsv(0, 150%, 50%)",
+            "hsl(nan, 50%, 50%)",
+            "cmyk(0, 0, 0)",
+            "teal-ish",
+        ] {
+            let error = input.parse::<Color>().unwrap_err();
+            assert!(error.to_string().contains("Invalid color"), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn color_spaces_convert() {
+        let blue = Color::new(0, 0, 0xff).to_hsv();
+        assert_eq!(
+            (blue.hue(), blue.saturation(), blue.value()),
+            (240.0, 1.0, 1.0)
+        );
+
+        let blue = Color::new(0, 0, 0xff).to_hsl();
+        assert_eq!(
+            (blue.hue(), blue.saturation(), blue.lightness()),
+            (240.0, 1.0, 0.5)
+        );
+
+        let gray = Color::new(0x80, 0x80, 0x80);
+        assert_eq!(gray.to_hsv().saturation(), 0.0);
+        assert_eq!(gray.to_hsl().saturation(), 0.0);
+        assert_eq!(Color::new(0, 0, 0).to_hsv().saturation(), 0.0);
+
+        assert_eq!(Hsv::new(-90.0, 2.0, -1.0), Hsv::new(270.0, 1.0, 0.0));
+        assert_eq!(Hsv::new(30.0, 1.0, 1.0).to_string(), "hsv(30, 100%, 100%)");
+        assert_eq!(
+            Hsl::new(120.0, 1.0, 0.25).to_string(),
+            "hsl(120, 100%, 25%)"
+        );
+    }
+
+    #[test]
+    fn distinct_colors_are_spread_around_the_hue_circle() {
+        assert_eq!(
+            Color::distinct(3),
+            [
+                Color::new(0xff, 0, 0),
+                Color::new(0, 0xff, 0),
+                Color::new(0, 0, 0xff)
+            ]
+        );
+        assert_eq!(Color::distinct(5).len(), 5);
+        assert!(Color::distinct(0).is_empty());
+    }
+
+    #[test]
+    fn correction_scales_channels() {
+        let color = Color::new(0xff, 0x80, 0x00);
+        assert_eq!(ColorCorrection::default().apply(color), color);
+        assert_eq!(color.corrected(ColorCorrection::default()), color);
+
+        let gamma = ColorCorrection::new(2.0, 1.0).unwrap();
+        assert_eq!(gamma.apply(color), Color::new(0xff, 0x40, 0x00));
+
+        let dimmed = ColorCorrection::new(1.0, 0.5).unwrap();
+        assert_eq!(
+            dimmed.apply(Color::new(0xff, 0x64, 0)),
+            Color::new(0x80, 0x32, 0)
+        );
+
+        assert!(ColorCorrection::new(0.0, 1.0).is_err());
+        assert!(ColorCorrection::new(f32::NAN, 1.0).is_err());
+        assert!(ColorCorrection::new(1.0, 1.5).is_err());
+        assert!(ColorCorrection::new(1.0, -0.1).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn hsv_and_hsl_round_trip(rgb in any::<[u8; 3]>()) {
+            let color = Color::from(rgb);
+            let close = |other: Color| {
+                <[u8; 3]>::from(other)
+                    .iter()
+                    .zip(rgb)
+                    .all(|(&a, b)| a.abs_diff(b) <= 1)
+            };
+
+            prop_assert!(close(Color::from(color.to_hsv())));
+            prop_assert!(close(Color::from(color.to_hsl())));
+        }
+
+        #[test]
+        fn bytes_round_trip(rgb in any::<[u8; 3]>()) {
+            let color = Color::from(rgb);
+            let bytes: [u8; 4] = color.into();
+
+            prop_assert_eq!(Color::try_from(&bytes[..]).unwrap(), color);
+        }
+
+        #[test]
+        fn corrupted_bytes_are_rejected(rgb in any::<[u8; 3]>(), index in 0usize..4, flip in 1u8..) {
+            let mut bytes: [u8; 4] = Color::from(rgb).into();
+            bytes[index] ^= flip;
+
+            prop_assert!(Color::try_from(&bytes[..]).is_err());
+        }
+
+        #[test]
+        fn display_parses_back(rgb in any::<[u8; 3]>()) {
+            let color = Color::from(rgb);
+
+            prop_assert_eq!(color.to_string().parse::<Color>().unwrap(), color);
+        }
+    }
+}
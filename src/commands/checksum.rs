@@ -0,0 +1,16 @@
+use libatk_rs::prelude::*;
+
+/// Checks the `value, 0x55 - value` pairs written by `set_data_byte_with_checksum`.
+pub fn check_pairs(name: &str, data: &[u8]) -> Result<(), Error> {
+    for (index, pair) in data.chunks_exact(2).enumerate() {
+        if pair[1] != 0x55u8.wrapping_sub(pair[0]) {
+            return Err(Error::ParseError(format!(
+                "{}: Invalid checksum at offset {}",
+                name,
+                index * 2
+            )));
+        }
+    }
+
+    Ok(())
+}
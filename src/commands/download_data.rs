@@ -1,6 +1,8 @@
 use libatk_rs::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum ConnectionType {
     #[default]
@@ -40,7 +42,6 @@ impl std::fmt::Display for DownloadData {
     }
 }
 
-#[allow(dead_code)]
 impl DownloadData {
     pub fn encrypted_data(&self) -> &[u8; 4] {
         &self.encrypted_data
@@ -81,10 +82,15 @@ impl Command<DownloadData> {
     }
 }
 
-#[allow(dead_code)]
 #[derive(Command, Default)]
 pub struct DriverStatus(u8);
 
+impl DriverStatus {
+    pub fn status(&self) -> u8 {
+        self.0
+    }
+}
+
 #[command_extension]
 impl Command<DriverStatus> {
     pub fn query() -> Self {
@@ -1,61 +1,81 @@
+use super::setting::{eeprom_command, Checksum, EepromSetting, SettingField};
 use libatk_rs::prelude::*;
+use serde::{Deserialize, Serialize};
 
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LedEffectMode {
     Static = 0x1,
     Breathing = 0x2,
 }
 
-impl From<u8> for LedEffectMode {
-    fn from(value: u8) -> Self {
+impl TryFrom<u8> for LedEffectMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0x1 => LedEffectMode::Static,
-            0x2 => LedEffectMode::Breathing,
-            _ => panic!("Invalid RGB lighting effect"),
+            0x1 => Ok(LedEffectMode::Static),
+            0x2 => Ok(LedEffectMode::Breathing),
+            _ => Err(Error::ParseError(format!(
+                "LedEffectMode: Invalid RGB lighting effect: {:#04x}",
+                value
+            ))),
         }
     }
 }
 
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LedBreathingRate {
     Slow = 0x1,
     Medium = 0x3,
     Fast = 0x5,
 }
 
-impl From<u8> for LedBreathingRate {
-    fn from(value: u8) -> Self {
+impl TryFrom<u8> for LedBreathingRate {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0x1 => LedBreathingRate::Slow,
-            0x3 => LedBreathingRate::Medium,
-            0x5 => LedBreathingRate::Fast,
-            _ => panic!("Invalid breathing speed"),
+            0x1 => Ok(LedBreathingRate::Slow),
+            0x3 => Ok(LedBreathingRate::Medium),
+            0x5 => Ok(LedBreathingRate::Fast),
+            _ => Err(Error::ParseError(format!(
+                "LedBreathingRate: Invalid breathing speed: {:#04x}",
+                value
+            ))),
         }
     }
 }
 
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LedBrightnessLevel {
     Low = 0x10,
     Medium = 0x80,
     High = 0xff,
 }
 
-impl From<u8> for LedBrightnessLevel {
-    fn from(value: u8) -> Self {
+impl TryFrom<u8> for LedBrightnessLevel {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0x10 => LedBrightnessLevel::Low,
-            0x80 => LedBrightnessLevel::Medium,
-            0xff => LedBrightnessLevel::High,
-            _ => panic!("Invalid long bright brightness"),
+            0x10 => Ok(LedBrightnessLevel::Low),
+            0x80 => Ok(LedBrightnessLevel::Medium),
+            0xff => Ok(LedBrightnessLevel::High),
+            _ => Err(Error::ParseError(format!(
+                "LedBrightnessLevel: Invalid long bright brightness: {:#04x}",
+                value
+            ))),
         }
     }
 }
 
-#[derive(Command, Debug, Clone)]
+#[derive(Command, Debug, Clone, PartialEq, Eq)]
 pub struct DpiLedSettings {
     mode: LedEffectMode,
     brightness: LedBrightnessLevel,
@@ -88,18 +108,13 @@ impl Default for DpiLedSettings {
 }
 
 impl DpiLedSettings {
-    pub fn set(
-        &self,
-        enabled: Option<bool>,
-        mode: Option<LedEffectMode>,
-        brightness: Option<LedBrightnessLevel>,
-        breathing_rate: Option<LedBreathingRate>,
-    ) -> DpiLedSettings {
+    /// Returns a copy of these settings with every field present in `patch` replaced.
+    pub fn apply(&self, patch: &LedPatch) -> DpiLedSettings {
         DpiLedSettings {
-            mode: mode.unwrap_or(self.mode),
-            brightness: brightness.unwrap_or(self.brightness),
-            breathing_rate: breathing_rate.unwrap_or(self.breathing_rate),
-            enabled: enabled.unwrap_or(self.enabled),
+            mode: patch.mode.unwrap_or(self.mode),
+            brightness: patch.brightness.unwrap_or(self.brightness),
+            breathing_rate: patch.breathing_rate.unwrap_or(self.breathing_rate),
+            enabled: patch.enabled.unwrap_or(self.enabled),
         }
     }
 
@@ -128,33 +143,109 @@ impl DpiLedSettings {
     }
 }
 
+/// A partial update of [`DpiLedSettings`]. Fields left as `None` keep their current value.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct LedPatch {
+    pub enabled: Option<bool>,
+    pub mode: Option<LedEffectMode>,
+    pub brightness: Option<LedBrightnessLevel>,
+    pub breathing_rate: Option<LedBreathingRate>,
+}
+
+impl LedPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn enabled(mut self, value: bool) -> Self {
+        self.enabled = Some(value);
+        self
+    }
+
+    pub fn mode(mut self, value: LedEffectMode) -> Self {
+        self.mode = Some(value);
+        self
+    }
+
+    pub fn brightness(mut self, value: LedBrightnessLevel) -> Self {
+        self.brightness = Some(value);
+        self
+    }
+
+    pub fn breathing_rate(mut self, value: LedBreathingRate) -> Self {
+        self.breathing_rate = Some(value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Combines two patches, fields set in `other` take precedence.
+    pub fn merge(self, other: LedPatch) -> Self {
+        LedPatch {
+            enabled: other.enabled.or(self.enabled),
+            mode: other.mode.or(self.mode),
+            brightness: other.brightness.or(self.brightness),
+            breathing_rate: other.breathing_rate.or(self.breathing_rate),
+        }
+    }
+}
+
+impl EepromSetting for DpiLedSettings {
+    type Key = ();
+
+    const NAME: &'static str = "DpiLedSettings";
+    const LEN: usize = 0x8;
+    const CHECKSUM: Checksum = Checksum::Pairs;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::byte("mode", 0x0),
+        SettingField::byte("brightness", 0x2),
+        SettingField::byte("breathing_rate", 0x4),
+        SettingField::byte("enabled", 0x6),
+    ];
+
+    fn address(_: ()) -> EEPROMAddress {
+        EEPROMAddress::DpiRgbLightingEffects
+    }
+
+    fn key(&self) {}
+
+    fn decode(_: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(DpiLedSettings {
+            mode: data[0x0].try_into()?,
+            brightness: data[0x2].try_into()?,
+            breathing_rate: data[0x4].try_into()?,
+            enabled: data[0x6] == 0x1,
+        })
+    }
+
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        data[0x0] = self.mode as u8;
+        data[0x2] = self.brightness as u8;
+        data[0x4] = self.breathing_rate as u8;
+        data[0x6] = self.enabled as u8;
+        Ok(())
+    }
+}
+
 #[command_extension]
 impl Command<DpiLedSettings> {
     pub fn builder() -> CommandBuilder<DpiLedSettings> {
-        let mut command = Command::default();
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::DpiRgbLightingEffects);
-        command.set_data_len(0x8).unwrap();
-
-        CommandBuilder::new(command)
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, ()))
     }
 
     pub fn query() -> Self {
-        let mut command = Command::default();
-        command.set_id(CommandId::GetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::DpiRgbLightingEffects);
-        command.set_data_len(0x8).unwrap();
-
-        command
+        DpiLedSettings::query_command(())
     }
 
     pub fn config(self) -> DpiLedSettings {
-        DpiLedSettings {
-            mode: self.data()[0x0].into(),
-            brightness: self.data()[0x2].into(),
-            breathing_rate: self.data()[0x4].into(),
-            enabled: self.data()[0x6] == 0x1,
-        }
+        DpiLedSettings::decode(self.eeprom_address(), self.data()).expect("Invalid DpiLedSettings")
+    }
+
+    /// Like [`config`](Self::config), but fails on bad checksums and unknown values.
+    pub fn try_config(self) -> Result<DpiLedSettings, Error> {
+        DpiLedSettings::from_bytes(self.eeprom_address(), self.data())
     }
 
     pub fn set_effect_mode(&mut self, value: LedEffectMode) {
@@ -173,3 +264,53 @@ impl Command<DpiLedSettings> {
         self.set_data_byte_with_checksum(value as u8, 0x6).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn settings() -> impl Strategy<Value = DpiLedSettings> {
+        (
+            prop::sample::select(vec![LedEffectMode::Static, LedEffectMode::Breathing]),
+            prop::sample::select(vec![
+                LedBrightnessLevel::Low,
+                LedBrightnessLevel::Medium,
+                LedBrightnessLevel::High,
+            ]),
+            prop::sample::select(vec![
+                LedBreathingRate::Slow,
+                LedBreathingRate::Medium,
+                LedBreathingRate::Fast,
+            ]),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(mode, brightness, breathing_rate, enabled)| DpiLedSettings {
+                    mode,
+                    brightness,
+                    breathing_rate,
+                    enabled,
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(settings in settings()) {
+            let bytes = settings.builder().build().as_bytes();
+            let command = Command::<DpiLedSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert_eq!(command.try_config().unwrap(), settings);
+        }
+
+        #[test]
+        fn corrupted_bytes_are_rejected(settings in settings(), index in 5usize..13, flip in 1u8..) {
+            let mut bytes = settings.builder().build().as_bytes();
+            bytes[index] ^= flip;
+            let command = Command::<DpiLedSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert!(command.try_config().is_err());
+        }
+    }
+}
@@ -1,8 +1,10 @@
+use super::setting::{eeprom_command, Checksum, EepromSetting, SettingField};
+use crate::color::Color;
 use libatk_rs::prelude::*;
 
 static DPI_STEP: u16 = 50;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Dpi(u16);
 
 impl std::fmt::Display for Dpi {
@@ -34,11 +36,10 @@ impl TryFrom<&[u8]> for Dpi {
             )));
         }
 
-        let checksum = 0xff
-            & 0x55u8
-                .wrapping_sub(data[0])
-                .wrapping_sub(data[1])
-                .wrapping_sub(data[2]);
+        let checksum = 0x55u8
+            .wrapping_sub(data[0])
+            .wrapping_sub(data[1])
+            .wrapping_sub(data[2]);
         if checksum != data[3] {
             return Err(Error::ParseError("DPI: Invalid checksum".to_string()));
         }
@@ -52,18 +53,19 @@ impl TryFrom<&[u8]> for Dpi {
     }
 }
 
-impl Into<[u8; 4]> for Dpi {
-    fn into(self) -> [u8; 4] {
-        let steps = (self.dpi() / DPI_STEP) - 1;
+impl From<Dpi> for [u8; 4] {
+    fn from(dpi: Dpi) -> Self {
+        let steps = (dpi.dpi() / DPI_STEP) - 1;
 
-        let x_dpi = u8::MAX & steps as u8;
+        // The low byte of the step count goes in X and Y, each 256 steps above that add
+        // 0x44 to the extension byte (one bit for X, one for Y).
+        let x_dpi = steps as u8;
         let y_dpi = x_dpi;
-        let dpi_ex = (0x44 * steps / (u8::MAX as u16 + 1)) as u8;
-        let checksum = u8::MAX
-            & 0x55u8
-                .wrapping_sub(x_dpi)
-                .wrapping_sub(y_dpi)
-                .wrapping_sub(dpi_ex);
+        let dpi_ex = (0x44 * (steps >> 8)) as u8;
+        let checksum = 0x55u8
+            .wrapping_sub(x_dpi)
+            .wrapping_sub(y_dpi)
+            .wrapping_sub(dpi_ex);
 
         [x_dpi, y_dpi, dpi_ex, checksum]
     }
@@ -79,74 +81,8 @@ impl Dpi {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct Color {
-    red: u8,
-    green: u8,
-    blue: u8,
-}
-
-impl Default for Color {
-    fn default() -> Self {
-        Color {
-            red: 0xFF,
-            green: 0xFF,
-            blue: 0xFF,
-        }
-    }
-}
-
-impl std::fmt::Display for Color {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
-    }
-}
-
-impl TryFrom<&[u8]> for Color {
-    type Error = Error;
-
-    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
-        if data.len() != 4 {
-            return Err(Error::ParseError("Color: Invalid data length".to_string()));
-        }
-
-        let checksum = 0xff
-            & 0x55u8
-                .wrapping_sub(data[0])
-                .wrapping_sub(data[1])
-                .wrapping_sub(data[2]);
-
-        if checksum != data[3] {
-            return Err(Error::ParseError("Color: Invalid checksum".to_string()));
-        }
-
-        Ok(Self {
-            red: data[0],
-            green: data[1],
-            blue: data[2],
-        })
-    }
-}
-
-impl Into<[u8; 4]> for Color {
-    fn into(self) -> [u8; 4] {
-        let checksum = 0xff
-            & 0x55u8
-                .wrapping_sub(self.red)
-                .wrapping_sub(self.green)
-                .wrapping_sub(self.blue);
-
-        [self.red, self.green, self.blue, checksum]
-    }
-}
-
-impl Color {
-    pub fn new(red: u8, green: u8, blue: u8) -> Self {
-        Color { red, green, blue }
-    }
-}
-
-#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Preset {
     Preset1,
     Preset2,
@@ -158,7 +94,7 @@ pub enum Preset {
     Preset8,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub enum Pair {
     #[default]
     Pair1,
@@ -250,7 +186,7 @@ impl TryFrom<EEPROMAddress> for Pair {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Gear {
     dpi: Dpi,
     color: Color,
@@ -260,6 +196,14 @@ impl Gear {
     pub fn new(dpi: Dpi, color: Color) -> Self {
         Gear { dpi, color }
     }
+
+    pub fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
 }
 
 impl std::fmt::Display for Gear {
@@ -268,14 +212,13 @@ impl std::fmt::Display for Gear {
     }
 }
 
-#[derive(Command, Debug, Default)]
+#[derive(Command, Debug, Default, Clone, PartialEq, Eq)]
 pub struct DpiPairSetting {
     _pair: Pair,
     dpi_first: Dpi,
     dpi_second: Dpi,
 }
 
-#[allow(dead_code)]
 impl DpiPairSetting {
     pub fn dpi(&self, slot: Slot) -> Dpi {
         match slot {
@@ -284,6 +227,16 @@ impl DpiPairSetting {
         }
     }
 
+    /// Returns a copy of this pair with the DPI in `slot` replaced.
+    pub fn with_dpi(&self, dpi: Dpi, slot: Slot) -> Self {
+        let mut setting = self.clone();
+        match slot {
+            Slot::First => setting.dpi_first = dpi,
+            Slot::Second => setting.dpi_second = dpi,
+        }
+        setting
+    }
+
     pub fn builder(&self) -> CommandBuilder<DpiPairSetting> {
         Command::<DpiPairSetting>::builder(self._pair)
             .dpi(self.dpi(Slot::First), Slot::First)
@@ -291,37 +244,69 @@ impl DpiPairSetting {
     }
 }
 
+impl From<Pair> for DpiPairSetting {
+    fn from(pair: Pair) -> Self {
+        DpiPairSetting {
+            _pair: pair,
+            ..Default::default()
+        }
+    }
+}
+
+impl EepromSetting for DpiPairSetting {
+    type Key = Pair;
+
+    const NAME: &'static str = "DpiPairSetting";
+    const LEN: usize = 0x8;
+    const CHECKSUM: Checksum = Checksum::PerField;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::new("dpi_first", Slot::First as usize, 4),
+        SettingField::new("dpi_second", Slot::Second as usize, 4),
+    ];
+
+    fn address(pair: Pair) -> EEPROMAddress {
+        pair.dpi_eeprom_address()
+    }
+
+    fn key(&self) -> Pair {
+        self._pair
+    }
+
+    fn decode(address: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(DpiPairSetting {
+            _pair: Pair::try_from(address)
+                .map_err(|e| Error::ParseError(format!("Pair: {}", e)))?,
+            dpi_first: Dpi::try_from(&data[0..4])?,
+            dpi_second: Dpi::try_from(&data[4..8])?,
+        })
+    }
+
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        for slot in [Slot::First, Slot::Second] {
+            let bytes: [u8; 4] = self.dpi(slot).into();
+            data[slot as usize..slot as usize + 4].copy_from_slice(&bytes);
+        }
+        Ok(())
+    }
+}
+
 #[command_extension]
 impl Command<DpiPairSetting> {
     pub fn query(pair: Pair) -> Self {
-        let mut instance = Command::default();
-        instance.set_id(CommandId::GetEEPROM);
-        instance.set_eeprom_address(pair.dpi_eeprom_address());
-        instance.set_data_len(0x8).unwrap();
-
-        instance
+        DpiPairSetting::query_command(pair)
     }
 
     pub fn builder(pair: Pair) -> CommandBuilder<DpiPairSetting> {
-        let mut command = Command::default();
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(pair.dpi_eeprom_address());
-        command.set_data_len(0x8).unwrap();
-
-        CommandBuilder::new(command)
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, pair))
     }
 
     pub fn config(self) -> DpiPairSetting {
-        let data = self.data();
-        let pair = Pair::try_from(self.eeprom_address())
-            .expect("Failed to parse EEPROM address to DPI pair");
-        let dpi1 = Dpi::try_from(&data[0..4]).expect("Failed to parse DPI #1");
-        let dpi2 = Dpi::try_from(&data[4..8]).expect("Failed to parse DPI #2");
-        DpiPairSetting {
-            _pair: pair,
-            dpi_first: dpi1,
-            dpi_second: dpi2,
-        }
+        DpiPairSetting::decode(self.eeprom_address(), self.data()).expect("Invalid DpiPairSetting")
+    }
+
+    /// Like [`config`](Self::config), but fails instead of panicking on bad data.
+    pub fn try_config(self) -> Result<DpiPairSetting, Error> {
+        DpiPairSetting::from_bytes(self.eeprom_address(), self.data())
     }
 
     pub fn set_dpi(&mut self, dpi: Dpi, slot: Slot) {
@@ -331,14 +316,13 @@ impl Command<DpiPairSetting> {
     }
 }
 
-#[derive(Command, Debug, Default)]
+#[derive(Command, Debug, Default, Clone, PartialEq, Eq)]
 pub struct ColorPairSetting {
     _pair: Pair,
     color_first: Color,
     color_second: Color,
 }
 
-#[allow(dead_code)]
 impl ColorPairSetting {
     pub fn color(&self, slot: Slot) -> Color {
         match slot {
@@ -347,6 +331,16 @@ impl ColorPairSetting {
         }
     }
 
+    /// Returns a copy of this pair with the color in `slot` replaced.
+    pub fn with_color(&self, color: Color, slot: Slot) -> Self {
+        let mut setting = self.clone();
+        match slot {
+            Slot::First => setting.color_first = color,
+            Slot::Second => setting.color_second = color,
+        }
+        setting
+    }
+
     pub fn builder(&self) -> CommandBuilder<ColorPairSetting> {
         Command::<ColorPairSetting>::builder(self._pair)
             .color(self.color(Slot::First), Slot::First)
@@ -354,36 +348,70 @@ impl ColorPairSetting {
     }
 }
 
+impl From<Pair> for ColorPairSetting {
+    fn from(pair: Pair) -> Self {
+        ColorPairSetting {
+            _pair: pair,
+            ..Default::default()
+        }
+    }
+}
+
+impl EepromSetting for ColorPairSetting {
+    type Key = Pair;
+
+    const NAME: &'static str = "ColorPairSetting";
+    const LEN: usize = 0x8;
+    const CHECKSUM: Checksum = Checksum::PerField;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::new("color_first", Slot::First as usize, 4),
+        SettingField::new("color_second", Slot::Second as usize, 4),
+    ];
+
+    fn address(pair: Pair) -> EEPROMAddress {
+        pair.color_eeprom_address()
+    }
+
+    fn key(&self) -> Pair {
+        self._pair
+    }
+
+    fn decode(address: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(ColorPairSetting {
+            _pair: Pair::try_from(address)
+                .map_err(|e| Error::ParseError(format!("Pair: {}", e)))?,
+            color_first: Color::try_from(&data[0..4])?,
+            color_second: Color::try_from(&data[4..8])?,
+        })
+    }
+
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        for slot in [Slot::First, Slot::Second] {
+            let bytes: [u8; 4] = self.color(slot).into();
+            data[slot as usize..slot as usize + 4].copy_from_slice(&bytes);
+        }
+        Ok(())
+    }
+}
+
 #[command_extension]
 impl Command<ColorPairSetting> {
     pub fn query(pair: Pair) -> Self {
-        let mut instance = Command::default();
-        instance.set_id(CommandId::GetEEPROM);
-        instance.set_eeprom_address(pair.color_eeprom_address());
-        instance.set_data_len(0x8).unwrap();
-        instance
+        ColorPairSetting::query_command(pair)
     }
 
     pub fn builder(pair: Pair) -> CommandBuilder<ColorPairSetting> {
-        let mut command = Command::default();
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(pair.color_eeprom_address());
-        command.set_data_len(0x8).unwrap();
-
-        CommandBuilder::new(command)
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, pair))
     }
 
     pub fn config(self) -> ColorPairSetting {
-        let data = self.data();
-        let pair = Pair::try_from(self.eeprom_address())
-            .expect("Failed to parse EEPROM address to DPI pair");
-        let color1 = Color::try_from(&data[0..4]).expect("Failed to parse color #1");
-        let color2 = Color::try_from(&data[4..8]).expect("Failed to parse color #2");
-        ColorPairSetting {
-            _pair: pair,
-            color_first: color1,
-            color_second: color2,
-        }
+        ColorPairSetting::decode(self.eeprom_address(), self.data())
+            .expect("Invalid ColorPairSetting")
+    }
+
+    /// Like [`config`](Self::config), but fails instead of panicking on bad data.
+    pub fn try_config(self) -> Result<ColorPairSetting, Error> {
+        ColorPairSetting::from_bytes(self.eeprom_address(), self.data())
     }
 
     pub fn set_color(&mut self, color: Color, slot: Slot) {
@@ -392,3 +420,87 @@ impl Command<ColorPairSetting> {
             .expect("Failed to set color value");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Every DPI the encoding can represent: 1 to 1024 steps of 50.
+    fn dpi() -> impl Strategy<Value = Dpi> {
+        (1u16..=1024).prop_map(|steps| Dpi::new(steps * DPI_STEP))
+    }
+
+    fn pair() -> impl Strategy<Value = Pair> {
+        prop::sample::select(vec![Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4])
+    }
+
+    fn color() -> impl Strategy<Value = Color> {
+        any::<[u8; 3]>().prop_map(Color::from)
+    }
+
+    fn reparse<T: CommandDescriptor>(command: Command<T>) -> Command<T> {
+        Command::try_from(&command.as_bytes()[..]).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn dpi_round_trip(dpi in dpi()) {
+            let bytes: [u8; 4] = dpi.into();
+
+            prop_assert_eq!(Dpi::try_from(&bytes[..]).unwrap(), dpi);
+        }
+
+        #[test]
+        fn corrupted_dpi_is_rejected(dpi in dpi(), index in 0usize..4, flip in 1u8..) {
+            let mut bytes: [u8; 4] = dpi.into();
+            bytes[index] ^= flip;
+
+            prop_assert!(Dpi::try_from(&bytes[..]).is_err());
+        }
+
+        #[test]
+        fn dpi_pair_round_trip(pair in pair(), first in dpi(), second in dpi()) {
+            let command = Command::<DpiPairSetting>::builder(pair)
+                .dpi(first, Slot::First)
+                .dpi(second, Slot::Second)
+                .build();
+
+            prop_assert_eq!(
+                reparse(command).try_config().unwrap(),
+                DpiPairSetting { _pair: pair, dpi_first: first, dpi_second: second }
+            );
+        }
+
+        #[test]
+        fn color_pair_round_trip(pair in pair(), first in color(), second in color()) {
+            let command = Command::<ColorPairSetting>::builder(pair)
+                .color(first, Slot::First)
+                .color(second, Slot::Second)
+                .build();
+
+            prop_assert_eq!(
+                reparse(command).try_config().unwrap(),
+                ColorPairSetting { _pair: pair, color_first: first, color_second: second }
+            );
+        }
+
+        #[test]
+        fn corrupted_color_pair_is_rejected(
+            first in color(),
+            second in color(),
+            index in 5usize..13,
+            flip in 1u8..,
+        ) {
+            let mut bytes = Command::<ColorPairSetting>::builder(Pair::Pair1)
+                .color(first, Slot::First)
+                .color(second, Slot::Second)
+                .build()
+                .as_bytes();
+            bytes[index] ^= flip;
+
+            let command = Command::<ColorPairSetting>::try_from(&bytes[..]).unwrap();
+            prop_assert!(command.try_config().is_err());
+        }
+    }
+}
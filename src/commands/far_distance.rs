@@ -1,6 +1,6 @@
 use libatk_rs::prelude::*;
 
-#[derive(Command, Default, Debug)]
+#[derive(Command, Default, Debug, Clone, PartialEq, Eq)]
 pub struct FarDistanceMode(bool);
 
 impl std::fmt::Display for FarDistanceMode {
@@ -9,7 +9,12 @@ impl std::fmt::Display for FarDistanceMode {
     }
 }
 
-#[allow(dead_code)]
+impl From<bool> for FarDistanceMode {
+    fn from(mode: bool) -> Self {
+        FarDistanceMode(mode)
+    }
+}
+
 impl FarDistanceMode {
     pub fn far_distance_mode(&self) -> bool {
         self.0
@@ -39,11 +44,36 @@ impl Command<FarDistanceMode> {
     }
 
     pub fn config(self) -> FarDistanceMode {
-        FarDistanceMode(self.data()[0x0] == 0x01)
+        FarDistanceMode(self.data().first() == Some(&0x01))
     }
 
+    /// The mode goes out with a data length of 0, the way this command has always been
+    /// sent. Changing that needs a capture from real hardware.
     pub fn set_far_distance_mode(&mut self, mode: bool) {
+        let len = self.data_len();
+        self.set_data_len(0x1).unwrap();
         self.set_data_byte(mode as u8, 0x0)
             .expect("Failed to set far distance mode");
+        self.set_data_len(len).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for mode in [false, true] {
+            let bytes = FarDistanceMode::from(mode).builder().build().as_bytes();
+            assert_eq!(bytes[0x4], 0x0);
+            assert_eq!(bytes[0x5], mode as u8);
+
+            let mut response = Command::<FarDistanceMode>::query();
+            response.set_data_len(0x1).unwrap();
+            response.set_data_byte(mode as u8, 0x0).unwrap();
+            let command = Command::<FarDistanceMode>::try_from(response.as_bytes()).unwrap();
+            assert_eq!(command.config(), FarDistanceMode::from(mode));
+        }
     }
 }
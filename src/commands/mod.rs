@@ -1,4 +1,5 @@
 mod battery;
+mod checksum;
 mod download_data;
 mod dpi_led;
 mod dpi_profiles;
@@ -7,7 +8,10 @@ mod far_distance;
 mod mouse_info;
 mod pairing;
 mod performance;
+mod setting;
 mod silent_height;
+#[cfg(test)]
+mod unverified;
 
 pub mod prelude {
     pub use super::battery::*;
@@ -19,5 +23,6 @@ pub mod prelude {
     pub use super::mouse_info::*;
     pub use super::pairing::*;
     pub use super::performance::*;
+    pub use super::setting::*;
     pub use super::silent_height::*;
 }
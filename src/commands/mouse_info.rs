@@ -1,6 +1,9 @@
+use super::setting::{eeprom_command, Checksum, EepromSetting, SettingField};
 use libatk_rs::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Default, Debug, Clone, Copy)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum PollingRate {
     #[default]
@@ -27,22 +30,27 @@ impl std::fmt::Display for PollingRate {
     }
 }
 
-impl From<u8> for PollingRate {
-    fn from(value: u8) -> Self {
+impl TryFrom<u8> for PollingRate {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
         match value {
-            0x1 => PollingRate::Hz1000,
-            0x2 => PollingRate::Hz500,
-            0x4 => PollingRate::Hz250,
-            0x8 => PollingRate::Hz125,
-            0x10 => PollingRate::Hz2000,
-            0x20 => PollingRate::Hz4000,
-            0x40 => PollingRate::Hz8000,
-            _ => panic!("Invalid Value"),
+            0x1 => Ok(PollingRate::Hz1000),
+            0x2 => Ok(PollingRate::Hz500),
+            0x4 => Ok(PollingRate::Hz250),
+            0x8 => Ok(PollingRate::Hz125),
+            0x10 => Ok(PollingRate::Hz2000),
+            0x20 => Ok(PollingRate::Hz4000),
+            0x40 => Ok(PollingRate::Hz8000),
+            _ => Err(Error::ParseError(format!(
+                "PollingRate: Invalid value: {:#04x}",
+                value
+            ))),
         }
     }
 }
 
-#[derive(Command, Default, Debug)]
+#[derive(Command, Default, Debug, Clone, PartialEq, Eq)]
 pub struct MouseInfo {
     poll_rate: PollingRate,
     num_profile: u8,
@@ -55,7 +63,6 @@ impl std::fmt::Display for MouseInfo {
     }
 }
 
-#[allow(dead_code)]
 impl MouseInfo {
     pub fn poll_rate(&self) -> PollingRate {
         self.poll_rate
@@ -69,6 +76,30 @@ impl MouseInfo {
         self.active_profile
     }
 
+    /// Returns a copy with the polling rate replaced.
+    pub fn with_poll_rate(&self, poll_rate: PollingRate) -> Self {
+        MouseInfo {
+            poll_rate,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy with the index of the last preset in use replaced.
+    pub fn with_num_profile(&self, num_profile: u8) -> Self {
+        MouseInfo {
+            num_profile,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a copy with the active preset index replaced.
+    pub fn with_active_profile(&self, active_profile: u8) -> Self {
+        MouseInfo {
+            active_profile,
+            ..self.clone()
+        }
+    }
+
     pub fn builder(&self) -> CommandBuilder<MouseInfo> {
         Command::builder()
             .poll_rate(self.poll_rate)
@@ -77,38 +108,57 @@ impl MouseInfo {
     }
 }
 
-#[command_extension]
-impl Command<MouseInfo> {
-    pub fn query() -> Self {
-        let mut command = Command::default();
+impl EepromSetting for MouseInfo {
+    type Key = ();
 
-        command.set_id(CommandId::GetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::ReportRate);
-        command.set_data_len(0x6).unwrap();
+    const NAME: &'static str = "MouseInfo";
+    const LEN: usize = 0x6;
+    const CHECKSUM: Checksum = Checksum::Pairs;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::byte("poll_rate", 0x0),
+        SettingField::byte("num_profile", 0x2),
+        SettingField::byte("active_profile", 0x4),
+    ];
 
-        command
+    fn address(_: ()) -> EEPROMAddress {
+        EEPROMAddress::ReportRate
     }
 
-    pub fn builder() -> CommandBuilder<MouseInfo> {
-        let mut command = Command::default();
+    fn key(&self) {}
+
+    fn decode(_: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(MouseInfo {
+            poll_rate: PollingRate::try_from(data[0x0])?,
+            num_profile: data[0x2],
+            active_profile: data[0x4],
+        })
+    }
+
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        data[0x0] = self.poll_rate as u8;
+        data[0x2] = self.num_profile;
+        data[0x4] = self.active_profile;
+        Ok(())
+    }
+}
 
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::ReportRate);
-        command.set_data_len(0x6).unwrap();
+#[command_extension]
+impl Command<MouseInfo> {
+    pub fn query() -> Self {
+        MouseInfo::query_command(())
+    }
 
-        CommandBuilder::new(command)
+    pub fn builder() -> CommandBuilder<MouseInfo> {
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, ()))
     }
 
     pub fn config(self) -> MouseInfo {
-        let poll_rate = PollingRate::from(self.data()[0x0]);
-        let num_profile = self.data()[0x2];
-        let active_profile = self.data()[0x4];
+        MouseInfo::decode(self.eeprom_address(), self.data()).expect("Invalid MouseInfo")
+    }
 
-        MouseInfo {
-            poll_rate,
-            num_profile,
-            active_profile,
-        }
+    /// Like [`config`](Self::config), but fails on bad checksums and unknown values.
+    pub fn try_config(self) -> Result<MouseInfo, Error> {
+        MouseInfo::from_bytes(self.eeprom_address(), self.data())
     }
 
     pub fn set_poll_rate(&mut self, rate: PollingRate) {
@@ -123,3 +173,49 @@ impl Command<MouseInfo> {
         self.set_data_byte_with_checksum(dpi, 0x4).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn info() -> impl Strategy<Value = MouseInfo> {
+        (
+            prop::sample::select(vec![
+                PollingRate::Hz125,
+                PollingRate::Hz250,
+                PollingRate::Hz500,
+                PollingRate::Hz1000,
+                PollingRate::Hz2000,
+                PollingRate::Hz4000,
+                PollingRate::Hz8000,
+            ]),
+            any::<u8>(),
+            any::<u8>(),
+        )
+            .prop_map(|(poll_rate, num_profile, active_profile)| MouseInfo {
+                poll_rate,
+                num_profile,
+                active_profile,
+            })
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(info in info()) {
+            let bytes = info.builder().build().as_bytes();
+            let command = Command::<MouseInfo>::try_from(&bytes[..]).unwrap();
+
+            prop_assert_eq!(command.try_config().unwrap(), info);
+        }
+
+        #[test]
+        fn corrupted_bytes_are_rejected(info in info(), index in 5usize..11, flip in 1u8..) {
+            let mut bytes = info.builder().build().as_bytes();
+            bytes[index] ^= flip;
+            let command = Command::<MouseInfo>::try_from(&bytes[..]).unwrap();
+
+            prop_assert!(command.try_config().is_err());
+        }
+    }
+}
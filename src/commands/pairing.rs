@@ -11,18 +11,43 @@ impl Command<StartPairing> {
         CommandBuilder::new(command)
     }
 
+    /// The IDs go out with a data length of 0, like the mode of `SetFarDistanceMode`.
+    /// Changing that needs a capture from real hardware.
     pub fn set_cid(&mut self, cid: u8) {
+        let len = self.data_len();
+        self.set_data_len(0x2).unwrap();
         self.set_data_byte(cid, 0x0).expect("Failed to set CID");
+        self.set_data_len(len).unwrap();
     }
 
     pub fn set_mid(&mut self, mid: u8) {
+        let len = self.data_len();
+        self.set_data_len(0x2).unwrap();
         self.set_data_byte(mid, 0x1).expect("Failed to set MID");
+        self.set_data_len(len).unwrap();
     }
 }
 
 #[derive(Command)]
 pub struct GetPairingStatus;
 
+/// Progress of pairing the dongle with a mouse, as reported by the dongle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PairingStatus {
+    pub status: u8,
+    pub time_left: u8,
+}
+
+impl std::fmt::Display for PairingStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Pairing Status: {} | Time Left: {}s",
+            self.status, self.time_left
+        )
+    }
+}
+
 #[command_extension]
 impl Command<GetPairingStatus> {
     pub fn query() -> Self {
@@ -31,15 +56,31 @@ impl Command<GetPairingStatus> {
         command
     }
 
-    pub fn pair_status(&mut self) -> u8 {
-        self.data()[0x0]
+    pub fn pair_status(&self) -> Result<u8, Error> {
+        pairing_byte(self, 0x0)
+    }
+
+    pub fn pair_time_left(&self) -> Result<u8, Error> {
+        pairing_byte(self, 0x1)
     }
 
-    pub fn pair_time_left(&mut self) -> u8 {
-        self.data()[0x1]
+    pub fn try_config(self) -> Result<PairingStatus, Error> {
+        Ok(PairingStatus {
+            status: self.pair_status()?,
+            time_left: self.pair_time_left()?,
+        })
     }
 }
 
+fn pairing_byte(command: &Command<GetPairingStatus>, index: usize) -> Result<u8, Error> {
+    command.data().get(index).copied().ok_or_else(|| {
+        Error::ParseError(format!(
+            "Pairing: Status needs 2 data bytes, got {}",
+            command.data().len()
+        ))
+    })
+}
+
 #[derive(Command)]
 pub struct ExitPairing;
 
@@ -51,3 +92,41 @@ impl Command<ExitPairing> {
         command
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_pairing_carries_the_ids_past_its_length() {
+        let bytes = Command::<StartPairing>::builder()
+            .cid(0x12)
+            .mid(0x34)
+            .build()
+            .as_bytes();
+        assert_eq!(bytes[0x4], 0x0);
+        assert_eq!(bytes[0x5..0x7], [0x12, 0x34]);
+    }
+
+    #[test]
+    fn short_status_responses_are_errors() {
+        for len in [0x0, 0x1] {
+            let mut response = Command::<GetPairingStatus>::query();
+            response.set_data_len(len).unwrap();
+            let command = Command::<GetPairingStatus>::try_from(response.as_bytes()).unwrap();
+            assert!(command.try_config().is_err());
+        }
+
+        let mut response = Command::<GetPairingStatus>::query();
+        response.set_data_len(0x2).unwrap();
+        response.set_data(&[0x1, 0x3], 0x0).unwrap();
+        let command = Command::<GetPairingStatus>::try_from(response.as_bytes()).unwrap();
+        assert_eq!(
+            command.try_config().unwrap(),
+            PairingStatus {
+                status: 0x1,
+                time_left: 0x3
+            }
+        );
+    }
+}
@@ -1,7 +1,22 @@
-use crate::types::{Decaseconds, Duration, Milliseconds, Seconds};
+use super::setting::{eeprom_command, Checksum, EepromSetting, SettingField};
+use crate::types::{Decaseconds, Duration, DurationRange, Milliseconds, Seconds};
 use libatk_rs::prelude::*;
 
-#[derive(Command, Default, Debug)]
+/// Every timing is stored in a single byte of its unit.
+const BYTE_MAX: u32 = u8::MAX as u32;
+
+// What each timing byte can encode. The range a mouse actually accepts is not known per
+// setting, models that accept less narrow these through their capabilities.
+pub static STABILIZATION_TIME_RANGE: DurationRange<Milliseconds> =
+    DurationRange::new("Stabilization time", 0, BYTE_MAX);
+pub static CLOSE_LED_TIME_RANGE: DurationRange<Decaseconds> =
+    DurationRange::new("Close LED time", 0, BYTE_MAX);
+pub static SENSOR_SLEEP_TIME_RANGE: DurationRange<Decaseconds> =
+    DurationRange::new("Sensor sleep time", 0, BYTE_MAX);
+pub static RF_TX_TIME_RANGE: DurationRange<Milliseconds> =
+    DurationRange::new("RF Tx time", 0, BYTE_MAX);
+
+#[derive(Command, Default, Debug, Clone, PartialEq, Eq)]
 pub struct MousePerfSettings {
     stabilization_time: Duration<Milliseconds>,
     motion_sync: bool,
@@ -24,23 +39,25 @@ impl std::fmt::Display for MousePerfSettings {
     }
 }
 
-#[allow(dead_code)]
 impl MousePerfSettings {
-    pub fn set(
-        &self,
-        stabilization_time: Option<Duration<Milliseconds>>,
-        motion_sync: Option<bool>,
-        close_led_time: Option<Duration<Decaseconds>>,
-        linear_correction: Option<bool>,
-        ripple_control: Option<bool>,
-    ) -> Self {
-        MousePerfSettings {
-            stabilization_time: stabilization_time.unwrap_or(self.stabilization_time),
-            motion_sync: motion_sync.unwrap_or(self.motion_sync),
-            close_led_time: close_led_time.unwrap_or(self.close_led_time),
-            linear_correction: linear_correction.unwrap_or(self.linear_correction),
-            ripple_control: ripple_control.unwrap_or(self.ripple_control),
-        }
+    /// Returns a copy of these settings with every field present in `patch` replaced.
+    pub fn apply(&self, patch: &MousePerfPatch) -> Result<Self, Error> {
+        patch
+            .stabilization_time
+            .map(|value| STABILIZATION_TIME_RANGE.check(value))
+            .transpose()?;
+        patch
+            .close_led_time
+            .map(|value| CLOSE_LED_TIME_RANGE.check(value))
+            .transpose()?;
+
+        Ok(MousePerfSettings {
+            stabilization_time: patch.stabilization_time.unwrap_or(self.stabilization_time),
+            motion_sync: patch.motion_sync.unwrap_or(self.motion_sync),
+            close_led_time: patch.close_led_time.unwrap_or(self.close_led_time),
+            linear_correction: patch.linear_correction.unwrap_or(self.linear_correction),
+            ripple_control: patch.ripple_control.unwrap_or(self.ripple_control),
+        })
     }
 
     pub fn stabilization_time(&self) -> Duration<Milliseconds> {
@@ -63,66 +80,151 @@ impl MousePerfSettings {
         self.ripple_control
     }
 
-    pub fn builder(&self) -> CommandBuilder<Self> {
-        Command::<Self>::builder()
-            .stabilization_time(self.stabilization_time)
+    /// Fails if a timing is outside its range.
+    pub fn builder(&self) -> Result<CommandBuilder<Self>, Error> {
+        let mut command = Command::<Self>::builder()
             .motion_sync(self.motion_sync)
-            .close_led_time(self.close_led_time)
             .linear_correction(self.linear_correction)
             .ripple_control(self.ripple_control)
+            .build();
+        command.try_set_stabilization_time(self.stabilization_time)?;
+        command.try_set_close_led_time(self.close_led_time)?;
+
+        Ok(CommandBuilder::new(command))
     }
 }
 
-#[command_extension]
-impl Command<MousePerfSettings> {
-    pub fn query() -> Self {
-        let mut instance = Command::default();
+/// A partial update of [`MousePerfSettings`]. Fields left as `None` keep their current value.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct MousePerfPatch {
+    pub stabilization_time: Option<Duration<Milliseconds>>,
+    pub motion_sync: Option<bool>,
+    pub close_led_time: Option<Duration<Decaseconds>>,
+    pub linear_correction: Option<bool>,
+    pub ripple_control: Option<bool>,
+}
 
-        instance.set_id(CommandId::GetEEPROM);
-        instance.set_eeprom_address(EEPROMAddress::StabilizationTime);
-        instance.set_data_len(0xA).unwrap();
+impl MousePerfPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        instance
+    pub fn stabilization_time(mut self, value: Duration<Milliseconds>) -> Self {
+        self.stabilization_time = Some(value);
+        self
     }
 
-    pub fn builder() -> CommandBuilder<MousePerfSettings> {
-        let mut command = Command::default();
+    pub fn motion_sync(mut self, value: bool) -> Self {
+        self.motion_sync = Some(value);
+        self
+    }
 
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::StabilizationTime);
-        command.set_data_len(0xA).unwrap();
+    pub fn close_led_time(mut self, value: Duration<Decaseconds>) -> Self {
+        self.close_led_time = Some(value);
+        self
+    }
 
-        CommandBuilder::new(command)
+    pub fn linear_correction(mut self, value: bool) -> Self {
+        self.linear_correction = Some(value);
+        self
     }
 
-    pub fn config(self) -> MousePerfSettings {
-        let stabilization_time = Duration::<Milliseconds>::new(self.data()[0x0] as u32);
-        let motion_sync = self.data()[0x2] == 0x1;
-        let close_led_time = Duration::<Decaseconds>::new(self.data()[0x4] as u32);
-        let linear_correction = self.data()[0x6] == 0x1;
-        let ripple_control = self.data()[0x8] == 0x1;
-
-        MousePerfSettings {
-            stabilization_time,
-            motion_sync,
-            close_led_time,
-            linear_correction,
-            ripple_control,
+    pub fn ripple_control(mut self, value: bool) -> Self {
+        self.ripple_control = Some(value);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Combines two patches, fields set in `other` take precedence.
+    pub fn merge(self, other: MousePerfPatch) -> Self {
+        MousePerfPatch {
+            stabilization_time: other.stabilization_time.or(self.stabilization_time),
+            motion_sync: other.motion_sync.or(self.motion_sync),
+            close_led_time: other.close_led_time.or(self.close_led_time),
+            linear_correction: other.linear_correction.or(self.linear_correction),
+            ripple_control: other.ripple_control.or(self.ripple_control),
         }
     }
+}
+
+impl EepromSetting for MousePerfSettings {
+    type Key = ();
+
+    const NAME: &'static str = "MousePerfSettings";
+    const LEN: usize = 0xA;
+    const CHECKSUM: Checksum = Checksum::Pairs;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::byte("stabilization_time", 0x0),
+        SettingField::byte("motion_sync", 0x2),
+        SettingField::byte("close_led_time", 0x4),
+        SettingField::byte("linear_correction", 0x6),
+        SettingField::byte("ripple_control", 0x8),
+    ];
+
+    fn address(_: ()) -> EEPROMAddress {
+        EEPROMAddress::StabilizationTime
+    }
+
+    fn key(&self) {}
+
+    fn decode(_: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(MousePerfSettings {
+            stabilization_time: Duration::new(data[0x0] as u32),
+            motion_sync: data[0x2] == 0x1,
+            close_led_time: Duration::new(data[0x4] as u32),
+            linear_correction: data[0x6] == 0x1,
+            ripple_control: data[0x8] == 0x1,
+        })
+    }
 
-    pub fn set_stabilization_time(&mut self, value: Duration<Milliseconds>) {
-        self.set_data_byte_with_checksum(value.as_unit() as u8, 0x0)
-            .unwrap();
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        data[0x0] = STABILIZATION_TIME_RANGE.encode(self.stabilization_time)?;
+        data[0x2] = self.motion_sync as u8;
+        data[0x4] = CLOSE_LED_TIME_RANGE.encode(self.close_led_time)?;
+        data[0x6] = self.linear_correction as u8;
+        data[0x8] = self.ripple_control as u8;
+        Ok(())
+    }
+}
+
+#[command_extension]
+impl Command<MousePerfSettings> {
+    pub fn query() -> Self {
+        MousePerfSettings::query_command(())
+    }
+
+    pub fn builder() -> CommandBuilder<MousePerfSettings> {
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, ()))
+    }
+
+    pub fn config(self) -> MousePerfSettings {
+        MousePerfSettings::decode(self.eeprom_address(), self.data())
+            .expect("Invalid MousePerfSettings")
+    }
+
+    /// Like [`config`](Self::config), but fails on bad checksums.
+    pub fn try_config(self) -> Result<MousePerfSettings, Error> {
+        MousePerfSettings::from_bytes(self.eeprom_address(), self.data())
+    }
+
+    /// Fails if `value` is outside [`STABILIZATION_TIME_RANGE`].
+    pub fn try_set_stabilization_time(
+        &mut self,
+        value: Duration<Milliseconds>,
+    ) -> Result<(), Error> {
+        self.set_data_byte_with_checksum(STABILIZATION_TIME_RANGE.encode(value)?, 0x0)
     }
 
     pub fn set_motion_sync(&mut self, value: bool) {
         self.set_data_byte_with_checksum(value as u8, 0x2).unwrap();
     }
 
-    pub fn set_close_led_time(&mut self, value: Duration<Decaseconds>) {
-        self.set_data_byte_with_checksum(value.as_unit() as u8, 0x4)
-            .unwrap();
+    /// Fails if `value` is outside [`CLOSE_LED_TIME_RANGE`].
+    pub fn try_set_close_led_time(&mut self, value: Duration<Decaseconds>) -> Result<(), Error> {
+        self.set_data_byte_with_checksum(CLOSE_LED_TIME_RANGE.encode(value)?, 0x4)
     }
 
     pub fn set_linear_correction(&mut self, value: bool) {
@@ -134,7 +236,7 @@ impl Command<MousePerfSettings> {
     }
 }
 
-#[derive(Command, Default, Debug)]
+#[derive(Command, Default, Debug, Clone, PartialEq, Eq)]
 pub struct SensorPerfSettings {
     move_close_led: bool,
     sensor_sleep: bool,
@@ -157,23 +259,25 @@ impl std::fmt::Display for SensorPerfSettings {
     }
 }
 
-#[allow(dead_code)]
 impl SensorPerfSettings {
-    pub fn set(
-        &self,
-        move_close_led: Option<bool>,
-        sensor_sleep: Option<bool>,
-        sensor_sleep_time: Option<Duration<Decaseconds>>,
-        performance_mode: Option<bool>,
-        rf_tx_time: Option<Duration<Milliseconds>>,
-    ) -> Self {
-        SensorPerfSettings {
-            move_close_led: move_close_led.unwrap_or(self.move_close_led),
-            sensor_sleep: sensor_sleep.unwrap_or(self.sensor_sleep),
-            sensor_sleep_time: sensor_sleep_time.unwrap_or(self.sensor_sleep_time),
-            performance_mode: performance_mode.unwrap_or(self.performance_mode),
-            rf_tx_time: rf_tx_time.unwrap_or(self.rf_tx_time),
-        }
+    /// Returns a copy of these settings with every field present in `patch` replaced.
+    pub fn apply(&self, patch: &SensorPerfPatch) -> Result<Self, Error> {
+        patch
+            .sensor_sleep_time
+            .map(|value| SENSOR_SLEEP_TIME_RANGE.check(value))
+            .transpose()?;
+        patch
+            .rf_tx_time
+            .map(|value| RF_TX_TIME_RANGE.check(value))
+            .transpose()?;
+
+        Ok(SensorPerfSettings {
+            move_close_led: patch.move_close_led.unwrap_or(self.move_close_led),
+            sensor_sleep: patch.sensor_sleep.unwrap_or(self.sensor_sleep),
+            sensor_sleep_time: patch.sensor_sleep_time.unwrap_or(self.sensor_sleep_time),
+            performance_mode: patch.performance_mode.unwrap_or(self.performance_mode),
+            rf_tx_time: patch.rf_tx_time.unwrap_or(self.rf_tx_time),
+        })
     }
 
     pub fn move_close_led(&self) -> bool {
@@ -196,53 +300,135 @@ impl SensorPerfSettings {
         self.rf_tx_time
     }
 
-    pub fn builder(&self) -> CommandBuilder<Self> {
-        Command::<Self>::builder()
+    /// Fails if a timing is outside its range.
+    pub fn builder(&self) -> Result<CommandBuilder<Self>, Error> {
+        let mut command = Command::<Self>::builder()
             .move_close_led(self.move_close_led)
             .sensor_sleep(self.sensor_sleep)
-            .sensor_sleep_time(self.sensor_sleep_time)
             .performance_mode(self.performance_mode)
-            .rf_tx_time(self.rf_tx_time)
+            .build();
+        command.try_set_sensor_sleep_time(self.sensor_sleep_time)?;
+        command.try_set_rf_tx_time(self.rf_tx_time)?;
+
+        Ok(CommandBuilder::new(command))
     }
 }
 
-#[command_extension]
-impl Command<SensorPerfSettings> {
-    pub fn query() -> Self {
-        let mut instance = Command::default();
+/// A partial update of [`SensorPerfSettings`]. Fields left as `None` keep their current value.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct SensorPerfPatch {
+    pub move_close_led: Option<bool>,
+    pub sensor_sleep: Option<bool>,
+    pub sensor_sleep_time: Option<Duration<Decaseconds>>,
+    pub performance_mode: Option<bool>,
+    pub rf_tx_time: Option<Duration<Milliseconds>>,
+}
 
-        instance.set_id(CommandId::GetEEPROM);
-        instance.set_eeprom_address(EEPROMAddress::MoveCloseLights);
-        instance.set_data_len(0xA).unwrap();
+impl SensorPerfPatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        instance
+    pub fn move_close_led(mut self, value: bool) -> Self {
+        self.move_close_led = Some(value);
+        self
     }
 
-    pub fn builder() -> CommandBuilder<SensorPerfSettings> {
-        let mut command = Command::default();
+    pub fn sensor_sleep(mut self, value: bool) -> Self {
+        self.sensor_sleep = Some(value);
+        self
+    }
+
+    pub fn sensor_sleep_time(mut self, value: Duration<Decaseconds>) -> Self {
+        self.sensor_sleep_time = Some(value);
+        self
+    }
 
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::MoveCloseLights);
-        command.set_data_len(0xA).unwrap();
+    pub fn performance_mode(mut self, value: bool) -> Self {
+        self.performance_mode = Some(value);
+        self
+    }
 
-        CommandBuilder::new(command)
+    pub fn rf_tx_time(mut self, value: Duration<Milliseconds>) -> Self {
+        self.rf_tx_time = Some(value);
+        self
     }
 
-    pub fn config(self) -> SensorPerfSettings {
-        let move_close_led = self.data()[0x0] == 0x1;
-        let sensor_sleep = self.data()[0x2] == 0x1;
-        let sensor_sleep_time = Duration::<Decaseconds>::new(self.data()[0x4] as u32);
-        let performance_mode = self.data()[0x6] == 0x1;
-        let rf_tx_time = Duration::<Milliseconds>::new(self.data()[0x8] as u32);
-
-        SensorPerfSettings {
-            move_close_led,
-            sensor_sleep,
-            sensor_sleep_time,
-            performance_mode,
-            rf_tx_time,
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Combines two patches, fields set in `other` take precedence.
+    pub fn merge(self, other: SensorPerfPatch) -> Self {
+        SensorPerfPatch {
+            move_close_led: other.move_close_led.or(self.move_close_led),
+            sensor_sleep: other.sensor_sleep.or(self.sensor_sleep),
+            sensor_sleep_time: other.sensor_sleep_time.or(self.sensor_sleep_time),
+            performance_mode: other.performance_mode.or(self.performance_mode),
+            rf_tx_time: other.rf_tx_time.or(self.rf_tx_time),
         }
     }
+}
+
+impl EepromSetting for SensorPerfSettings {
+    type Key = ();
+
+    const NAME: &'static str = "SensorPerfSettings";
+    const LEN: usize = 0xA;
+    const CHECKSUM: Checksum = Checksum::Pairs;
+    const FIELDS: &'static [SettingField] = &[
+        SettingField::byte("move_close_led", 0x0),
+        SettingField::byte("sensor_sleep", 0x2),
+        SettingField::byte("sensor_sleep_time", 0x4),
+        SettingField::byte("performance_mode", 0x6),
+        SettingField::byte("rf_tx_time", 0x8),
+    ];
+
+    fn address(_: ()) -> EEPROMAddress {
+        EEPROMAddress::MoveCloseLights
+    }
+
+    fn key(&self) {}
+
+    fn decode(_: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        Ok(SensorPerfSettings {
+            move_close_led: data[0x0] == 0x1,
+            sensor_sleep: data[0x2] == 0x1,
+            sensor_sleep_time: Duration::new(data[0x4] as u32),
+            performance_mode: data[0x6] == 0x1,
+            rf_tx_time: Duration::new(data[0x8] as u32),
+        })
+    }
+
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        data[0x0] = self.move_close_led as u8;
+        data[0x2] = self.sensor_sleep as u8;
+        data[0x4] = SENSOR_SLEEP_TIME_RANGE.encode(self.sensor_sleep_time)?;
+        data[0x6] = self.performance_mode as u8;
+        data[0x8] = RF_TX_TIME_RANGE.encode(self.rf_tx_time)?;
+        Ok(())
+    }
+}
+
+#[command_extension]
+impl Command<SensorPerfSettings> {
+    pub fn query() -> Self {
+        SensorPerfSettings::query_command(())
+    }
+
+    pub fn builder() -> CommandBuilder<SensorPerfSettings> {
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, ()))
+    }
+
+    pub fn config(self) -> SensorPerfSettings {
+        SensorPerfSettings::decode(self.eeprom_address(), self.data())
+            .expect("Invalid SensorPerfSettings")
+    }
+
+    /// Like [`config`](Self::config), but fails on bad checksums.
+    pub fn try_config(self) -> Result<SensorPerfSettings, Error> {
+        SensorPerfSettings::from_bytes(self.eeprom_address(), self.data())
+    }
 
     pub fn set_move_close_led(&mut self, value: bool) {
         self.set_data_byte_with_checksum(value as u8, 0x0).unwrap();
@@ -252,17 +438,109 @@ impl Command<SensorPerfSettings> {
         self.set_data_byte_with_checksum(value as u8, 0x2).unwrap();
     }
 
-    pub fn set_sensor_sleep_time(&mut self, value: Duration<Decaseconds>) {
-        self.set_data_byte_with_checksum(value.as_unit() as u8, 0x4)
-            .unwrap();
+    /// Fails if `value` is outside [`SENSOR_SLEEP_TIME_RANGE`].
+    pub fn try_set_sensor_sleep_time(&mut self, value: Duration<Decaseconds>) -> Result<(), Error> {
+        self.set_data_byte_with_checksum(SENSOR_SLEEP_TIME_RANGE.encode(value)?, 0x4)
     }
 
     pub fn set_performance_mode(&mut self, value: bool) {
         self.set_data_byte_with_checksum(value as u8, 0x6).unwrap();
     }
 
-    pub fn set_rf_tx_time(&mut self, value: Duration<Milliseconds>) {
-        self.set_data_byte_with_checksum(value.as_unit() as u8, 0x8)
-            .unwrap();
+    /// Fails if `value` is outside [`RF_TX_TIME_RANGE`].
+    pub fn try_set_rf_tx_time(&mut self, value: Duration<Milliseconds>) -> Result<(), Error> {
+        self.set_data_byte_with_checksum(RF_TX_TIME_RANGE.encode(value)?, 0x8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn mouse_perf() -> impl Strategy<Value = MousePerfSettings> {
+        (
+            any::<u8>(),
+            any::<bool>(),
+            any::<u8>(),
+            any::<bool>(),
+            any::<bool>(),
+        )
+            .prop_map(
+                |(stabilization, motion_sync, close_led, linear_correction, ripple_control)| {
+                    MousePerfSettings {
+                        stabilization_time: Duration::new(stabilization as u32),
+                        motion_sync,
+                        close_led_time: Duration::new(close_led as u32),
+                        linear_correction,
+                        ripple_control,
+                    }
+                },
+            )
+    }
+
+    fn sensor_perf() -> impl Strategy<Value = SensorPerfSettings> {
+        (
+            any::<bool>(),
+            any::<bool>(),
+            any::<u8>(),
+            any::<bool>(),
+            any::<u8>(),
+        )
+            .prop_map(
+                |(move_close_led, sensor_sleep, sleep_time, performance_mode, rf_tx)| {
+                    SensorPerfSettings {
+                        move_close_led,
+                        sensor_sleep,
+                        sensor_sleep_time: Duration::new(sleep_time as u32),
+                        performance_mode,
+                        rf_tx_time: Duration::new(rf_tx as u32),
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn mouse_perf_round_trip(settings in mouse_perf()) {
+            let bytes = settings.builder().unwrap().build().as_bytes();
+            let command = Command::<MousePerfSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert_eq!(command.try_config().unwrap(), settings);
+        }
+
+        #[test]
+        fn corrupted_mouse_perf_is_rejected(
+            settings in mouse_perf(),
+            index in 5usize..15,
+            flip in 1u8..,
+        ) {
+            let mut bytes = settings.builder().unwrap().build().as_bytes();
+            bytes[index] ^= flip;
+            let command = Command::<MousePerfSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert!(command.try_config().is_err());
+        }
+
+        #[test]
+        fn sensor_perf_round_trip(settings in sensor_perf()) {
+            let bytes = settings.builder().unwrap().build().as_bytes();
+            let command = Command::<SensorPerfSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert_eq!(command.try_config().unwrap(), settings);
+        }
+
+        #[test]
+        fn corrupted_sensor_perf_is_rejected(
+            settings in sensor_perf(),
+            index in 5usize..15,
+            flip in 1u8..,
+        ) {
+            let mut bytes = settings.builder().unwrap().build().as_bytes();
+            bytes[index] ^= flip;
+            let command = Command::<SensorPerfSettings>::try_from(&bytes[..]).unwrap();
+
+            prop_assert!(command.try_config().is_err());
+        }
     }
 }
@@ -0,0 +1,236 @@
+use super::checksum::check_pairs;
+use libatk_rs::prelude::*;
+
+/// How the bytes of a setting are protected against corruption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checksum {
+    /// Every value byte is followed by `0x55 - value`, as written by
+    /// `set_data_byte_with_checksum`.
+    Pairs,
+    /// Each field carries its own checksum, which [`EepromSetting::decode`] checks.
+    PerField,
+}
+
+/// A named value inside a setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettingField {
+    pub name: &'static str,
+    /// Offset of the value from the start of the setting.
+    pub offset: usize,
+    /// Bytes taken by the value, checksums included for [`Checksum::PerField`].
+    pub len: usize,
+}
+
+impl SettingField {
+    /// A one byte value, followed by its checksum in a [`Checksum::Pairs`] setting.
+    pub const fn byte(name: &'static str, offset: usize) -> Self {
+        SettingField {
+            name,
+            offset,
+            len: 1,
+        }
+    }
+
+    pub const fn new(name: &'static str, offset: usize, len: usize) -> Self {
+        SettingField { name, offset, len }
+    }
+}
+
+/// A setting stored in a fixed region of the EEPROM.
+///
+/// Implementors declare where the setting lives and how its fields are laid out; building
+/// the `GetEEPROM`/`SetEEPROM` commands and handling checksums is shared.
+pub trait EepromSetting: CommandDescriptor + Clone + PartialEq + Sized {
+    /// Tells apart settings sharing a layout at different addresses, `()` for unique ones.
+    type Key: Copy + PartialEq + std::fmt::Debug;
+
+    const NAME: &'static str;
+    /// Bytes read and written at once, checksums included.
+    const LEN: usize;
+    const CHECKSUM: Checksum;
+    const FIELDS: &'static [SettingField];
+
+    fn address(key: Self::Key) -> EEPROMAddress;
+
+    fn key(&self) -> Self::Key;
+
+    /// Parses the values at their offsets in `data`, which holds [`Self::LEN`] bytes read from
+    /// `address`. [`Checksum::Pairs`] are not checked here, see [`EepromSetting::from_bytes`].
+    fn decode(address: EEPROMAddress, data: &[u8]) -> Result<Self, Error>;
+
+    /// Writes the values at their offsets in `data`, leaving [`Checksum::Pairs`] to the caller.
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error>;
+
+    /// The bytes stored on the device, checksums included.
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut data = vec![0; Self::LEN];
+        self.encode(&mut data)?;
+
+        if Self::CHECKSUM == Checksum::Pairs {
+            for pair in data.chunks_exact_mut(2) {
+                pair[1] = 0x55u8.wrapping_sub(pair[0]);
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Like [`decode`](Self::decode), but fails on bad checksums and short data.
+    fn from_bytes(address: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        let data = payload::<Self>(data)?;
+
+        if Self::CHECKSUM == Checksum::Pairs {
+            check_pairs(Self::NAME, data)?;
+        }
+
+        Self::decode(address, data)
+    }
+
+    /// Parses the data of a `GetEEPROM` or `SetEEPROM` response like [`decode`](Self::decode),
+    /// failing instead of panicking when the device sent too few bytes.
+    fn from_response(response: &Command<Self>) -> Result<Self, Error> {
+        Self::decode(response.eeprom_address(), payload::<Self>(response.data())?)
+    }
+
+    /// A `GetEEPROM` of the setting stored under `key`.
+    fn query_command(key: Self::Key) -> Command<Self> {
+        eeprom_command(CommandId::GetEEPROM, key)
+    }
+
+    /// A `SetEEPROM` storing this setting.
+    fn write_command(&self) -> Result<Command<Self>, Error> {
+        let mut command = eeprom_command(CommandId::SetEEPROM, self.key());
+        command.set_data(&self.to_bytes()?, 0x0)?;
+
+        Ok(command)
+    }
+
+    /// Fields whose stored bytes differ between `self` and `other`.
+    fn diff(&self, other: &Self) -> Result<Vec<&'static SettingField>, Error> {
+        let (ours, theirs) = (self.to_bytes()?, other.to_bytes()?);
+        let range = |field: &SettingField| field.offset..field.offset + field.len;
+
+        Ok(Self::FIELDS
+            .iter()
+            .filter(|field| ours[range(field)] != theirs[range(field)])
+            .collect())
+    }
+}
+
+/// An empty `GetEEPROM` or `SetEEPROM` command for the setting stored under `key`.
+pub fn eeprom_command<T: EepromSetting>(id: CommandId, key: T::Key) -> Command<T> {
+    let mut command = Command::default();
+
+    command.set_id(id);
+    command.set_eeprom_address(T::address(key));
+    command.set_data_len(T::LEN).unwrap();
+
+    command
+}
+
+/// The first [`EepromSetting::LEN`] bytes of `data`.
+fn payload<T: EepromSetting>(data: &[u8]) -> Result<&[u8], Error> {
+    data.get(..T::LEN).ok_or_else(|| {
+        Error::ParseError(format!(
+            "{}: Invalid data length: expected {} got {}",
+            T::NAME,
+            T::LEN,
+            data.len()
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        color::Color, commands::prelude::*, manager::MouseManager,
+        transport::simulated::SimulatedMouse, types::Duration,
+    };
+
+    fn assert_same_frame<T: EepromSetting>(setting: &T, legacy: Command<T>) {
+        assert_eq!(
+            setting.write_command().unwrap().as_bytes(),
+            legacy.as_bytes(),
+            "{}",
+            T::NAME
+        );
+    }
+
+    #[test]
+    fn generic_frames_match_the_builders() {
+        let led = DpiLedSettings::default().apply(
+            &LedPatch::new()
+                .mode(LedEffectMode::Breathing)
+                .brightness(LedBrightnessLevel::High),
+        );
+        assert_same_frame(&led, led.builder().build());
+
+        let mouse = MousePerfSettings::default()
+            .apply(
+                &MousePerfPatch::new()
+                    .stabilization_time(Duration::new(8))
+                    .motion_sync(true),
+            )
+            .unwrap();
+        assert_same_frame(&mouse, mouse.builder().unwrap().build());
+
+        let sensor = SensorPerfSettings::default()
+            .apply(&SensorPerfPatch::new().rf_tx_time(Duration::new(4)))
+            .unwrap();
+        assert_same_frame(&sensor, sensor.builder().unwrap().build());
+
+        let height = SilentHeight::from(SilentHeightMode::TwoMm);
+        assert_same_frame(&height, height.builder().build());
+
+        let info = MouseInfo::default()
+            .with_poll_rate(PollingRate::Hz500)
+            .with_active_profile(3);
+        assert_same_frame(&info, info.builder().build());
+
+        let dpi = DpiPairSetting::from(Pair::Pair3).with_dpi(Dpi::new(26000), Slot::Second);
+        assert_same_frame(&dpi, dpi.builder().build());
+
+        let color =
+            ColorPairSetting::from(Pair::Pair2).with_color(Color::new(1, 2, 3), Slot::First);
+        assert_same_frame(&color, color.builder().build());
+    }
+
+    #[test]
+    fn diff_names_the_changed_fields() {
+        let before = DpiLedSettings::default();
+        let after = before.apply(&LedPatch::new().enabled(false));
+        let names: Vec<_> = before
+            .diff(&after)
+            .unwrap()
+            .iter()
+            .map(|field| field.name)
+            .collect();
+        assert_eq!(names, ["enabled"]);
+
+        let pair = DpiPairSetting::from(Pair::Pair1);
+        let changed = pair.with_dpi(Dpi::new(800), Slot::First);
+        assert_eq!(pair.diff(&changed).unwrap()[0].name, "dpi_first");
+        assert!(pair.diff(&pair).unwrap().is_empty());
+    }
+
+    #[test]
+    fn manager_writes_and_verifies_any_setting() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+
+        let setting = manager
+            .setting::<ColorPairSetting>(Pair::Pair4)
+            .with_color(Color::new(0x10, 0x20, 0x30), Slot::Second);
+        manager.write_setting(&setting).unwrap();
+        manager.verify_setting(&setting).unwrap();
+        assert_eq!(
+            manager
+                .load_setting::<ColorPairSetting>(Pair::Pair4)
+                .unwrap(),
+            setting
+        );
+
+        let stale = setting.with_color(Color::new(0, 0, 0), Slot::Second);
+        assert!(manager.verify_setting(&stale).is_err());
+    }
+}
@@ -1,6 +1,8 @@
+use super::setting::{eeprom_command, Checksum, EepromSetting, SettingField};
 use libatk_rs::prelude::*;
 
-#[derive(Default, Debug, Copy, Clone)]
+#[cfg_attr(feature = "python", pyo3::pyclass(eq, eq_int, module = "atk_hub"))]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum SilentHeightMode {
     #[default]
@@ -20,10 +22,15 @@ impl From<u8> for SilentHeightMode {
     }
 }
 
-#[derive(Command, Default, Debug)]
+#[derive(Command, Default, Debug, Clone, PartialEq, Eq)]
 pub struct SilentHeight(SilentHeightMode);
 
-#[allow(dead_code)]
+impl From<SilentHeightMode> for SilentHeight {
+    fn from(mode: SilentHeightMode) -> Self {
+        SilentHeight(mode)
+    }
+}
+
 impl SilentHeight {
     pub fn silent_height(&self) -> SilentHeightMode {
         self.0
@@ -34,33 +41,104 @@ impl SilentHeight {
     }
 }
 
-#[command_extension]
-impl Command<SilentHeight> {
-    pub fn query() -> Self {
-        let mut command = Command::default();
+impl EepromSetting for SilentHeight {
+    type Key = ();
 
-        command.set_id(CommandId::GetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::SilentHeight);
-        command.set_data_len(0x2).unwrap();
+    const NAME: &'static str = "SilentHeight";
+    const LEN: usize = 0x2;
+    const CHECKSUM: Checksum = Checksum::Pairs;
+    const FIELDS: &'static [SettingField] = &[SettingField::byte("silent_height", 0x0)];
 
-        command
+    fn address(_: ()) -> EEPROMAddress {
+        EEPROMAddress::SilentHeight
     }
 
-    pub fn builder() -> CommandBuilder<SilentHeight> {
-        let mut command = Command::default();
+    fn key(&self) {}
+
+    fn decode(_: EEPROMAddress, data: &[u8]) -> Result<Self, Error> {
+        match data[0x0] {
+            value @ 0x00..=0x02 => Ok(SilentHeight(value.into())),
+            value => Err(Error::ParseError(format!(
+                "SilentHeight: Invalid value: {:#04x}",
+                value
+            ))),
+        }
+    }
 
-        command.set_id(CommandId::SetEEPROM);
-        command.set_eeprom_address(EEPROMAddress::SilentHeight);
-        command.set_data_len(0x2).unwrap();
+    fn encode(&self, data: &mut [u8]) -> Result<(), Error> {
+        data[0x0] = self.silent_height() as u8;
+        Ok(())
+    }
+}
 
-        CommandBuilder::new(command)
+#[command_extension]
+impl Command<SilentHeight> {
+    pub fn query() -> Self {
+        SilentHeight::query_command(())
+    }
+
+    pub fn builder() -> CommandBuilder<SilentHeight> {
+        CommandBuilder::new(eeprom_command(CommandId::SetEEPROM, ()))
     }
 
     pub fn config(self) -> SilentHeight {
         SilentHeight(self.data()[0x0].into())
     }
 
+    /// Like [`config`](Self::config), but fails on bad checksums and unknown values.
+    pub fn try_config(self) -> Result<SilentHeight, Error> {
+        SilentHeight::from_bytes(self.eeprom_address(), self.data())
+    }
+
     pub fn set_silent_height(&mut self, mode: SilentHeightMode) {
         self.set_data_byte_with_checksum(mode as u8, 0x0).unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [SilentHeightMode; 3] = [
+        SilentHeightMode::Off,
+        SilentHeightMode::OneMm,
+        SilentHeightMode::TwoMm,
+    ];
+
+    fn reparse(bytes: &[u8]) -> Command<SilentHeight> {
+        Command::try_from(bytes).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        for mode in MODES {
+            let height = SilentHeight::from(mode);
+            let bytes = height.builder().build().as_bytes();
+
+            assert_eq!(reparse(&bytes).try_config().unwrap(), height);
+        }
+    }
+
+    #[test]
+    fn corrupted_bytes_are_rejected() {
+        for mode in MODES {
+            for index in 5..7 {
+                for flip in 1..=u8::MAX {
+                    let mut bytes = SilentHeight::from(mode).builder().build().as_bytes();
+                    bytes[index] ^= flip;
+
+                    assert!(reparse(&bytes).try_config().is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_heights_are_rejected() {
+        let mut bytes = SilentHeight::default().builder().build().as_bytes();
+        bytes[5] = 0x03;
+        bytes[6] = 0x55 - 0x03;
+
+        assert!(reparse(&bytes).try_config().is_err());
+    }
+}
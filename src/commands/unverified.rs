@@ -0,0 +1,159 @@
+//! Unverified frames for every encoder.
+//!
+//! These pin the bytes sent to the mouse so an encoder change can not slip through
+//! unnoticed, but they were produced by the encoders themselves and cross-checked against
+//! the checksum rules only. None of them has been compared with a capture from a real device,
+//! so they only catch changes, not mistakes. Real captures go in `tests/fixtures/captures`,
+//! where `real_captures_decode_cleanly` in `crate::capture` checks them: when a capture
+//! disagrees with a frame here, the capture wins and the encoder (and this table) is fixed.
+
+use super::prelude::*;
+use crate::{color::Color, types::Duration};
+use libatk_rs::prelude::*;
+
+fn assert_frame<T: CommandDescriptor>(command: Command<T>, expected: [u8; 16]) {
+    assert_eq!(command.as_bytes(), expected, "{:02x?}", command.as_bytes());
+}
+
+#[test]
+fn color() {
+    let bytes: [u8; 4] = Color::new(0x12, 0x34, 0x56).into();
+    assert_eq!(bytes, [0x12, 0x34, 0x56, 0xb9]);
+}
+
+#[test]
+fn dpi() {
+    let bytes: [u8; 4] = Dpi::new(26000).into();
+    assert_eq!(bytes, [0x07, 0x07, 0x88, 0xbf]);
+}
+
+#[test]
+fn dpi_pair() {
+    assert_frame(
+        Command::<DpiPairSetting>::builder(Pair::Pair2)
+            .dpi(Dpi::new(800), Slot::First)
+            .dpi(Dpi::new(26000), Slot::Second)
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0x14, 0x08, 0x0f, 0x0f, 0x00, 0x37, 0x07, 0x07, 0x88, 0xbf, 0x00,
+            0x00, 0x80,
+        ],
+    );
+}
+
+#[test]
+fn color_pair() {
+    assert_frame(
+        Command::<ColorPairSetting>::builder(Pair::Pair1)
+            .color(Color::new(0xff, 0x00, 0x00), Slot::First)
+            .color(Color::new(0x00, 0x80, 0xff), Slot::Second)
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0x2c, 0x08, 0xff, 0x00, 0x00, 0x56, 0x00, 0x80, 0xff, 0xd6, 0x00,
+            0x00, 0x68,
+        ],
+    );
+}
+
+#[test]
+fn dpi_led() {
+    assert_frame(
+        Command::<DpiLedSettings>::builder()
+            .effect_mode(LedEffectMode::Breathing)
+            .brightness_level(LedBrightnessLevel::High)
+            .breathing_rate(LedBreathingRate::Fast)
+            .enabled(true)
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0x4c, 0x08, 0x02, 0x53, 0xff, 0x56, 0x05, 0x50, 0x01, 0x54, 0x00,
+            0x00, 0x9e,
+        ],
+    );
+}
+
+#[test]
+fn mouse_perf() {
+    assert_frame(
+        MousePerfSettings::default()
+            .apply(
+                &MousePerfPatch::new()
+                    .stabilization_time(Duration::new(8))
+                    .motion_sync(true)
+                    .close_led_time(Duration::new(6))
+                    .linear_correction(false)
+                    .ripple_control(true),
+            )
+            .unwrap()
+            .builder()
+            .unwrap()
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0xa9, 0x0a, 0x08, 0x4d, 0x01, 0x54, 0x06, 0x4f, 0x00, 0x55, 0x01,
+            0x54, 0xea,
+        ],
+    );
+}
+
+#[test]
+fn sensor_perf() {
+    assert_frame(
+        SensorPerfSettings::default()
+            .apply(
+                &SensorPerfPatch::new()
+                    .move_close_led(true)
+                    .sensor_sleep(true)
+                    .sensor_sleep_time(Duration::new(30))
+                    .performance_mode(false)
+                    .rf_tx_time(Duration::new(20)),
+            )
+            .unwrap()
+            .builder()
+            .unwrap()
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0xb3, 0x0a, 0x01, 0x54, 0x01, 0x54, 0x1e, 0x37, 0x00, 0x55, 0x14,
+            0x41, 0xe0,
+        ],
+    );
+}
+
+#[test]
+fn mouse_info() {
+    assert_frame(
+        Command::<MouseInfo>::builder()
+            .poll_rate(PollingRate::Hz4000)
+            .num_profile(4)
+            .active_profile(2)
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0x00, 0x06, 0x20, 0x35, 0x04, 0x51, 0x02, 0x53, 0x00, 0x00, 0x00,
+            0x00, 0x41,
+        ],
+    );
+}
+
+#[test]
+fn silent_height() {
+    assert_frame(
+        Command::<SilentHeight>::builder()
+            .silent_height(SilentHeightMode::TwoMm)
+            .build(),
+        [
+            0x07, 0x00, 0x00, 0x0a, 0x02, 0x02, 0x53, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0xe5,
+        ],
+    );
+}
+
+#[test]
+fn far_distance() {
+    assert_frame(
+        Command::<FarDistanceMode>::builder()
+            .far_distance_mode(true)
+            .build(),
+        [
+            0x16, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x36,
+        ],
+    );
+}
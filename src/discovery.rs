@@ -0,0 +1,92 @@
+use libatk_rs::prelude::*;
+
+/// First vendor defined HID usage page. The configuration interface of ATK mice is
+/// vendor defined, the mouse and keyboard interfaces are not.
+pub const VENDOR_USAGE_PAGE: u16 = 0xff00;
+
+/// A HID interface that may accept ATK configuration commands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredDevice {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usage_page: u16,
+    pub usage: u16,
+    pub manufacturer: String,
+    pub product: String,
+}
+
+impl std::fmt::Display for DiscoveredDevice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04x}:{:04x}:{:04x}:{:04x} {} {}",
+            self.vendor_id,
+            self.product_id,
+            self.usage_page,
+            self.usage,
+            self.manufacturer,
+            self.product
+        )
+    }
+}
+
+impl DiscoveredDevice {
+    pub fn open(&self) -> Result<Device, Error> {
+        Device::new(self.vendor_id, self.product_id, self.usage_page, self.usage)
+    }
+}
+
+/// Lists every HID interface with a vendor defined usage page.
+///
+/// The protocol has no identification handshake, so this can include interfaces of other
+/// vendors' devices. Each interface is listed once even if several paths lead to it.
+pub fn discover() -> Result<Vec<DiscoveredDevice>, Error> {
+    let context = hidapi::HidApi::new().map_err(Error::HidError)?;
+
+    let mut devices: Vec<DiscoveredDevice> = Vec::new();
+    for info in context.device_list() {
+        if info.usage_page() < VENDOR_USAGE_PAGE {
+            continue;
+        }
+
+        let device = DiscoveredDevice {
+            vendor_id: info.vendor_id(),
+            product_id: info.product_id(),
+            usage_page: info.usage_page(),
+            usage: info.usage(),
+            manufacturer: info.manufacturer_string().unwrap_or_default().to_string(),
+            product: info.product_string().unwrap_or_default().to_string(),
+        };
+        if !devices.contains(&device) {
+            devices.push(device);
+        }
+    }
+
+    Ok(devices)
+}
+
+/// Serial number reported by the interface with the given identifiers, if any.
+///
+/// Dongles usually report the serial of the dongle rather than of the paired mouse.
+pub fn serial_number(
+    vendor_id: u16,
+    product_id: u16,
+    usage_page: u16,
+    usage: u16,
+) -> Result<Option<String>, Error> {
+    let context = hidapi::HidApi::new().map_err(Error::HidError)?;
+
+    let serial = context
+        .device_list()
+        .find(|info| {
+            info.vendor_id() == vendor_id
+                && info.product_id() == product_id
+                && info.usage_page() == usage_page
+                && info.usage() == usage
+        })
+        .and_then(|info| info.serial_number())
+        .filter(|serial| !serial.is_empty())
+        .map(str::to_string);
+
+    Ok(serial)
+}
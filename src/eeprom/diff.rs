@@ -0,0 +1,384 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+
+use super::{label, region_of, DeviceIdentity, EepromDump, MAX_CHUNK_LEN};
+use libatk_rs::prelude::*;
+
+/// How a changed byte relates to its neighbours, based on the checksum schemes used by
+/// the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ByteRole {
+    /// A value followed by its `0x55 - value` checksum, as written by
+    /// `set_data_byte_with_checksum`.
+    PairValue,
+    /// The `0x55 - value` checksum of the previous byte.
+    PairChecksum,
+    /// Byte `index` of a group of three values followed by `0x55 - a - b - c`,
+    /// like DPI and colour entries.
+    GroupValue {
+        index: u8,
+    },
+    /// The checksum closing a group of three values.
+    GroupChecksum,
+    Unknown,
+}
+
+impl std::fmt::Display for ByteRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ByteRole::PairValue => write!(f, "value, checksum at +1"),
+            ByteRole::PairChecksum => write!(f, "checksum of -1"),
+            ByteRole::GroupValue { index } => {
+                write!(f, "group value {}, checksum at +{}", index, 3 - index)
+            }
+            ByteRole::GroupChecksum => write!(f, "group checksum of -3..-1"),
+            ByteRole::Unknown => write!(f, "no checksum pattern"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByteChange {
+    pub address: u16,
+    pub label: String,
+    pub before: u8,
+    pub after: u8,
+    pub role: ByteRole,
+}
+
+/// The smallest command layout covering a run of changed bytes, i.e. what a new command
+/// module would need to declare.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    /// The known address a command must target to reach the changed bytes.
+    pub address: u16,
+    pub region: String,
+    /// Data length covering every changed byte, at most 10.
+    pub data_len: usize,
+    /// Offsets relative to `address` of the changed values, checksums excluded.
+    pub value_offsets: Vec<usize>,
+    pub uses_pair_checksum: bool,
+    pub uses_group_checksum: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EepromDiff {
+    pub before_device: DeviceIdentity,
+    pub after_device: DeviceIdentity,
+    pub changes: Vec<ByteChange>,
+    pub findings: Vec<Finding>,
+    /// Addresses present in only one of the two dumps.
+    pub missing: Vec<u16>,
+}
+
+fn pair_checksum(value: u8) -> u8 {
+    0x55u8.wrapping_sub(value)
+}
+
+fn group_checksum(values: [u8; 3]) -> u8 {
+    values.iter().fold(0x55u8, |acc, &v| acc.wrapping_sub(v))
+}
+
+/// Classifies the byte at `address` by checking which checksum schemes its neighbours satisfy.
+fn classify(bytes: &BTreeMap<u16, u8>, address: u16) -> ByteRole {
+    let at = |a: u16| bytes.get(&a).copied();
+    let value = at(address);
+
+    if let (Some(v), Some(next)) = (value, address.checked_add(1).and_then(at)) {
+        if next == pair_checksum(v) {
+            return ByteRole::PairValue;
+        }
+    }
+    if let (Some(v), Some(prev)) = (value, address.checked_sub(1).and_then(at)) {
+        if v == pair_checksum(prev) {
+            return ByteRole::PairChecksum;
+        }
+    }
+
+    for index in 0..=3u16 {
+        let Some(start) = address.checked_sub(index) else {
+            continue;
+        };
+        let group: Option<Vec<u8>> = (start..start + 4).map(at).collect();
+        if let Some(group) = group {
+            if group[3] == group_checksum([group[0], group[1], group[2]]) {
+                return match index {
+                    3 => ByteRole::GroupChecksum,
+                    index => ByteRole::GroupValue { index: index as u8 },
+                };
+            }
+        }
+    }
+
+    ByteRole::Unknown
+}
+
+impl EepromDiff {
+    pub fn between(before: &EepromDump, after: &EepromDump) -> Result<Self, Error> {
+        let old = before.bytes()?;
+        let new = after.bytes()?;
+
+        let mut changes = Vec::new();
+        let mut missing = Vec::new();
+        let addresses: BTreeSet<u16> = old.keys().chain(new.keys()).copied().collect();
+        for address in addresses {
+            match (old.get(&address), new.get(&address)) {
+                (Some(&before), Some(&after)) if before != after => changes.push(ByteChange {
+                    address,
+                    label: label(address),
+                    before,
+                    after,
+                    role: classify(&new, address),
+                }),
+                (Some(_), Some(_)) => {}
+                _ => missing.push(address),
+            }
+        }
+
+        let findings = findings(&changes);
+
+        Ok(EepromDiff {
+            before_device: before.device,
+            after_device: after.device,
+            changes,
+            findings,
+            missing,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Groups changes by the known address that can reach them in a single command.
+fn findings(changes: &[ByteChange]) -> Vec<Finding> {
+    let mut findings: Vec<Finding> = Vec::new();
+
+    for change in changes {
+        // A checksum may sit at a known address of its own, it still belongs to its value.
+        let checksum = matches!(
+            change.role,
+            ByteRole::PairChecksum | ByteRole::GroupChecksum
+        );
+        let covered = findings
+            .last()
+            .is_some_and(|finding| change.address < finding.address + finding.data_len as u16);
+        if checksum && covered {
+            continue;
+        }
+
+        let Some((region, offset)) = region_of(change.address) else {
+            continue;
+        };
+        // Bytes further than one command away from any known address cannot be written
+        // by a dedicated command, they are still listed in the changes.
+        if offset as usize >= MAX_CHUNK_LEN {
+            continue;
+        }
+
+        let finding = match findings.last_mut() {
+            Some(finding) if finding.address == region as u16 => finding,
+            _ => {
+                findings.push(Finding {
+                    address: region as u16,
+                    region: format!("{:?}", region),
+                    data_len: 0,
+                    value_offsets: Vec::new(),
+                    uses_pair_checksum: false,
+                    uses_group_checksum: false,
+                });
+                findings.last_mut().unwrap()
+            }
+        };
+
+        let offset = offset as usize;
+        finding.data_len = finding.data_len.max(offset + 1);
+        match change.role {
+            ByteRole::PairValue => {
+                finding.uses_pair_checksum = true;
+                finding.data_len = finding.data_len.max(offset + 2).min(MAX_CHUNK_LEN);
+                finding.value_offsets.push(offset);
+            }
+            ByteRole::PairChecksum => finding.uses_pair_checksum = true,
+            ByteRole::GroupValue { index } => {
+                finding.uses_group_checksum = true;
+                let checksum = offset + (3 - index as usize);
+                finding.data_len = finding.data_len.max(checksum + 1).min(MAX_CHUNK_LEN);
+                finding.value_offsets.push(offset);
+            }
+            ByteRole::GroupChecksum => finding.uses_group_checksum = true,
+            ByteRole::Unknown => finding.value_offsets.push(offset),
+        }
+    }
+
+    findings
+}
+
+impl std::fmt::Display for EepromDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.before_device != self.after_device {
+            writeln!(
+                f,
+                "Warning: comparing different devices ({}) and ({})",
+                self.before_device, self.after_device
+            )?;
+        }
+
+        if !self.missing.is_empty() {
+            writeln!(
+                f,
+                "{} addresses were only read in one of the snapshots",
+                self.missing.len()
+            )?;
+        }
+
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+
+        for change in &self.changes {
+            writeln!(
+                f,
+                "{:#06x}  {:02x} -> {:02x}  {:<28} {}",
+                change.address, change.before, change.after, change.label, change.role
+            )?;
+        }
+
+        for finding in &self.findings {
+            writeln!(
+                f,
+                "\nCandidate command: address {} ({:#06x}), data length {:#x}, value offsets {:x?}{}{}",
+                finding.region,
+                finding.address,
+                finding.data_len,
+                finding.value_offsets,
+                if finding.uses_pair_checksum { ", pair checksums" } else { "" },
+                if finding.uses_group_checksum { ", group checksums" } else { "" },
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eeprom::{Chunk, DUMP_FORMAT};
+
+    fn bytes(start: u16, data: &[u8]) -> BTreeMap<u16, u8> {
+        (start..).zip(data.iter().copied()).collect()
+    }
+
+    fn dump(chunks: &[(EEPROMAddress, &[u8])]) -> EepromDump {
+        EepromDump {
+            format: DUMP_FORMAT,
+            device: DeviceIdentity {
+                cid: 0x03,
+                mid: 0x11,
+                firmware_major: 0x01,
+                firmware_minor: 0x07,
+            },
+            created: 0,
+            chunks: chunks
+                .iter()
+                .map(|&(address, data)| Chunk::new(address, data))
+                .collect(),
+            unreachable: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn bytes_are_classified_by_their_checksums() {
+        let pair = bytes(0xa9, &[0x08, 0x4d]);
+        assert_eq!(classify(&pair, 0xa9), ByteRole::PairValue);
+        assert_eq!(classify(&pair, 0xaa), ByteRole::PairChecksum);
+
+        let group = bytes(0x0c, &[0x10, 0x20, 0x05, 0x20]);
+        assert_eq!(classify(&group, 0x0c), ByteRole::GroupValue { index: 0 });
+        assert_eq!(classify(&group, 0x0d), ByteRole::GroupValue { index: 1 });
+        assert_eq!(classify(&group, 0x0e), ByteRole::GroupValue { index: 2 });
+        assert_eq!(classify(&group, 0x0f), ByteRole::GroupChecksum);
+
+        let unknown = bytes(0x100, &[0x12, 0x34]);
+        assert_eq!(classify(&unknown, 0x100), ByteRole::Unknown);
+        assert_eq!(classify(&unknown, 0x101), ByteRole::Unknown);
+    }
+
+    #[test]
+    fn findings_cover_each_changed_region() {
+        let before = dump(&[
+            (
+                EEPROMAddress::DpiPair1,
+                &[0x10, 0x20, 0x05, 0x20, 0, 0, 0, 0x55],
+            ),
+            (EEPROMAddress::StabilizationTime, &[0x08, 0x4d, 0x01, 0x54]),
+        ]);
+        let after = dump(&[
+            (
+                EEPROMAddress::DpiPair1,
+                &[0x10, 0x30, 0x05, 0x10, 0, 0, 0, 0x55],
+            ),
+            (EEPROMAddress::StabilizationTime, &[0x08, 0x4d, 0x00, 0x55]),
+            (EEPROMAddress::KeyShortcuts0, &[0x01]),
+        ]);
+
+        let diff = EepromDiff::between(&before, &after).unwrap();
+        let changed: Vec<(u16, ByteRole)> = diff
+            .changes
+            .iter()
+            .map(|change| (change.address, change.role))
+            .collect();
+        assert_eq!(
+            changed,
+            [
+                (0x0d, ByteRole::GroupValue { index: 1 }),
+                (0x0f, ByteRole::GroupChecksum),
+                (0xab, ByteRole::PairValue),
+                (0xac, ByteRole::PairChecksum),
+            ]
+        );
+        assert_eq!(diff.missing, [0x100]);
+
+        let [dpi, motion_sync] = &diff.findings[..] else {
+            panic!("expected two findings, got {:?}", diff.findings);
+        };
+        assert_eq!(
+            (dpi.region.as_str(), dpi.data_len, &dpi.value_offsets[..]),
+            ("DpiPair1", 4, &[1][..])
+        );
+        assert!(dpi.uses_group_checksum && !dpi.uses_pair_checksum);
+        assert_eq!(
+            (
+                motion_sync.region.as_str(),
+                motion_sync.data_len,
+                &motion_sync.value_offsets[..]
+            ),
+            ("MotionSync", 2, &[0][..])
+        );
+        assert!(motion_sync.uses_pair_checksum && !motion_sync.uses_group_checksum);
+
+        let report = diff.to_string();
+        assert!(report.contains("1 addresses were only read in one of the snapshots"));
+        assert!(report.contains(
+            "Candidate command: address MotionSync (0x00ab), data length 0x2, value offsets [0], pair checksums"
+        ));
+    }
+
+    #[test]
+    fn identical_dumps_have_no_findings() {
+        let dump = dump(&[(EEPROMAddress::StabilizationTime, &[0x08, 0x4d])]);
+        let diff = EepromDiff::between(&dump, &dump).unwrap();
+
+        assert!(diff.is_empty());
+        assert!(diff.findings.is_empty());
+        assert_eq!(diff.to_string(), "No changes");
+    }
+}
@@ -0,0 +1,598 @@
+pub mod diff;
+
+use std::ops::Range;
+
+use crate::{commands::prelude::*, manager::MouseManager};
+use libatk_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Maximum number of data bytes carried by a single `GetEEPROM`/`SetEEPROM` command.
+pub const MAX_CHUNK_LEN: usize = 0xA;
+
+/// End of the last known region (`Macro15` spans 0x180 bytes).
+pub const EEPROM_END: u16 = 0x1b00;
+
+/// Version of the JSON dump format, bumped on incompatible changes.
+pub const DUMP_FORMAT: u32 = 1;
+
+/// Every address the protocol knows about, in ascending order.
+///
+/// Commands can only target these addresses, so they are also the only possible
+/// starting points for a read or write.
+pub fn known_addresses() -> impl Iterator<Item = EEPROMAddress> {
+    (0..EEPROM_END).filter_map(|address| EEPROMAddress::try_from(address).ok())
+}
+
+/// Returns the closest known address at or below `address` along with the offset into it.
+pub fn region_of(address: u16) -> Option<(EEPROMAddress, u16)> {
+    (0..=address)
+        .rev()
+        .find_map(|start| EEPROMAddress::try_from(start).ok())
+        .map(|region| (region, address - region as u16))
+}
+
+/// Describes `address` relative to the known region containing it, e.g. `DpiPair1+3`.
+pub fn label(address: u16) -> String {
+    match region_of(address) {
+        Some((region, 0)) => format!("{:?}", region),
+        Some((region, offset)) => format!("{:?}+{}", region, offset),
+        None => format!("{:#06x}", address),
+    }
+}
+
+/// A plan of reads (or writes) covering a range of the EEPROM.
+#[derive(Debug, Default)]
+pub struct ChunkPlan {
+    pub chunks: Vec<(EEPROMAddress, usize)>,
+    /// Bytes that can not be reached because no known address starts close enough.
+    pub unreachable: Vec<u16>,
+}
+
+impl ChunkPlan {
+    /// Covers `ranges` with as few chunks as possible, each starting at a known address.
+    pub fn new(ranges: impl IntoIterator<Item = Range<u16>>) -> Self {
+        let mut plan = ChunkPlan::default();
+        ranges.into_iter().for_each(|range| plan.extend(range));
+        plan
+    }
+
+    /// Covers the bytes reachable from every known address.
+    pub fn known() -> Self {
+        let mut plan = ChunkPlan::default();
+        let mut covered: Option<Range<u16>> = None;
+
+        for address in known_addresses() {
+            let start = address as u16;
+            let end = start + MAX_CHUNK_LEN as u16;
+            covered = match covered {
+                Some(range) if start <= range.end => Some(range.start..range.end.max(end)),
+                Some(range) => {
+                    plan.extend(range);
+                    Some(start..end)
+                }
+                None => Some(start..end),
+            };
+        }
+
+        if let Some(range) = covered {
+            plan.extend(range);
+        }
+
+        plan
+    }
+
+    pub fn extend(&mut self, range: Range<u16>) {
+        let mut cursor = range.start;
+        while cursor < range.end {
+            let start = (cursor.saturating_sub(MAX_CHUNK_LEN as u16 - 1)..=cursor)
+                .rev()
+                .find_map(|start| EEPROMAddress::try_from(start).ok());
+
+            match start {
+                Some(start) => {
+                    let len = MAX_CHUNK_LEN.min((range.end - start as u16) as usize);
+                    self.chunks.push((start, len));
+                    cursor = start as u16 + len as u16;
+                }
+                None => {
+                    self.unreachable.push(cursor);
+                    cursor += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Identifies the model and firmware a dump was taken from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub cid: u8,
+    pub mid: u8,
+    pub firmware_major: u8,
+    pub firmware_minor: u8,
+}
+
+impl std::fmt::Display for DeviceIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CID: {} | MID: {} | Firmware: {:02x}{:02x}",
+            self.cid, self.mid, self.firmware_major, self.firmware_minor
+        )
+    }
+}
+
+impl DeviceIdentity {
+    /// Identifies the mouse behind `manager`, once it is online.
+    pub fn query(manager: &MouseManager) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        manager.wrapper(|manager| {
+            let ids = manager
+                .execute(Command::<GetMouseCidMid>::query())?
+                .config();
+            let version = manager
+                .execute(Command::<GetMouseVersion>::query())?
+                .config();
+
+            Ok(DeviceIdentity {
+                cid: ids.cid(),
+                mid: ids.mid(),
+                firmware_major: version.major(),
+                firmware_minor: version.minor(),
+            })
+        })
+    }
+}
+
+/// Bytes read from a single known address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chunk {
+    pub address: u16,
+    /// Name of the region at `address`, for humans only.
+    pub region: String,
+    /// Space separated hex bytes.
+    pub data: String,
+    /// The region each byte belongs to, for humans only.
+    pub labels: Vec<String>,
+}
+
+impl Chunk {
+    pub fn new(address: EEPROMAddress, data: &[u8]) -> Self {
+        let start = address as u16;
+        Chunk {
+            address: start,
+            region: format!("{:?}", address),
+            data: data
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+            labels: (start..start + data.len() as u16).map(label).collect(),
+        }
+    }
+
+    pub fn bytes(&self) -> Result<Vec<u8>, Error> {
+        self.data
+            .split_whitespace()
+            .map(|byte| {
+                u8::from_str_radix(byte, 16).map_err(|_| {
+                    Error::ParseError(format!(
+                        "Dump: Invalid byte {:?} at {:#06x}",
+                        byte, self.address
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    pub fn eeprom_address(&self) -> Result<EEPROMAddress, Error> {
+        EEPROMAddress::try_from(self.address)
+    }
+}
+
+/// A raw copy of the EEPROM, annotated with the known region names.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EepromDump {
+    pub format: u32,
+    pub device: DeviceIdentity,
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    pub chunks: Vec<Chunk>,
+    #[serde(default)]
+    pub unreachable: Vec<u16>,
+}
+
+impl EepromDump {
+    /// Reads every chunk in `plan` from the device.
+    pub fn capture(
+        manager: &MouseManager,
+        plan: &ChunkPlan,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let device = DeviceIdentity::query(manager)?;
+
+        let mut chunks = Vec::with_capacity(plan.chunks.len());
+        for &(address, len) in &plan.chunks {
+            let data = manager.read_raw_eeprom(address, len)?;
+            chunks.push(Chunk::new(address, &data));
+        }
+
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        Ok(EepromDump {
+            format: DUMP_FORMAT,
+            device,
+            created,
+            chunks,
+            unreachable: plan.unreachable.clone(),
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let dump: EepromDump = serde_json::from_str(json)?;
+        if dump.format != DUMP_FORMAT {
+            return Err(format!(
+                "Unsupported dump format {}, expected {}",
+                dump.format, DUMP_FORMAT
+            )
+            .into());
+        }
+        dump.check_chunks()?;
+
+        Ok(dump)
+    }
+
+    /// Checks that every chunk parses and fits in a single write.
+    fn check_chunks(&self) -> Result<(), Error> {
+        for chunk in &self.chunks {
+            check_chunk_len(chunk.eeprom_address()?, chunk.bytes()?.len())?;
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Returns every byte in the dump keyed by its address.
+    pub fn bytes(&self) -> Result<std::collections::BTreeMap<u16, u8>, Error> {
+        let mut bytes = std::collections::BTreeMap::new();
+        for chunk in &self.chunks {
+            for (offset, byte) in chunk.bytes()?.into_iter().enumerate() {
+                bytes.insert(chunk.address + offset as u16, byte);
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Flattens the dump into an image starting at address 0. Bytes that were not read are
+    /// filled with `0xff`, the erased state of the EEPROM.
+    pub fn to_image(&self) -> Result<Vec<u8>, Error> {
+        let bytes = self.bytes()?;
+        let len = bytes.keys().last().map_or(0, |&last| last as usize + 1);
+
+        let mut image = vec![0xffu8; len];
+        for (address, byte) in bytes {
+            image[address as usize] = byte;
+        }
+        Ok(image)
+    }
+
+    /// Formats the dump as hex lines, one per known region.
+    pub fn to_hex(&self) -> Result<String, Error> {
+        let mut lines = Vec::new();
+        let mut current: Option<(EEPROMAddress, u16, Vec<u8>)> = None;
+
+        for (address, byte) in self.bytes()? {
+            let region = region_of(address).map(|(region, _)| region);
+            match (&mut current, region) {
+                (Some((start, last, data)), Some(region))
+                    if *start as u16 == region as u16 && *last + 1 == address =>
+                {
+                    *last = address;
+                    data.push(byte);
+                }
+                (_, region) => {
+                    if let Some(line) = current.take() {
+                        lines.push(line);
+                    }
+                    current = region.map(|region| (region, address, vec![byte]));
+                }
+            }
+        }
+        lines.extend(current);
+
+        Ok(lines
+            .into_iter()
+            .map(|(_, last, data)| {
+                let first = last + 1 - data.len() as u16;
+                let hex: Vec<String> = data.iter().map(|b| format!("{:02x}", b)).collect();
+                format!("{:#06x}  {:<30} {}", first, hex.join(" "), label(first))
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Writes every chunk back to the device byte for byte.
+    ///
+    /// Refuses to restore a dump taken from a different model or firmware unless `force` is set.
+    pub fn restore(
+        &self,
+        manager: &MouseManager,
+        force: bool,
+    ) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let device = DeviceIdentity::query(manager)?;
+        if device != self.device && !force {
+            return Err(format!(
+                "Dump was taken from a different device ({}), connected device is ({})",
+                self.device, device
+            )
+            .into());
+        }
+
+        // Dumps can be built or edited without `from_json`, nothing is written unless the
+        // whole dump can be.
+        self.check_chunks()?;
+
+        let mut written = 0;
+        for chunk in &self.chunks {
+            let bytes = chunk.bytes()?;
+            manager.write_raw_eeprom(chunk.eeprom_address()?, &bytes)?;
+            written += bytes.len();
+        }
+
+        Ok(written)
+    }
+}
+
+#[derive(Command)]
+pub struct RawEeprom;
+
+/// Rejects reads and writes that do not fit in a single frame.
+fn check_chunk_len(address: EEPROMAddress, len: usize) -> Result<(), Error> {
+    if len > MAX_CHUNK_LEN {
+        return Err(Error::ParseError(format!(
+            "EEPROM: {} bytes at {:?} exceed the {} bytes of a frame",
+            len, address, MAX_CHUNK_LEN
+        )));
+    }
+    Ok(())
+}
+
+#[command_extension]
+impl Command<RawEeprom> {
+    pub fn query(address: EEPROMAddress, len: usize) -> Result<Command<RawEeprom>, Error> {
+        check_chunk_len(address, len)?;
+        let mut command = Command::default();
+
+        command.set_id(CommandId::GetEEPROM);
+        command.set_eeprom_address(address);
+        command.set_data_len(len)?;
+
+        Ok(command)
+    }
+
+    pub fn builder(
+        address: EEPROMAddress,
+        bytes: &[u8],
+    ) -> Result<CommandBuilder<RawEeprom>, Error> {
+        let mut command = Command::default();
+
+        command.set_id(CommandId::SetEEPROM);
+        command.set_eeprom_address(address);
+        command.try_set_bytes(bytes)?;
+
+        Ok(CommandBuilder::new(command))
+    }
+
+    /// Replaces the data with `bytes`, at most [`MAX_CHUNK_LEN`] of them.
+    pub fn try_set_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        check_chunk_len(self.eeprom_address(), bytes.len())?;
+        self.set_data_len(bytes.len())?;
+        self.set_data(bytes, 0x0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{simulated::SimulatedMouse, Transport};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// A simulated mouse that sleeps through its first `naps` online queries.
+    struct Drowsy {
+        mouse: SimulatedMouse,
+        naps: AtomicUsize,
+    }
+
+    impl Transport for Drowsy {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            if request[0] == CommandId::GetWirelessMouseOnline as u8 {
+                let naps = self.naps.load(Ordering::SeqCst);
+                self.naps.store(naps.saturating_sub(1), Ordering::SeqCst);
+                self.mouse.with_state(|state| state.online = naps == 0);
+            }
+
+            self.mouse.exchange(request)
+        }
+    }
+
+    fn capture(mouse: &Arc<SimulatedMouse>) -> (MouseManager, EepromDump) {
+        let manager = MouseManager::new_without_profile(Arc::clone(mouse));
+        let dump = EepromDump::capture(&manager, &ChunkPlan::known()).unwrap();
+        (manager, dump)
+    }
+
+    fn chunks(plan: &ChunkPlan) -> Vec<(u16, usize)> {
+        plan.chunks
+            .iter()
+            .map(|&(address, len)| (address as u16, len))
+            .collect()
+    }
+
+    fn eeprom(mouse: &SimulatedMouse) -> Vec<u8> {
+        mouse.with_state(|state| state.eeprom.clone())
+    }
+
+    #[test]
+    fn chunks_start_at_known_addresses() {
+        let plan = ChunkPlan::new([0x0..0x8, 0x62..0x64]);
+        assert_eq!(chunks(&plan), [(0x0, 8), (0x60, 4)]);
+        assert!(plan.unreachable.is_empty());
+
+        let plan = ChunkPlan::new(Some(0x0..0x19));
+        assert_eq!(chunks(&plan), [(0x0, 10), (0xa, 10), (0x14, 5)]);
+
+        let plan = ChunkPlan::new(Some(0xa6..0xaa));
+        assert_eq!(chunks(&plan), [(0xa9, 1)]);
+        assert_eq!(plan.unreachable, [0xa6, 0xa7, 0xa8]);
+    }
+
+    #[test]
+    fn known_plan_reaches_every_known_address() {
+        let plan = ChunkPlan::known();
+        for address in known_addresses() {
+            assert!(plan.chunks.iter().any(|&(start, len)| {
+                (start as u16..start as u16 + len as u16).contains(&(address as u16))
+            }));
+        }
+        assert!(plan.chunks.iter().all(|&(_, len)| len <= MAX_CHUNK_LEN));
+        assert!(plan
+            .chunks
+            .windows(2)
+            .all(|pair| (pair[0].0 as u16) < pair[1].0 as u16));
+    }
+
+    #[test]
+    fn identity_waits_for_the_mouse() {
+        let mouse = SimulatedMouse::default();
+        mouse.with_state(|state| state.online = false);
+        let manager = MouseManager::new_without_profile(Drowsy {
+            mouse,
+            naps: AtomicUsize::new(3),
+        });
+
+        let identity = DeviceIdentity::query(&manager).unwrap();
+        assert_eq!((identity.cid, identity.mid), (0x03, 0x11));
+    }
+
+    #[test]
+    fn dumps_round_trip_through_json_and_images() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (_, dump) = capture(&mouse);
+        assert_eq!(
+            dump.device,
+            DeviceIdentity {
+                cid: 0x03,
+                mid: 0x11,
+                firmware_major: 0x01,
+                firmware_minor: 0x07,
+            }
+        );
+
+        let parsed = EepromDump::from_json(&dump.to_json().unwrap()).unwrap();
+        assert_eq!(parsed.device, dump.device);
+        assert_eq!(parsed.bytes().unwrap(), dump.bytes().unwrap());
+
+        let image = dump.to_image().unwrap();
+        let device = eeprom(&mouse);
+        for (address, byte) in dump.bytes().unwrap() {
+            assert_eq!(image[address as usize], byte);
+            assert_eq!(device[address as usize], byte);
+        }
+
+        let hex = dump.to_hex().unwrap();
+        let first = hex.lines().next().unwrap();
+        assert!(first.starts_with("0x0000  "), "{}", first);
+        assert!(first.ends_with(" ReportRate"), "{}", first);
+    }
+
+    #[test]
+    fn malformed_dumps_are_rejected() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (_, mut dump) = capture(&mouse);
+
+        dump.format = DUMP_FORMAT + 1;
+        assert!(EepromDump::from_json(&dump.to_json().unwrap()).is_err());
+
+        dump.chunks[0].data = "00 zz".to_string();
+        assert!(dump.bytes().is_err());
+        assert!(dump.to_image().is_err());
+    }
+
+    #[test]
+    fn restore_writes_the_dump_back() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (manager, dump) = capture(&mouse);
+        let before = eeprom(&mouse);
+
+        mouse.with_state(|state| state.eeprom[EEPROMAddress::DpiPair1 as usize] ^= 0xff);
+        let written = dump.restore(&manager, false).unwrap();
+
+        let chunked: usize = dump.chunks.iter().map(|c| c.bytes().unwrap().len()).sum();
+        assert_eq!(written, chunked);
+        assert_eq!(eeprom(&mouse), before);
+        manager.load_profile().unwrap();
+    }
+
+    #[test]
+    fn oversized_chunks_are_rejected_before_writing() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (manager, mut dump) = capture(&mouse);
+
+        let last = dump.chunks.last_mut().unwrap();
+        *last = Chunk::new(last.eeprom_address().unwrap(), &[0x00; MAX_CHUNK_LEN + 1]);
+        let error = EepromDump::from_json(&dump.to_json().unwrap()).unwrap_err();
+        assert!(error.to_string().contains("exceed"), "{}", error);
+
+        mouse.with_state(|state| state.eeprom[EEPROMAddress::DpiPair1 as usize] ^= 0xff);
+        let before = eeprom(&mouse);
+        assert!(dump.restore(&manager, false).is_err());
+        assert_eq!(eeprom(&mouse), before);
+
+        assert!(Command::<RawEeprom>::query(EEPROMAddress::DpiPair1, MAX_CHUNK_LEN + 1).is_err());
+    }
+
+    #[test]
+    fn restore_refuses_other_models_and_firmware() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (manager, dump) = capture(&mouse);
+
+        mouse.with_state(|state| state.firmware.1 += 1);
+        let error = dump.restore(&manager, false).unwrap_err();
+        assert!(error.to_string().contains("different device"), "{}", error);
+        dump.restore(&manager, true).unwrap();
+
+        mouse.with_state(|state| {
+            state.firmware.1 -= 1;
+            state.mid += 1;
+        });
+        assert!(dump.restore(&manager, false).is_err());
+    }
+
+    #[test]
+    fn restored_garbage_fails_to_load_instead_of_panicking() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let (manager, mut dump) = capture(&mouse);
+
+        let effects = EEPROMAddress::DpiRgbLightingEffects as u16;
+        let chunk = dump
+            .chunks
+            .iter_mut()
+            .find(|chunk| (chunk.address..chunk.address + 10).contains(&effects))
+            .unwrap();
+        let mut bytes = chunk.bytes().unwrap();
+        bytes[(effects - chunk.address) as usize] = 0x7f;
+        *chunk = Chunk::new(chunk.eeprom_address().unwrap(), &bytes);
+
+        dump.restore(&manager, false).unwrap();
+        assert!(manager.load_profile().is_err());
+    }
+}
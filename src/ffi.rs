@@ -0,0 +1,995 @@
+//! C bindings.
+//!
+//! The header is `include/atk_hub.h`, generated from this file with cbindgen. Every function
+//! returns an [`AtkStatus`]; the message of the last failure on the calling thread is
+//! available from [`atk_last_error_message`]. Panics are caught and reported as
+//! [`AtkStatus::Panic`] instead of unwinding into C.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    color::Color,
+    commands::prelude::*,
+    discovery,
+    manager::{verify::VerificationError, MouseManager},
+    trace::replay::{Replay, ReplayError},
+    transport::simulated::SimulatedMouse,
+    types::{Decaseconds, Duration, Milliseconds},
+};
+use libatk_rs::prelude::*;
+
+/// Number of DPI presets in [`AtkProfile`].
+pub const ATK_PRESET_COUNT: usize = 8;
+
+/// Length of the strings in [`AtkDeviceInfo`], including the terminating NUL.
+pub const ATK_NAME_LEN: usize = 64;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AtkStatus {
+    Ok = 0,
+    /// A pointer argument was NULL or a value is not accepted by the API.
+    InvalidArgument = 1,
+    /// Opening or talking to the HID device failed.
+    Device = 2,
+    /// The device answered with a malformed frame.
+    Protocol = 3,
+    /// A value is out of range, or data read from the device does not parse.
+    InvalidValue = 4,
+    /// Read-back verification of a write failed.
+    Verification = 5,
+    /// The request does not match the replayed trace.
+    Replay = 6,
+    /// Reading or writing a file failed.
+    Io = 7,
+    /// The output buffer is too small, the required size was still written.
+    BufferTooSmall = 8,
+    /// The library panicked, this is a bug.
+    Panic = 9,
+    Other = 10,
+}
+
+/// A connected mouse. Create with one of the `atk_manager_open*` functions and release
+/// with [`atk_manager_free`].
+pub struct AtkManager {
+    inner: MouseManager,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkColor {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// Values are the raw protocol values: mode 1 static, 2 breathing; brightness 0x10, 0x80
+/// or 0xff; breathing rate 1, 3 or 5.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkLedSettings {
+    pub mode: u8,
+    pub brightness: u8,
+    pub breathing_rate: u8,
+    pub enabled: bool,
+}
+
+/// Times are in milliseconds and must be whole units of the underlying setting.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkMousePerformance {
+    pub stabilization_time_ms: u32,
+    pub motion_sync: bool,
+    /// Whole multiples of 10 seconds.
+    pub close_led_time_ms: u32,
+    pub linear_correction: bool,
+    pub ripple_control: bool,
+}
+
+/// Times are in milliseconds and must be whole units of the underlying setting.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkSensorPerformance {
+    pub move_close_led: bool,
+    pub sensor_sleep: bool,
+    /// Whole multiples of 10 seconds.
+    pub sensor_sleep_time_ms: u32,
+    pub performance_mode: bool,
+    pub rf_tx_time_ms: u32,
+}
+
+/// The configuration cached by the manager, see [`atk_manager_get_profile`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkProfile {
+    pub polling_rate_hz: u32,
+    /// As stored on the device.
+    pub num_profile: u8,
+    /// As stored on the device.
+    pub active_profile: u8,
+    pub dpi: [u16; ATK_PRESET_COUNT],
+    pub colors: [AtkColor; ATK_PRESET_COUNT],
+    pub led: AtkLedSettings,
+    pub mouse_performance: AtkMousePerformance,
+    pub sensor_performance: AtkSensorPerformance,
+    pub far_distance_mode: bool,
+    /// 0 off, 1 for 1mm, 2 for 2mm.
+    pub silent_height: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AtkBattery {
+    /// Percent.
+    pub level: u8,
+    pub charge: u8,
+    pub voltage: f32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AtkPairingStatus {
+    pub status: u8,
+    /// Seconds.
+    pub time_left: u8,
+}
+
+/// A HID interface that may be an ATK mouse, see [`atk_discover`].
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AtkDeviceInfo {
+    pub vendor_id: u16,
+    pub product_id: u16,
+    pub usage_page: u16,
+    pub usage: u16,
+    /// NUL terminated, truncated to fit.
+    pub manufacturer: [c_char; ATK_NAME_LEN],
+    /// NUL terminated, truncated to fit.
+    pub product: [c_char; ATK_NAME_LEN],
+}
+
+/// Raised for bad arguments before anything is sent to the device.
+#[derive(Debug)]
+struct InvalidArgument(String);
+
+impl std::fmt::Display for InvalidArgument {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidArgument {}
+
+/// Also for the short write of a buffer that is too small, so the caller still gets a message.
+#[derive(Debug)]
+struct BufferTooSmall {
+    required: usize,
+}
+
+impl std::fmt::Display for BufferTooSmall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Buffer too small: {} entries required", self.required)
+    }
+}
+
+impl std::error::Error for BufferTooSmall {}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+fn status_of(error: &(dyn std::error::Error + 'static)) -> AtkStatus {
+    if let Some(error) = error.downcast_ref::<Error>() {
+        return match error {
+            Error::HidError(_) => AtkStatus::Device,
+            Error::ParseError(_) => AtkStatus::InvalidValue,
+            _ => AtkStatus::Protocol,
+        };
+    }
+
+    if error.is::<InvalidArgument>() {
+        AtkStatus::InvalidArgument
+    } else if error.is::<BufferTooSmall>() {
+        AtkStatus::BufferTooSmall
+    } else if error.is::<VerificationError>() {
+        AtkStatus::Verification
+    } else if error.is::<ReplayError>() {
+        AtkStatus::Replay
+    } else if error.is::<std::io::Error>() || error.is::<serde_json::Error>() {
+        AtkStatus::Io
+    } else {
+        AtkStatus::Other
+    }
+}
+
+/// Runs `func`, recording its error (or panic) for [`atk_last_error_message`].
+fn call(func: impl FnOnce() -> Result<(), Box<dyn std::error::Error + Send + Sync>>) -> AtkStatus {
+    let result = catch_unwind(AssertUnwindSafe(func));
+
+    match result {
+        Ok(Ok(())) => {
+            LAST_ERROR.with(|last| *last.borrow_mut() = None);
+            AtkStatus::Ok
+        }
+        Ok(Err(error)) => {
+            set_last_error(error.to_string());
+            status_of(&*error)
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            set_last_error(format!("Panic: {}", message));
+            AtkStatus::Panic
+        }
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Box<dyn std::error::Error + Send + Sync> {
+    InvalidArgument(message.into()).into()
+}
+
+/// Borrows the manager behind `manager`.
+///
+/// # Safety
+///
+/// `manager` must be NULL or returned by an `atk_manager_open*` function and not freed.
+unsafe fn borrow<'a>(
+    manager: *const AtkManager,
+) -> Result<&'a MouseManager, Box<dyn std::error::Error + Send + Sync>> {
+    // SAFETY: Guaranteed by the caller.
+    unsafe { manager.as_ref() }
+        .map(|manager| &manager.inner)
+        .ok_or_else(|| invalid("manager is NULL"))
+}
+
+/// Writes `value` to `out`.
+///
+/// # Safety
+///
+/// `out` must be NULL or valid for writes.
+unsafe fn write<T>(out: *mut T, value: T) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if out.is_null() {
+        return Err(invalid("output pointer is NULL"));
+    }
+
+    // SAFETY: Checked for NULL above, valid otherwise by the caller's guarantee.
+    unsafe { out.write(value) };
+    Ok(())
+}
+
+fn preset(index: u8) -> Result<Preset, Box<dyn std::error::Error + Send + Sync>> {
+    Preset::try_from(index).map_err(|_| invalid(format!("preset {} is not 0 to 7", index)))
+}
+
+fn name(value: &str) -> [c_char; ATK_NAME_LEN] {
+    let mut name = [0; ATK_NAME_LEN];
+    for (dst, &src) in name
+        .iter_mut()
+        .zip(value.as_bytes().iter().take(ATK_NAME_LEN - 1))
+    {
+        *dst = src as c_char;
+    }
+    name
+}
+
+fn polling_rate_hz(rate: PollingRate) -> u32 {
+    match rate {
+        PollingRate::Hz125 => 125,
+        PollingRate::Hz250 => 250,
+        PollingRate::Hz500 => 500,
+        PollingRate::Hz1000 => 1000,
+        PollingRate::Hz2000 => 2000,
+        PollingRate::Hz4000 => 4000,
+        PollingRate::Hz8000 => 8000,
+    }
+}
+
+fn profile(manager: &MouseManager) -> AtkProfile {
+    let profile = manager.profile();
+
+    let mut dpi = [0; ATK_PRESET_COUNT];
+    let mut colors = [AtkColor::default(); ATK_PRESET_COUNT];
+    for (index, pair) in [Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4]
+        .into_iter()
+        .enumerate()
+    {
+        let (first, second) = profile.dpi_profile(pair);
+        for (offset, gear) in [first, second].into_iter().enumerate() {
+            let color = gear.color();
+            dpi[index * 2 + offset] = gear.dpi().dpi();
+            colors[index * 2 + offset] = AtkColor {
+                red: color.red(),
+                green: color.green(),
+                blue: color.blue(),
+            };
+        }
+    }
+
+    let info = profile.mouse_info();
+    let led = profile.dpi_led_settings();
+    let mouse = profile.mouse_performance_settings();
+    let sensor = profile.sensor_performance_settings();
+
+    AtkProfile {
+        polling_rate_hz: polling_rate_hz(info.poll_rate()),
+        num_profile: info.num_profile(),
+        active_profile: info.active_profile(),
+        dpi,
+        colors,
+        led: AtkLedSettings {
+            mode: led.mode() as u8,
+            brightness: led.brightness() as u8,
+            breathing_rate: led.breathing_rate() as u8,
+            enabled: led.enabled(),
+        },
+        mouse_performance: AtkMousePerformance {
+            stabilization_time_ms: mouse.stabilization_time().as_millis(),
+            motion_sync: mouse.motion_sync(),
+            close_led_time_ms: mouse.close_led_time().as_millis(),
+            linear_correction: mouse.linear_correction(),
+            ripple_control: mouse.ripple_control(),
+        },
+        sensor_performance: AtkSensorPerformance {
+            move_close_led: sensor.move_close_led(),
+            sensor_sleep: sensor.sensor_sleep(),
+            sensor_sleep_time_ms: sensor.sensor_sleep_time().as_millis(),
+            performance_mode: sensor.performance_mode(),
+            rf_tx_time_ms: sensor.rf_tx_time().as_millis(),
+        },
+        far_distance_mode: profile.far_distance_mode().far_distance_mode(),
+        silent_height: profile.silent_height().silent_height() as u8,
+    }
+}
+
+/// Returns the message of the last failed call on this thread, or NULL if the last call
+/// succeeded. The string is valid until the next call on this thread.
+#[no_mangle]
+pub extern "C" fn atk_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Lists HID interfaces that may be ATK mice.
+///
+/// Writes up to `capacity` entries to `out` and the number of interfaces found to `count`.
+/// Returns `ATK_STATUS_BUFFER_TOO_SMALL` if `count` exceeds `capacity`.
+///
+/// # Safety
+///
+/// `out` must be valid for `capacity` writes, it may be NULL if `capacity` is 0. `count`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn atk_discover(
+    out: *mut AtkDeviceInfo,
+    capacity: usize,
+    count: *mut usize,
+) -> AtkStatus {
+    call(|| {
+        let devices = discovery::discover()?;
+        // SAFETY: Guaranteed by the caller.
+        unsafe { write(count, devices.len())? };
+
+        if capacity > 0 && out.is_null() {
+            return Err(invalid("out is NULL"));
+        }
+        for (index, device) in devices.iter().take(capacity).enumerate() {
+            let info = AtkDeviceInfo {
+                vendor_id: device.vendor_id,
+                product_id: device.product_id,
+                usage_page: device.usage_page,
+                usage: device.usage,
+                manufacturer: name(&device.manufacturer),
+                product: name(&device.product),
+            };
+            // SAFETY: `index` is below `capacity`.
+            unsafe { out.add(index).write(info) };
+        }
+
+        if devices.len() > capacity {
+            return Err(BufferTooSmall {
+                required: devices.len(),
+            }
+            .into());
+        }
+        Ok(())
+    })
+}
+
+/// Takes ownership of `manager` and hands it to C.
+///
+/// # Safety
+///
+/// `out` must be NULL or valid for writes.
+unsafe fn open(
+    out: *mut *mut AtkManager,
+    manager: impl FnOnce() -> Result<MouseManager, Box<dyn std::error::Error + Send + Sync>>,
+) -> AtkStatus {
+    call(|| {
+        if out.is_null() {
+            return Err(invalid("out is NULL"));
+        }
+
+        let manager = Box::new(AtkManager { inner: manager()? });
+        // SAFETY: Checked for NULL above, valid otherwise by the caller's guarantee.
+        unsafe { out.write(Box::into_raw(manager)) };
+        Ok(())
+    })
+}
+
+/// Opens the HID interface with the given identifiers and reads its configuration.
+///
+/// # Safety
+///
+/// `out` must be valid for writes. On success it holds a manager to be released with
+/// [`atk_manager_free`].
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_open(
+    vendor_id: u16,
+    product_id: u16,
+    usage_page: u16,
+    usage: u16,
+    out: *mut *mut AtkManager,
+) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    unsafe {
+        open(out, || {
+            let device = Device::new(vendor_id, product_id, usage_page, usage)?;
+            MouseManager::new(device)
+        })
+    }
+}
+
+/// Answers commands from a trace recorded with `--trace`, see the `trace` module.
+///
+/// # Safety
+///
+/// `path` must be a NUL terminated string. `out` must be valid for writes. On success it
+/// holds a manager to be released with [`atk_manager_free`].
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_open_replay(
+    path: *const c_char,
+    out: *mut *mut AtkManager,
+) -> AtkStatus {
+    let manager = || {
+        if path.is_null() {
+            return Err(invalid("path is NULL"));
+        }
+        // SAFETY: Checked for NULL above, NUL terminated by the caller's guarantee.
+        let path = unsafe { CStr::from_ptr(path) }.to_str()?;
+        MouseManager::new(Replay::open(path)?)
+    };
+
+    // SAFETY: Guaranteed by the caller.
+    unsafe { open(out, manager) }
+}
+
+/// Opens a simulated mouse, for testing code against the API without hardware.
+///
+/// # Safety
+///
+/// `out` must be valid for writes. On success it holds a manager to be released with
+/// [`atk_manager_free`].
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_open_simulated(out: *mut *mut AtkManager) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    unsafe { open(out, || MouseManager::new(SimulatedMouse::default())) }
+}
+
+/// Closes the device. Passing NULL does nothing.
+///
+/// # Safety
+///
+/// `manager` must be NULL or returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_free(manager: *mut AtkManager) {
+    if !manager.is_null() {
+        // SAFETY: Created by `Box::into_raw` in `open`, not freed by the caller's guarantee.
+        drop(unsafe { Box::from_raw(manager) });
+    }
+}
+
+/// Reads the configuration from the device again.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_load_profile(manager: *const AtkManager) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    call(|| unsafe { borrow(manager) }?.load_profile())
+}
+
+/// Copies the cached configuration to `out`. Does not talk to the device.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_get_profile(
+    manager: *const AtkManager,
+    out: *mut AtkProfile,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        // SAFETY: Guaranteed by the caller.
+        unsafe { write(out, profile(manager)) }
+    })
+}
+
+/// Sets the DPI of preset 0 to 7.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_dpi(
+    manager: *const AtkManager,
+    preset_index: u8,
+    dpi: u16,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        if dpi < 50 || !dpi.is_multiple_of(50) {
+            return Err(invalid(format!(
+                "DPI {} is not a positive multiple of 50",
+                dpi
+            )));
+        }
+        manager.set_dpi_profile_dpi(preset(preset_index)?, Dpi::new(dpi))
+    })
+}
+
+/// Sets the colour of preset 0 to 7. The manager's colour correction is applied.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_color(
+    manager: *const AtkManager,
+    preset_index: u8,
+    color: AtkColor,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        manager.set_dpi_profile_color(
+            preset(preset_index)?,
+            Color::new(color.red, color.green, color.blue),
+        )
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+/// must be valid for reads.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_led(
+    manager: *const AtkManager,
+    settings: *const AtkLedSettings,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        // SAFETY: Guaranteed by the caller.
+        let settings = unsafe { settings.as_ref() }.ok_or_else(|| invalid("settings is NULL"))?;
+
+        manager.set_dpi_led_settings(
+            LedPatch::new()
+                .mode(LedEffectMode::try_from(settings.mode)?)
+                .brightness(LedBrightnessLevel::try_from(settings.brightness)?)
+                .breathing_rate(LedBreathingRate::try_from(settings.breathing_rate)?)
+                .enabled(settings.enabled),
+        )
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+/// must be valid for reads.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_mouse_performance(
+    manager: *const AtkManager,
+    settings: *const AtkMousePerformance,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        // SAFETY: Guaranteed by the caller.
+        let settings = unsafe { settings.as_ref() }.ok_or_else(|| invalid("settings is NULL"))?;
+
+        manager.set_mouse_performance_settings(
+            MousePerfPatch::new()
+                .stabilization_time(Duration::<Milliseconds>::from_millis(
+                    settings.stabilization_time_ms,
+                ))
+                .motion_sync(settings.motion_sync)
+                .close_led_time(Duration::<Decaseconds>::from_millis(
+                    settings.close_led_time_ms,
+                ))
+                .linear_correction(settings.linear_correction)
+                .ripple_control(settings.ripple_control),
+        )
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `settings`
+/// must be valid for reads.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_sensor_performance(
+    manager: *const AtkManager,
+    settings: *const AtkSensorPerformance,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        // SAFETY: Guaranteed by the caller.
+        let settings = unsafe { settings.as_ref() }.ok_or_else(|| invalid("settings is NULL"))?;
+
+        manager.set_sensor_performance_settings(
+            SensorPerfPatch::new()
+                .move_close_led(settings.move_close_led)
+                .sensor_sleep(settings.sensor_sleep)
+                .sensor_sleep_time(Duration::<Decaseconds>::from_millis(
+                    settings.sensor_sleep_time_ms,
+                ))
+                .performance_mode(settings.performance_mode)
+                .rf_tx_time(Duration::<Milliseconds>::from_millis(
+                    settings.rf_tx_time_ms,
+                )),
+        )
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_far_distance_mode(
+    manager: *const AtkManager,
+    enabled: bool,
+) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    call(|| unsafe { borrow(manager) }?.set_far_distance_mode(enabled))
+}
+
+/// Sets the lift-off height: 0 off, 1 for 1mm, 2 for 2mm.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_set_silent_height(
+    manager: *const AtkManager,
+    height: u8,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let manager = unsafe { borrow(manager) }?;
+        if height > 2 {
+            return Err(invalid(format!("silent height {} is not 0 to 2", height)));
+        }
+        manager.set_silent_height(SilentHeightMode::from(height))
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_battery(
+    manager: *const AtkManager,
+    out: *mut AtkBattery,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let battery = unsafe { borrow(manager) }?.battery_level()?;
+        let battery = AtkBattery {
+            level: battery.level(),
+            charge: battery.charge(),
+            voltage: battery.voltage(),
+        };
+        // SAFETY: Guaranteed by the caller.
+        unsafe { write(out, battery) }
+    })
+}
+
+/// Puts the dongle in pairing mode, poll [`atk_manager_pairing_status`] for progress.
+///
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_start_pairing(manager: *const AtkManager) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    call(|| unsafe { borrow(manager) }?.start_pairing())
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed. `out`
+/// must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_pairing_status(
+    manager: *const AtkManager,
+    out: *mut AtkPairingStatus,
+) -> AtkStatus {
+    call(|| {
+        // SAFETY: Guaranteed by the caller.
+        let status = unsafe { borrow(manager) }?.pairing_status()?;
+        let status = AtkPairingStatus {
+            status: status.status,
+            time_left: status.time_left,
+        };
+        // SAFETY: Guaranteed by the caller.
+        unsafe { write(out, status) }
+    })
+}
+
+/// # Safety
+///
+/// `manager` must be returned by an `atk_manager_open*` function and not freed.
+#[no_mangle]
+pub unsafe extern "C" fn atk_manager_exit_pairing(manager: *const AtkManager) -> AtkStatus {
+    // SAFETY: Guaranteed by the caller.
+    call(|| unsafe { borrow(manager) }?.exit_pairing())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn simulated() -> *mut AtkManager {
+        let mut manager = ptr::null_mut();
+        assert_eq!(
+            unsafe { atk_manager_open_simulated(&mut manager) },
+            AtkStatus::Ok
+        );
+        manager
+    }
+
+    fn get_profile(manager: *const AtkManager) -> AtkProfile {
+        let mut profile = AtkProfile::default();
+        assert_eq!(
+            unsafe { atk_manager_get_profile(manager, &mut profile) },
+            AtkStatus::Ok
+        );
+        profile
+    }
+
+    fn last_error() -> String {
+        let message = atk_last_error_message();
+        assert!(!message.is_null());
+        unsafe { CStr::from_ptr(message) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn reads_profile() {
+        let manager = simulated();
+        let profile = get_profile(manager);
+
+        assert_eq!(profile.polling_rate_hz, 1000);
+        assert_eq!(
+            profile.dpi,
+            [400, 800, 1600, 3200, 4800, 6400, 12800, 26000]
+        );
+        assert!(profile.led.enabled);
+        assert!(!profile.far_distance_mode);
+
+        unsafe { atk_manager_free(manager) };
+    }
+
+    #[test]
+    fn setters_round_trip() {
+        let manager = simulated();
+
+        unsafe {
+            assert_eq!(atk_manager_set_dpi(manager, 2, 1200), AtkStatus::Ok);
+            assert_eq!(
+                atk_manager_set_color(
+                    manager,
+                    7,
+                    AtkColor {
+                        red: 0xff,
+                        green: 0,
+                        blue: 0,
+                    }
+                ),
+                AtkStatus::Ok
+            );
+
+            let led = AtkLedSettings {
+                mode: 2,
+                brightness: 0x80,
+                breathing_rate: 3,
+                enabled: true,
+            };
+            assert_eq!(atk_manager_set_led(manager, &led), AtkStatus::Ok);
+
+            let mouse = AtkMousePerformance {
+                stabilization_time_ms: 20,
+                motion_sync: true,
+                close_led_time_ms: 60_000,
+                linear_correction: false,
+                ripple_control: true,
+            };
+            assert_eq!(
+                atk_manager_set_mouse_performance(manager, &mouse),
+                AtkStatus::Ok
+            );
+
+            let sensor = AtkSensorPerformance {
+                move_close_led: false,
+                sensor_sleep: true,
+                sensor_sleep_time_ms: 30_000,
+                performance_mode: true,
+                rf_tx_time_ms: 2,
+            };
+            assert_eq!(
+                atk_manager_set_sensor_performance(manager, &sensor),
+                AtkStatus::Ok
+            );
+
+            assert_eq!(
+                atk_manager_set_far_distance_mode(manager, true),
+                AtkStatus::Ok
+            );
+            assert_eq!(atk_manager_set_silent_height(manager, 2), AtkStatus::Ok);
+            assert!(atk_last_error_message().is_null());
+
+            let written = get_profile(manager);
+            assert_eq!(atk_manager_load_profile(manager), AtkStatus::Ok);
+            let profile = get_profile(manager);
+            assert_eq!(profile, written);
+
+            assert_eq!(profile.dpi[2], 1200);
+            assert_eq!(profile.colors[7].red, 0xff);
+            assert_eq!(profile.led, led);
+            assert_eq!(profile.mouse_performance, mouse);
+            assert_eq!(profile.sensor_performance, sensor);
+            assert!(profile.far_distance_mode);
+            assert_eq!(profile.silent_height, 2);
+
+            atk_manager_free(manager);
+        }
+    }
+
+    #[test]
+    fn reads_battery() {
+        let manager = simulated();
+
+        let mut battery = AtkBattery::default();
+        assert_eq!(
+            unsafe { atk_manager_battery(manager, &mut battery) },
+            AtkStatus::Ok
+        );
+        assert_eq!(battery.level, 100);
+
+        unsafe { atk_manager_free(manager) };
+    }
+
+    #[test]
+    fn pairing_counts_down() {
+        let manager = simulated();
+        let mut status = AtkPairingStatus::default();
+
+        unsafe {
+            assert_eq!(atk_manager_start_pairing(manager), AtkStatus::Ok);
+
+            let mut seen = Vec::new();
+            for _ in 0..5 {
+                assert_eq!(
+                    atk_manager_pairing_status(manager, &mut status),
+                    AtkStatus::Ok
+                );
+                seen.push((status.status, status.time_left));
+            }
+            assert_eq!(seen, [(1, 3), (1, 2), (1, 1), (2, 0), (2, 0)]);
+
+            assert_eq!(atk_manager_exit_pairing(manager), AtkStatus::Ok);
+            atk_manager_pairing_status(manager, &mut status);
+            assert_eq!(status, AtkPairingStatus::default());
+
+            atk_manager_free(manager);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        let manager = simulated();
+
+        unsafe {
+            assert_eq!(
+                atk_manager_set_dpi(ptr::null(), 0, 800),
+                AtkStatus::InvalidArgument
+            );
+            assert_eq!(last_error(), "manager is NULL");
+
+            assert_eq!(
+                atk_manager_get_profile(manager, ptr::null_mut()),
+                AtkStatus::InvalidArgument
+            );
+            assert_eq!(
+                atk_manager_set_dpi(manager, 8, 800),
+                AtkStatus::InvalidArgument
+            );
+            assert_eq!(
+                atk_manager_set_dpi(manager, 0, 825),
+                AtkStatus::InvalidArgument
+            );
+            assert_eq!(
+                atk_manager_set_silent_height(manager, 3),
+                AtkStatus::InvalidArgument
+            );
+
+            let led = AtkLedSettings {
+                mode: 0x7,
+                ..Default::default()
+            };
+            assert_eq!(atk_manager_set_led(manager, &led), AtkStatus::InvalidValue);
+            assert!(!last_error().is_empty());
+
+            assert_eq!(
+                atk_manager_open_replay(c"/nonexistent.jsonl".as_ptr(), &mut ptr::null_mut()),
+                AtkStatus::Io
+            );
+
+            atk_manager_free(manager);
+            atk_manager_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn header_is_current() {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let config = cbindgen::Config::from_file(root.join("cbindgen.toml")).unwrap();
+        let path = root.join("include/atk_hub.h");
+
+        let mut generated = Vec::new();
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(root.join("src/ffi.rs"))
+            .generate()
+            .unwrap()
+            .write(&mut generated);
+        let generated = String::from_utf8(generated).unwrap();
+
+        if std::env::var_os("ATK_HUB_BLESS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, &generated).unwrap();
+        }
+        let current = std::fs::read_to_string(&path).unwrap_or_default();
+        assert!(
+            current == generated,
+            "{} is out of date, rerun with ATK_HUB_BLESS=1",
+            path.display()
+        );
+    }
+}
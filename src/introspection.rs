@@ -0,0 +1,905 @@
+//! Describes every configurable setting of a [`Profile`] so frontends can list, show and
+//! change them without hard-coding each one.
+//!
+//! Settings are identified by dotted ids such as `led.mode` or `dpi.3`. The list, the
+//! accepted values and the ranges follow the [`Capabilities`] of the mouse.
+
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+use crate::{
+    capabilities::{Capabilities, Feature},
+    color::Color,
+    commands::prelude::*,
+    manager::{
+        transaction::{Block, Change},
+        MouseManager, Profile,
+    },
+    types::{DurationRange, Milliseconds, Seconds, TimeUnit},
+};
+use libatk_rs::prelude::*;
+
+const PRESETS: [Preset; 8] = [
+    Preset::Preset1,
+    Preset::Preset2,
+    Preset::Preset3,
+    Preset::Preset4,
+    Preset::Preset5,
+    Preset::Preset6,
+    Preset::Preset7,
+    Preset::Preset8,
+];
+
+const POLLING_RATES: [PollingRate; 7] = [
+    PollingRate::Hz125,
+    PollingRate::Hz250,
+    PollingRate::Hz500,
+    PollingRate::Hz1000,
+    PollingRate::Hz2000,
+    PollingRate::Hz4000,
+    PollingRate::Hz8000,
+];
+
+const LED_MODES: [LedEffectMode; 2] = [LedEffectMode::Static, LedEffectMode::Breathing];
+const BREATHING_RATES: [LedBreathingRate; 3] = [
+    LedBreathingRate::Slow,
+    LedBreathingRate::Medium,
+    LedBreathingRate::Fast,
+];
+const BRIGHTNESS_LEVELS: [LedBrightnessLevel; 3] = [
+    LedBrightnessLevel::Low,
+    LedBrightnessLevel::Medium,
+    LedBrightnessLevel::High,
+];
+
+const SILENT_HEIGHTS: [SilentHeightMode; 3] = [
+    SilentHeightMode::Off,
+    SilentHeightMode::OneMm,
+    SilentHeightMode::TwoMm,
+];
+
+/// What a setting holds, along with the values it accepts.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Kind {
+    Bool,
+    Enum {
+        values: Vec<String>,
+    },
+    /// Bounds and step are counted in `unit`.
+    Duration {
+        unit: &'static str,
+        min: u32,
+        max: u32,
+        step: u32,
+    },
+    Dpi {
+        min: u16,
+        max: u16,
+        step: u16,
+    },
+    /// `#rrggbb`, or anything else [`Color`] can parse.
+    Color,
+}
+
+/// The value of a setting. Serializes to a JSON boolean, string or number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Bool(bool),
+    Enum(String),
+    Duration { value: u32, unit: &'static str },
+    Dpi(u16),
+    Color(Color),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Enum(name) => write!(f, "{}", name),
+            Value::Duration { value, unit } => write!(f, "{}{}", value, unit),
+            Value::Dpi(dpi) => write!(f, "{}", dpi),
+            Value::Color(color) => write!(f, "{}", color),
+        }
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Bool(value) => serializer.serialize_bool(*value),
+            Value::Enum(name) => serializer.serialize_str(name),
+            Value::Duration { value, .. } => serializer.serialize_u32(*value),
+            Value::Dpi(dpi) => serializer.serialize_u16(*dpi),
+            Value::Color(color) => serializer.collect_str(color),
+        }
+    }
+}
+
+/// The field of the profile a setting reads and writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Dpi(Preset),
+    Color(Preset),
+    LedMode,
+    LedBrightness,
+    LedBreathingRate,
+    LedEnabled,
+    StabilizationTime,
+    MotionSync,
+    CloseLedTime,
+    LinearCorrection,
+    RippleControl,
+    MoveCloseLed,
+    SensorSleep,
+    SensorSleepTime,
+    PerformanceMode,
+    RfTxTime,
+    FarDistanceMode,
+    SilentHeight,
+    PollingRate,
+}
+
+/// A configurable setting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Setting {
+    pub id: String,
+    pub name: String,
+    pub description: &'static str,
+    #[serde(flatten)]
+    pub kind: Kind,
+    #[serde(skip)]
+    target: Target,
+}
+
+/// A setting along with its current value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SettingValue {
+    #[serde(flatten)]
+    pub setting: Setting,
+    pub value: Value,
+}
+
+fn names<T: std::fmt::Debug>(values: &[T]) -> Vec<String> {
+    values.iter().map(|value| format!("{:?}", value)).collect()
+}
+
+/// A [`Kind::Duration`] counting `range` in whole `U`.
+fn duration<T: TimeUnit, U: TimeUnit>(range: &DurationRange<T>) -> Kind {
+    Kind::Duration {
+        unit: U::LABEL,
+        min: range.min().convert::<U>().as_unit(),
+        max: range.max().convert::<U>().as_unit(),
+        step: range.step().convert::<U>().as_unit(),
+    }
+}
+
+fn setting(
+    id: impl Into<String>,
+    name: impl Into<String>,
+    description: &'static str,
+    kind: Kind,
+    target: Target,
+) -> Setting {
+    Setting {
+        id: id.into(),
+        name: name.into(),
+        description,
+        kind,
+        target,
+    }
+}
+
+impl Setting {
+    /// The capability the setting needs, `None` when every mouse has it.
+    pub fn feature(&self) -> Option<Feature> {
+        match self.target {
+            Target::Dpi(_) | Target::Color(_) | Target::PollingRate => None,
+            Target::LedMode
+            | Target::LedBrightness
+            | Target::LedBreathingRate
+            | Target::LedEnabled => Some(Feature::DpiLed),
+            Target::StabilizationTime => Some(Feature::StabilizationTime),
+            Target::MotionSync => Some(Feature::MotionSync),
+            Target::CloseLedTime => Some(Feature::CloseLedTime),
+            Target::LinearCorrection => Some(Feature::LinearCorrection),
+            Target::RippleControl => Some(Feature::RippleControl),
+            Target::MoveCloseLed => Some(Feature::MoveCloseLed),
+            Target::SensorSleep | Target::SensorSleepTime => Some(Feature::SensorSleep),
+            Target::PerformanceMode => Some(Feature::PerformanceMode),
+            Target::RfTxTime => Some(Feature::RfTxTime),
+            Target::FarDistanceMode => Some(Feature::FarDistanceMode),
+            Target::SilentHeight => Some(Feature::SilentHeight),
+        }
+    }
+
+    /// The value cached in `profile`.
+    pub fn get(&self, profile: &Profile) -> Value {
+        let led = profile.dpi_led_settings();
+        let mouse = profile.mouse_performance_settings();
+        let sensor = profile.sensor_performance_settings();
+        let enum_value = |value: &dyn std::fmt::Debug| Value::Enum(format!("{:?}", value));
+
+        match self.target {
+            Target::Dpi(preset) => Value::Dpi(gear(profile, preset).dpi().dpi()),
+            Target::Color(preset) => Value::Color(gear(profile, preset).color()),
+            Target::LedMode => enum_value(&led.mode()),
+            Target::LedBrightness => enum_value(&led.brightness()),
+            Target::LedBreathingRate => enum_value(&led.breathing_rate()),
+            Target::LedEnabled => Value::Bool(led.enabled()),
+            Target::StabilizationTime => self.duration(mouse.stabilization_time().as_millis()),
+            Target::MotionSync => Value::Bool(mouse.motion_sync()),
+            Target::CloseLedTime => self.duration(mouse.close_led_time().as_millis()),
+            Target::LinearCorrection => Value::Bool(mouse.linear_correction()),
+            Target::RippleControl => Value::Bool(mouse.ripple_control()),
+            Target::MoveCloseLed => Value::Bool(sensor.move_close_led()),
+            Target::SensorSleep => Value::Bool(sensor.sensor_sleep()),
+            Target::SensorSleepTime => self.duration(sensor.sensor_sleep_time().as_millis()),
+            Target::PerformanceMode => Value::Bool(sensor.performance_mode()),
+            Target::RfTxTime => self.duration(sensor.rf_tx_time().as_millis()),
+            Target::FarDistanceMode => Value::Bool(profile.far_distance_mode().far_distance_mode()),
+            Target::SilentHeight => enum_value(&profile.silent_height().silent_height()),
+            Target::PollingRate => enum_value(&profile.mouse_info().poll_rate()),
+        }
+    }
+
+    /// A duration value in the unit of this setting.
+    fn duration(&self, millis: u32) -> Value {
+        let unit = match self.kind {
+            Kind::Duration { unit, .. } => unit,
+            _ => Milliseconds::LABEL,
+        };
+
+        Value::Duration {
+            value: millis / unit_factor(unit),
+            unit,
+        }
+    }
+
+    /// Parses `input` as typed on a command line and checks it against [`Setting::kind`].
+    ///
+    /// Booleans also accept `on`/`off`, `yes`/`no` and `1`/`0`, enum names are matched
+    /// ignoring case and durations take a unit suffix, a bare number being in the unit of
+    /// the setting.
+    pub fn parse(&self, input: &str) -> Result<Value, Error> {
+        let input = input.trim();
+        let invalid = |expected: &str| {
+            Error::ParseError(format!("{}: Expected {}, got {}", self.id, expected, input))
+        };
+
+        let value = match &self.kind {
+            Kind::Bool => match input.to_ascii_lowercase().as_str() {
+                "true" | "on" | "yes" | "1" => Value::Bool(true),
+                "false" | "off" | "no" | "0" => Value::Bool(false),
+                _ => return Err(invalid("true or false")),
+            },
+            Kind::Enum { values } => {
+                // Polling rates are also written the way they are displayed, e.g. 1000Hz.
+                let name = match input.to_ascii_lowercase().strip_suffix("hz") {
+                    Some(rate) if self.target == Target::PollingRate => format!("Hz{}", rate),
+                    _ => input.to_string(),
+                };
+                values
+                    .iter()
+                    .find(|value| value.eq_ignore_ascii_case(&name))
+                    .map(|value| Value::Enum(value.clone()))
+                    .ok_or_else(|| invalid(&format!("one of {}", values.join(", "))))?
+            }
+            Kind::Duration { unit, .. } => {
+                let millis = match *unit {
+                    Seconds::LABEL => crate::types::Duration::<Seconds>::from_str(input)
+                        .map(|value| value.as_millis()),
+                    _ => crate::types::Duration::<Milliseconds>::from_str(input)
+                        .map(|value| value.as_millis()),
+                }
+                .map_err(|_| invalid("a duration such as 10s or 8ms"))?;
+                if !millis.is_multiple_of(unit_factor(unit)) {
+                    return Err(invalid(&format!("a whole number of {}", unit)));
+                }
+                self.duration(millis)
+            }
+            Kind::Dpi { .. } => Value::Dpi(input.parse().map_err(|_| invalid("a DPI"))?),
+            Kind::Color => Value::Color(input.parse()?),
+        };
+
+        self.check(&value)?;
+        Ok(value)
+    }
+
+    /// Fails if `value` is of another kind or outside of the accepted values.
+    pub fn check(&self, value: &Value) -> Result<(), Error> {
+        let out_of_range = |allowed: String| {
+            Err(Error::ParseError(format!(
+                "{}: {} is not allowed, expected {}",
+                self.id, value, allowed
+            )))
+        };
+
+        match (&self.kind, value) {
+            (Kind::Bool, Value::Bool(_)) | (Kind::Color, Value::Color(_)) => Ok(()),
+            (Kind::Enum { values }, Value::Enum(name)) => match values.contains(name) {
+                true => Ok(()),
+                false => out_of_range(format!("one of {}", values.join(", "))),
+            },
+            (
+                Kind::Duration {
+                    unit,
+                    min,
+                    max,
+                    step,
+                },
+                Value::Duration { value, unit: given },
+            ) if unit == given => {
+                match (min..=max).contains(&value) && (value - min).is_multiple_of(*step) {
+                    true => Ok(()),
+                    false => out_of_range(format!(
+                        "{}{unit}..={}{unit} in steps of {}{unit}",
+                        min,
+                        max,
+                        step,
+                        unit = unit
+                    )),
+                }
+            }
+            (Kind::Dpi { min, max, step }, Value::Dpi(dpi)) => {
+                match (min..=max).contains(&dpi) && dpi.is_multiple_of(*step) {
+                    true => Ok(()),
+                    false => out_of_range(format!("{}..={} in steps of {}", min, max, step)),
+                }
+            }
+            _ => Err(Error::ParseError(format!(
+                "{}: {} is not a {:?} value",
+                self.id, value, self.kind
+            ))),
+        }
+    }
+
+    /// The change writing `value`, `None` for the polling rate which is not transactional.
+    fn change(&self, value: &Value) -> Result<Option<Change>, Error> {
+        self.check(value)?;
+
+        let name = match value {
+            Value::Enum(name) => name.as_str(),
+            _ => "",
+        };
+        let flag = matches!(value, Value::Bool(true));
+        let millis = match value {
+            Value::Duration { value, unit } => value * unit_factor(unit),
+            _ => 0,
+        };
+        let ms = crate::types::Duration::<Milliseconds>::from_millis(millis);
+
+        let change = match (self.target, value) {
+            (Target::Dpi(preset), Value::Dpi(dpi)) => Change::Dpi(preset, Dpi::new(*dpi)),
+            (Target::Color(preset), Value::Color(color)) => Change::Color(preset, *color),
+            (Target::LedMode, _) => {
+                Change::DpiLed(LedPatch::new().mode(lookup(&self.id, &LED_MODES, name)?))
+            }
+            (Target::LedBrightness, _) => Change::DpiLed(LedPatch::new().brightness(lookup(
+                &self.id,
+                &BRIGHTNESS_LEVELS,
+                name,
+            )?)),
+            (Target::LedBreathingRate, _) => Change::DpiLed(
+                LedPatch::new().breathing_rate(lookup(&self.id, &BREATHING_RATES, name)?),
+            ),
+            (Target::LedEnabled, _) => Change::DpiLed(LedPatch::new().enabled(flag)),
+            (Target::StabilizationTime, _) => {
+                Change::MousePerformance(MousePerfPatch::new().stabilization_time(ms))
+            }
+            (Target::MotionSync, _) => {
+                Change::MousePerformance(MousePerfPatch::new().motion_sync(flag))
+            }
+            (Target::CloseLedTime, _) => {
+                Change::MousePerformance(MousePerfPatch::new().close_led_time(ms.convert()))
+            }
+            (Target::LinearCorrection, _) => {
+                Change::MousePerformance(MousePerfPatch::new().linear_correction(flag))
+            }
+            (Target::RippleControl, _) => {
+                Change::MousePerformance(MousePerfPatch::new().ripple_control(flag))
+            }
+            (Target::MoveCloseLed, _) => {
+                Change::SensorPerformance(SensorPerfPatch::new().move_close_led(flag))
+            }
+            (Target::SensorSleep, _) => {
+                Change::SensorPerformance(SensorPerfPatch::new().sensor_sleep(flag))
+            }
+            (Target::SensorSleepTime, _) => {
+                Change::SensorPerformance(SensorPerfPatch::new().sensor_sleep_time(ms.convert()))
+            }
+            (Target::PerformanceMode, _) => {
+                Change::SensorPerformance(SensorPerfPatch::new().performance_mode(flag))
+            }
+            (Target::RfTxTime, _) => {
+                Change::SensorPerformance(SensorPerfPatch::new().rf_tx_time(ms))
+            }
+            (Target::FarDistanceMode, _) => Change::FarDistanceMode(flag),
+            (Target::SilentHeight, _) => {
+                Change::SilentHeight(lookup(&self.id, &SILENT_HEIGHTS, name)?)
+            }
+            (Target::PollingRate, _) => return Ok(None),
+            _ => unreachable!("checked against the kind of the setting"),
+        };
+
+        Ok(Some(change))
+    }
+}
+
+/// Milliseconds in one `unit`, as labelled by [`TimeUnit::LABEL`].
+fn unit_factor(unit: &str) -> u32 {
+    match unit {
+        Seconds::LABEL => Seconds::FACTOR,
+        _ => Milliseconds::FACTOR,
+    }
+}
+
+/// The variant of `values` whose `Debug` name is `name`.
+fn lookup<T: Copy + std::fmt::Debug>(id: &str, values: &[T], name: &str) -> Result<T, Error> {
+    values
+        .iter()
+        .copied()
+        .find(|value| format!("{:?}", value) == name)
+        .ok_or_else(|| Error::ParseError(format!("{}: Unknown value {}", id, name)))
+}
+
+fn gear(profile: &Profile, preset: Preset) -> Gear {
+    let (first, second) = profile.dpi_profile(preset.into());
+    match Slot::from(preset) {
+        Slot::First => first,
+        Slot::Second => second,
+    }
+}
+
+/// Every setting `capabilities` allows changing, in a stable order.
+pub fn settings(capabilities: &Capabilities) -> Vec<Setting> {
+    let mut settings = Vec::new();
+
+    for (index, &preset) in PRESETS.iter().enumerate() {
+        if capabilities.check_preset(preset).is_err() {
+            continue;
+        }
+        let number = index + 1;
+        settings.push(setting(
+            format!("dpi.{}", number),
+            format!("Preset {} DPI", number),
+            "Sensitivity while the preset is active",
+            Kind::Dpi {
+                min: capabilities.dpi_step,
+                max: capabilities.max_dpi,
+                step: capabilities.dpi_step,
+            },
+            Target::Dpi(preset),
+        ));
+        settings.push(setting(
+            format!("color.{}", number),
+            format!("Preset {} colour", number),
+            "Colour of the DPI LED while the preset is active",
+            Kind::Color,
+            Target::Color(preset),
+        ));
+    }
+
+    let led = &capabilities.led;
+    settings.extend([
+        setting(
+            "led.mode",
+            "LED effect",
+            "Lighting effect of the DPI LED",
+            Kind::Enum {
+                values: names(&led.modes),
+            },
+            Target::LedMode,
+        ),
+        setting(
+            "led.brightness",
+            "LED brightness",
+            "Brightness of the DPI LED",
+            Kind::Enum {
+                values: names(&led.brightness),
+            },
+            Target::LedBrightness,
+        ),
+        setting(
+            "led.breathing_rate",
+            "LED breathing rate",
+            "Speed of the breathing effect",
+            Kind::Enum {
+                values: names(&led.breathing_rates),
+            },
+            Target::LedBreathingRate,
+        ),
+        setting(
+            "led.enabled",
+            "LED enabled",
+            "Whether the DPI LED lights up",
+            Kind::Bool,
+            Target::LedEnabled,
+        ),
+        setting(
+            "perf.stabilization_time",
+            "Stabilization time",
+            "Debounce delay of the buttons",
+            duration::<_, Milliseconds>(&capabilities.stabilization_time_range()),
+            Target::StabilizationTime,
+        ),
+        setting(
+            "perf.motion_sync",
+            "Motion sync",
+            "Aligns sensor reports with the polling interval",
+            Kind::Bool,
+            Target::MotionSync,
+        ),
+        setting(
+            "perf.close_led_time",
+            "Close LED time",
+            "Idle time before the LEDs turn off",
+            duration::<_, Seconds>(&capabilities.close_led_time_range()),
+            Target::CloseLedTime,
+        ),
+        setting(
+            "perf.linear_correction",
+            "Linear correction",
+            "Straightens slightly angled movements, also known as angle snapping",
+            Kind::Bool,
+            Target::LinearCorrection,
+        ),
+        setting(
+            "perf.ripple_control",
+            "Ripple control",
+            "Smooths sensor jitter at high DPI",
+            Kind::Bool,
+            Target::RippleControl,
+        ),
+        setting(
+            "sensor.move_close_led",
+            "Move close LED",
+            "Turns the LEDs off while the mouse moves",
+            Kind::Bool,
+            Target::MoveCloseLed,
+        ),
+        setting(
+            "sensor.sleep",
+            "Sensor sleep",
+            "Lets the sensor sleep when idle",
+            Kind::Bool,
+            Target::SensorSleep,
+        ),
+        setting(
+            "sensor.sleep_time",
+            "Sensor sleep time",
+            "Idle time before the sensor sleeps",
+            duration::<_, Seconds>(&capabilities.sensor_sleep_time_range()),
+            Target::SensorSleepTime,
+        ),
+        setting(
+            "sensor.performance_mode",
+            "Performance mode",
+            "Keeps the sensor at full speed at the cost of battery life",
+            Kind::Bool,
+            Target::PerformanceMode,
+        ),
+        setting(
+            "sensor.rf_tx_time",
+            "RF Tx time",
+            "Interval between two wireless transmissions",
+            duration::<_, Milliseconds>(&capabilities.rf_tx_time_range()),
+            Target::RfTxTime,
+        ),
+        setting(
+            "far_distance_mode",
+            "Far distance mode",
+            "Extends the wireless range",
+            Kind::Bool,
+            Target::FarDistanceMode,
+        ),
+        setting(
+            "silent_height",
+            "Silent height",
+            "Lift-off distance of the sensor",
+            Kind::Enum {
+                values: names(&SILENT_HEIGHTS),
+            },
+            Target::SilentHeight,
+        ),
+    ]);
+
+    // Connection types left out of the capabilities support every rate.
+    let rates: Vec<PollingRate> = POLLING_RATES
+        .into_iter()
+        .filter(|rate| {
+            capabilities.polling_rates.is_empty()
+                || capabilities
+                    .polling_rates
+                    .values()
+                    .any(|rates| rates.contains(rate))
+        })
+        .collect();
+    settings.push(setting(
+        "polling_rate",
+        "Polling rate",
+        "Reports per second, limited by the connection",
+        Kind::Enum {
+            values: names(&rates),
+        },
+        Target::PollingRate,
+    ));
+
+    settings.retain(|setting| {
+        setting
+            .feature()
+            .is_none_or(|feature| capabilities.supports(feature))
+    });
+    settings
+}
+
+/// Looks up the setting with `id`, telling unknown ids apart from unsupported ones.
+pub fn find(capabilities: &Capabilities, id: &str) -> Result<Setting, Error> {
+    if let Some(setting) = settings(capabilities)
+        .into_iter()
+        .find(|setting| setting.id == id)
+    {
+        return Ok(setting);
+    }
+
+    match settings(&Capabilities::default())
+        .iter()
+        .any(|setting| setting.id == id)
+    {
+        true => Err(Error::ParseError(format!(
+            "Setting: {} is not supported by {}",
+            id, capabilities.name
+        ))),
+        false => Err(Error::ParseError(format!(
+            "Setting: Unknown setting {}",
+            id
+        ))),
+    }
+}
+
+/// The settings addressed by `path`: a single id, every setting under a prefix such as
+/// `led`, or all of them for `profile`.
+pub fn resolve(capabilities: &Capabilities, path: &str) -> Result<Vec<Setting>, Error> {
+    let all = settings(capabilities);
+    if path == "profile" {
+        return Ok(all);
+    }
+
+    let prefix = format!("{}.", path);
+    let matched: Vec<Setting> = all
+        .into_iter()
+        .filter(|setting| setting.id == path || setting.id.starts_with(&prefix))
+        .collect();
+
+    match matched.is_empty() {
+        true => find(capabilities, path).map(|setting| vec![setting]),
+        false => Ok(matched),
+    }
+}
+
+/// Parses an `id=value` assignment.
+pub fn parse_assignment(
+    capabilities: &Capabilities,
+    assignment: &str,
+) -> Result<(Setting, Value), Error> {
+    let (id, input) = assignment.split_once('=').ok_or_else(|| {
+        Error::ParseError(format!("Setting: Expected id=value, got {}", assignment))
+    })?;
+    let setting = find(capabilities, id.trim())?;
+    let value = setting.parse(input)?;
+
+    Ok((setting, value))
+}
+
+/// Nests `values` by the parts of their ids, e.g. `led.mode` under `led`.
+pub fn to_json(values: &[SettingValue]) -> serde_json::Value {
+    let mut root = serde_json::Map::new();
+
+    for SettingValue { setting, value } in values {
+        let mut parts: Vec<&str> = setting.id.split('.').collect();
+        let last = parts.pop().expect("ids are not empty");
+
+        let mut object = &mut root;
+        for part in parts {
+            object = object
+                .entry(part)
+                .or_insert_with(|| serde_json::Value::Object(Default::default()))
+                .as_object_mut()
+                .expect("no id is a prefix of another");
+        }
+        object.insert(last.to_string(), serde_json::json!(value));
+    }
+
+    serde_json::Value::Object(root)
+}
+
+/// Every setting of the mouse along with its cached value.
+pub fn describe(manager: &MouseManager) -> Vec<SettingValue> {
+    let profile = manager.profile();
+
+    settings(&manager.capabilities())
+        .into_iter()
+        .map(|setting| SettingValue {
+            value: setting.get(&profile),
+            setting,
+        })
+        .collect()
+}
+
+/// The cached value of the setting with `id`.
+pub fn get(manager: &MouseManager, id: &str) -> Result<Value, Error> {
+    let setting = find(&manager.capabilities(), id)?;
+    let value = setting.get(&manager.profile());
+
+    Ok(value)
+}
+
+/// Writes the setting with `id`, returning the blocks written.
+pub fn set(
+    manager: &MouseManager,
+    id: &str,
+    value: &Value,
+) -> Result<Vec<Block>, Box<dyn std::error::Error + Send + Sync>> {
+    let setting = find(&manager.capabilities(), id)?;
+    set_all(manager, &[(setting, value.clone())])
+}
+
+/// Writes several settings in one transaction, see [`MouseManager::transaction`]. A polling
+/// rate is written last, once the transaction went through.
+pub fn set_all(
+    manager: &MouseManager,
+    values: &[(Setting, Value)],
+) -> Result<Vec<Block>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut transaction = manager.transaction();
+    let mut polling_rate = None;
+
+    for (setting, value) in values {
+        match setting.change(value)? {
+            Some(change) => transaction = transaction.stage(change),
+            None => polling_rate = Some(value),
+        }
+    }
+
+    let mut blocks = transaction.commit()?;
+    if let Some(Value::Enum(name)) = polling_rate {
+        manager.set_polling_rate(lookup("polling_rate", &POLLING_RATES, name)?)?;
+        blocks.push(Block::MouseInfo);
+    }
+
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::simulated::SimulatedMouse;
+
+    fn value(id: &str, input: &str) -> Value {
+        find(&Capabilities::default(), id)
+            .unwrap()
+            .parse(input)
+            .unwrap()
+    }
+
+    #[test]
+    fn ids_are_unique_and_follow_capabilities() {
+        let all = settings(&Capabilities::default());
+        let mut ids: Vec<_> = all.iter().map(|setting| setting.id.as_str()).collect();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), all.len());
+
+        let mut capabilities = Capabilities {
+            presets: 4,
+            ..Capabilities::default()
+        };
+        capabilities.features.remove(&Feature::MotionSync);
+        assert!(find(&capabilities, "dpi.4").is_ok());
+        assert!(find(&capabilities, "dpi.5")
+            .unwrap_err()
+            .to_string()
+            .contains("not supported"));
+        assert!(find(&capabilities, "perf.motion_sync").is_err());
+        assert!(find(&capabilities, "perf.nope")
+            .unwrap_err()
+            .to_string()
+            .contains("Unknown"));
+    }
+
+    #[test]
+    fn values_are_parsed_and_checked() {
+        assert_eq!(
+            value("led.mode", "breathing"),
+            Value::Enum("Breathing".into())
+        );
+        assert_eq!(value("polling_rate", "500hz"), Value::Enum("Hz500".into()));
+        assert_eq!(value("led.enabled", "off"), Value::Bool(false));
+        assert_eq!(
+            value("sensor.sleep_time", "1m"),
+            Value::Duration {
+                value: 60,
+                unit: "s"
+            }
+        );
+        assert_eq!(value("sensor.sleep_time", "60").to_string(), "60s");
+
+        let sleep = find(&Capabilities::default(), "sensor.sleep_time").unwrap();
+        assert!(sleep.parse("15s").is_err());
+        assert!(sleep.parse("1h").is_err());
+        let dpi = find(&Capabilities::default(), "dpi.1").unwrap();
+        assert!(dpi.parse("1625").is_err());
+        assert!(dpi.check(&Value::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn settings_are_read_and_written_by_id() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+
+        set(&manager, "dpi.3", &value("dpi.3", "1600")).unwrap();
+        set(
+            &manager,
+            "sensor.sleep_time",
+            &value("sensor.sleep_time", "2m"),
+        )
+        .unwrap();
+        set(&manager, "silent_height", &value("silent_height", "twomm")).unwrap();
+
+        assert_eq!(get(&manager, "dpi.3").unwrap(), Value::Dpi(1600));
+        assert_eq!(
+            get(&manager, "sensor.sleep_time").unwrap().to_string(),
+            "120s"
+        );
+        assert_eq!(
+            get(&manager, "silent_height").unwrap(),
+            Value::Enum("TwoMm".into())
+        );
+
+        let (setting, value) =
+            parse_assignment(&manager.capabilities(), "led.mode = Breathing").unwrap();
+        set_all(&manager, &[(setting, value)]).unwrap();
+        assert_eq!(get(&manager, "led.mode").unwrap().to_string(), "Breathing");
+        assert!(parse_assignment(&manager.capabilities(), "led.mode").is_err());
+
+        let described = describe(&manager);
+        assert_eq!(described.len(), settings(&manager.capabilities()).len());
+        let json = serde_json::to_value(&described[0]).unwrap();
+        assert_eq!(json["id"], "dpi.1");
+        assert_eq!(json["type"], "dpi");
+    }
+
+    #[test]
+    fn paths_select_settings() {
+        let capabilities = Capabilities::default();
+        let ids = |path| -> Vec<String> {
+            resolve(&capabilities, path)
+                .unwrap()
+                .into_iter()
+                .map(|setting| setting.id)
+                .collect()
+        };
+
+        assert_eq!(
+            ids("led"),
+            [
+                "led.mode",
+                "led.brightness",
+                "led.breathing_rate",
+                "led.enabled"
+            ]
+        );
+        assert_eq!(ids("dpi.3"), ["dpi.3"]);
+        assert_eq!(ids("profile").len(), settings(&capabilities).len());
+        assert!(resolve(&capabilities, "le").is_err());
+    }
+
+    #[test]
+    fn json_nests_by_id() {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let json = to_json(&describe(&manager));
+
+        assert!(json["perf"]["motion_sync"].is_boolean());
+        assert!(json["dpi"]["1"].is_u64());
+        assert!(json["polling_rate"].is_string());
+    }
+}
@@ -0,0 +1,19 @@
+pub mod autoswitch;
+pub mod capabilities;
+pub mod capture;
+pub mod cli;
+pub mod color;
+pub mod commands;
+pub mod discovery;
+pub mod eeprom;
+pub mod ffi;
+pub mod introspection;
+pub mod library;
+pub mod manager;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod trace;
+pub mod transport;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod types;
@@ -0,0 +1,723 @@
+//! Named profiles saved under `$XDG_CONFIG_HOME/atk-hub`.
+//!
+//! Every profile lives in a single JSON file along with the default profile of each
+//! device. The file carries a format number, older files are migrated when loaded and
+//! backed up before the migrated library is first saved.
+
+pub mod schema;
+pub mod share;
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    color::Color,
+    commands::prelude::*,
+    eeprom::DeviceIdentity,
+    manager::{transaction::Block, MouseManager, Profile},
+    types::{Decaseconds, Duration, Milliseconds},
+};
+use libatk_rs::prelude::*;
+
+/// Version of the library file format, bumped on incompatible changes.
+pub const LIBRARY_FORMAT: u32 = 1;
+
+/// Upgrades a library in place from one format to the next.
+type Migration = fn(&mut serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+
+/// `MIGRATIONS[n]` upgrades format `n + 1` to `n + 2`.
+const MIGRATIONS: &[Migration] = &[];
+
+const _: () = assert!(MIGRATIONS.len() == LIBRARY_FORMAT as usize - 1);
+
+const PRESETS: [Preset; 8] = [
+    Preset::Preset1,
+    Preset::Preset2,
+    Preset::Preset3,
+    Preset::Preset4,
+    Preset::Preset5,
+    Preset::Preset6,
+    Preset::Preset7,
+    Preset::Preset8,
+];
+
+const LED_MODES: [LedEffectMode; 2] = [LedEffectMode::Static, LedEffectMode::Breathing];
+const BREATHING_RATES: [LedBreathingRate; 3] = [
+    LedBreathingRate::Slow,
+    LedBreathingRate::Medium,
+    LedBreathingRate::Fast,
+];
+const BRIGHTNESS_LEVELS: [LedBrightnessLevel; 3] = [
+    LedBrightnessLevel::Low,
+    LedBrightnessLevel::Medium,
+    LedBrightnessLevel::High,
+];
+const SILENT_HEIGHTS: [SilentHeightMode; 3] = [
+    SilentHeightMode::Off,
+    SilentHeightMode::OneMm,
+    SilentHeightMode::TwoMm,
+];
+
+const DPI_STEP: u16 = 50;
+const MAX_DPI: u16 = 1024 * DPI_STEP;
+
+/// Looks up the variant of `values` whose `Debug` name is `name`.
+fn by_name<T: Copy + std::fmt::Debug>(kind: &str, values: &[T], name: &str) -> Result<T, Error> {
+    values
+        .iter()
+        .copied()
+        .find(|value| format!("{:?}", value).eq_ignore_ascii_case(name))
+        .ok_or_else(|| {
+            let names = values
+                .iter()
+                .map(|value| format!("{:?}", value))
+                .collect::<Vec<_>>();
+            Error::ParseError(format!(
+                "{}: Expected one of {}, got {}",
+                kind,
+                names.join(", "),
+                name
+            ))
+        })
+}
+
+fn gear(profile: &Profile, preset: Preset) -> Gear {
+    let (first, second) = profile.dpi_profile(preset.into());
+    match Slot::from(preset) {
+        Slot::First => first,
+        Slot::Second => second,
+    }
+}
+
+/// DPI and colour of a preset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PresetSnapshot {
+    pub dpi: u16,
+    /// `#rrggbb`, or anything else [`Color`] can parse.
+    pub color: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedSnapshot {
+    pub mode: String,
+    pub brightness: String,
+    pub breathing_rate: String,
+    pub enabled: bool,
+}
+
+/// Times are in milliseconds, the close LED time in whole multiples of 10 seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MousePerformanceSnapshot {
+    pub stabilization_time_ms: u32,
+    pub motion_sync: bool,
+    pub close_led_time_ms: u32,
+    pub linear_correction: bool,
+    pub ripple_control: bool,
+}
+
+/// Times are in milliseconds, the sensor sleep time in whole multiples of 10 seconds.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SensorPerformanceSnapshot {
+    pub move_close_led: bool,
+    pub sensor_sleep: bool,
+    pub sensor_sleep_time_ms: u32,
+    pub performance_mode: bool,
+    pub rf_tx_time_ms: u32,
+}
+
+/// Every writable setting of a [`Profile`], in a form that is stable on disk and can be
+/// edited by hand. Enumerations are stored by name, e.g. `"Breathing"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileSnapshot {
+    pub presets: Vec<PresetSnapshot>,
+    pub led: LedSnapshot,
+    pub mouse_performance: MousePerformanceSnapshot,
+    pub sensor_performance: SensorPerformanceSnapshot,
+    pub far_distance_mode: bool,
+    pub silent_height: String,
+}
+
+impl From<&Profile> for ProfileSnapshot {
+    fn from(profile: &Profile) -> Self {
+        let led = profile.dpi_led_settings();
+        let mouse = profile.mouse_performance_settings();
+        let sensor = profile.sensor_performance_settings();
+
+        ProfileSnapshot {
+            presets: PRESETS
+                .iter()
+                .map(|&preset| {
+                    let gear = gear(profile, preset);
+                    PresetSnapshot {
+                        dpi: gear.dpi().dpi(),
+                        color: gear.color().to_string(),
+                    }
+                })
+                .collect(),
+            led: LedSnapshot {
+                mode: format!("{:?}", led.mode()),
+                brightness: format!("{:?}", led.brightness()),
+                breathing_rate: format!("{:?}", led.breathing_rate()),
+                enabled: led.enabled(),
+            },
+            mouse_performance: MousePerformanceSnapshot {
+                stabilization_time_ms: mouse.stabilization_time().as_millis(),
+                motion_sync: mouse.motion_sync(),
+                close_led_time_ms: mouse.close_led_time().as_millis(),
+                linear_correction: mouse.linear_correction(),
+                ripple_control: mouse.ripple_control(),
+            },
+            sensor_performance: SensorPerformanceSnapshot {
+                move_close_led: sensor.move_close_led(),
+                sensor_sleep: sensor.sensor_sleep(),
+                sensor_sleep_time_ms: sensor.sensor_sleep_time().as_millis(),
+                performance_mode: sensor.performance_mode(),
+                rf_tx_time_ms: sensor.rf_tx_time().as_millis(),
+            },
+            far_distance_mode: profile.far_distance_mode().far_distance_mode(),
+            silent_height: format!("{:?}", profile.silent_height().silent_height()),
+        }
+    }
+}
+
+impl ProfileSnapshot {
+    /// Checks every value without touching a device.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.gears()?;
+        self.led_patch()?;
+        MousePerfSettings::default().apply(&self.mouse_patch())?;
+        SensorPerfSettings::default().apply(&self.sensor_patch())?;
+        by_name("Silent height", &SILENT_HEIGHTS, &self.silent_height)?;
+        Ok(())
+    }
+
+    fn gears(&self) -> Result<Vec<(Dpi, Color)>, Error> {
+        if self.presets.len() != PRESETS.len() {
+            return Err(Error::ParseError(format!(
+                "Profile: Expected {} presets, got {}",
+                PRESETS.len(),
+                self.presets.len()
+            )));
+        }
+
+        self.presets
+            .iter()
+            .map(|preset| {
+                if !(DPI_STEP..=MAX_DPI).contains(&preset.dpi) || preset.dpi % DPI_STEP != 0 {
+                    return Err(Error::ParseError(format!(
+                        "DPI: {} is not a multiple of {} up to {}",
+                        preset.dpi, DPI_STEP, MAX_DPI
+                    )));
+                }
+                Ok((Dpi::new(preset.dpi), preset.color.parse()?))
+            })
+            .collect()
+    }
+
+    fn led_patch(&self) -> Result<LedPatch, Error> {
+        Ok(LedPatch::new()
+            .mode(by_name("LED mode", &LED_MODES, &self.led.mode)?)
+            .brightness(by_name(
+                "LED brightness",
+                &BRIGHTNESS_LEVELS,
+                &self.led.brightness,
+            )?)
+            .breathing_rate(by_name(
+                "LED breathing rate",
+                &BREATHING_RATES,
+                &self.led.breathing_rate,
+            )?)
+            .enabled(self.led.enabled))
+    }
+
+    fn mouse_patch(&self) -> MousePerfPatch {
+        let settings = &self.mouse_performance;
+        MousePerfPatch::new()
+            .stabilization_time(Duration::<Milliseconds>::from_millis(
+                settings.stabilization_time_ms,
+            ))
+            .motion_sync(settings.motion_sync)
+            .close_led_time(Duration::<Decaseconds>::from_millis(
+                settings.close_led_time_ms,
+            ))
+            .linear_correction(settings.linear_correction)
+            .ripple_control(settings.ripple_control)
+    }
+
+    fn sensor_patch(&self) -> SensorPerfPatch {
+        let settings = &self.sensor_performance;
+        SensorPerfPatch::new()
+            .move_close_led(settings.move_close_led)
+            .sensor_sleep(settings.sensor_sleep)
+            .sensor_sleep_time(Duration::<Decaseconds>::from_millis(
+                settings.sensor_sleep_time_ms,
+            ))
+            .performance_mode(settings.performance_mode)
+            .rf_tx_time(Duration::<Milliseconds>::from_millis(
+                settings.rf_tx_time_ms,
+            ))
+    }
+
+    /// Writes the settings that differ from the cached profile of `manager` in one
+    /// transaction and returns the blocks written, none if the profile is already active.
+    pub fn apply(
+        &self,
+        manager: &MouseManager,
+    ) -> Result<Vec<Block>, Box<dyn std::error::Error + Send + Sync>> {
+        self.validate()?;
+        let current = ProfileSnapshot::from(&*manager.profile());
+
+        let mut transaction = manager.transaction();
+        if self.led != current.led {
+            transaction = transaction.dpi_led_settings(self.led_patch()?);
+        }
+        if self.mouse_performance != current.mouse_performance {
+            transaction = transaction.mouse_performance_settings(self.mouse_patch());
+        }
+        if self.sensor_performance != current.sensor_performance {
+            transaction = transaction.sensor_performance_settings(self.sensor_patch());
+        }
+        if self.far_distance_mode != current.far_distance_mode {
+            transaction = transaction.far_distance_mode(self.far_distance_mode);
+        }
+        if self.silent_height != current.silent_height {
+            transaction = transaction.silent_height(by_name(
+                "Silent height",
+                &SILENT_HEIGHTS,
+                &self.silent_height,
+            )?);
+        }
+
+        let gears = self.gears()?;
+        for ((&preset, (dpi, color)), saved) in PRESETS.iter().zip(gears).zip(&current.presets) {
+            if dpi.dpi() != saved.dpi {
+                transaction = transaction.dpi(preset, dpi);
+            }
+            if color.to_string() != saved.color {
+                transaction = transaction.color(preset, color);
+            }
+        }
+
+        Ok(transaction.commit()?)
+    }
+}
+
+/// Identifies a single mouse, or every mouse of a model when the serial is unknown.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceKey {
+    pub cid: u8,
+    pub mid: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial: Option<String>,
+}
+
+impl std::fmt::Display for DeviceKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.cid, self.mid)?;
+        if let Some(serial) = &self.serial {
+            write!(f, ":{}", serial)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeviceKey {
+    /// Queries the CID and MID of the mouse behind `manager`, the serial comes from the HID
+    /// interface, see [`crate::discovery::serial_number`].
+    pub fn query(
+        manager: &MouseManager,
+        serial: Option<String>,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let identity = DeviceIdentity::query(manager)?;
+        Ok(DeviceKey {
+            cid: identity.cid,
+            mid: identity.mid,
+            serial: serial.filter(|serial| !serial.is_empty()),
+        })
+    }
+
+    /// The same key without the serial, matching every mouse of the model.
+    pub fn model(&self) -> Self {
+        DeviceKey {
+            serial: None,
+            ..self.clone()
+        }
+    }
+
+    pub fn same_model(&self, other: &DeviceKey) -> bool {
+        self.cid == other.cid && self.mid == other.mid
+    }
+}
+
+/// A profile saved in the [`Library`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedProfile {
+    /// Seconds since the Unix epoch.
+    pub created: u64,
+    /// The mouse the profile was saved from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceKey>,
+    pub settings: ProfileSnapshot,
+}
+
+impl SavedProfile {
+    pub fn new(settings: ProfileSnapshot, device: Option<DeviceKey>) -> Self {
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+
+        SavedProfile {
+            created,
+            device,
+            settings,
+        }
+    }
+}
+
+/// Named profiles and the default profile of each device.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Library {
+    pub format: u32,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, SavedProfile>,
+    /// Profile names keyed by the string form of a [`DeviceKey`].
+    #[serde(default)]
+    pub defaults: BTreeMap<String, String>,
+    /// Format of the file this library was migrated from.
+    #[serde(skip)]
+    pub migrated_from: Option<u32>,
+}
+
+impl Default for Library {
+    fn default() -> Self {
+        Library {
+            format: LIBRARY_FORMAT,
+            profiles: BTreeMap::new(),
+            defaults: BTreeMap::new(),
+            migrated_from: None,
+        }
+    }
+}
+
+fn check_name(name: &str) -> Result<(), Error> {
+    if name.is_empty() || name.trim() != name || name.chars().any(char::is_control) {
+        return Err(Error::ParseError(format!(
+            "Profile: Invalid name {:?}, names must not be empty or start or end with spaces",
+            name
+        )));
+    }
+    Ok(())
+}
+
+fn not_found(name: &str) -> Error {
+    Error::ParseError(format!("Profile: No profile named {:?}", name))
+}
+
+impl Library {
+    /// Parses a library, migrating files written in an older format.
+    pub fn from_json(json: &str) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut value: serde_json::Value = serde_json::from_str(json)?;
+
+        let format = value
+            .get("format")
+            .and_then(serde_json::Value::as_u64)
+            .and_then(|format| u32::try_from(format).ok())
+            .ok_or("Profile library has no format number")?;
+        if format == 0 || format > LIBRARY_FORMAT {
+            return Err(format!(
+                "Unsupported profile library format {}, expected at most {}",
+                format, LIBRARY_FORMAT
+            )
+            .into());
+        }
+
+        for migration in &MIGRATIONS[format as usize - 1..] {
+            migration(&mut value)?;
+        }
+        value["format"] = LIBRARY_FORMAT.into();
+
+        let mut library: Library = serde_json::from_value(value)?;
+        library.migrated_from = (format != LIBRARY_FORMAT).then_some(format);
+
+        Ok(library)
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&SavedProfile, Error> {
+        self.profiles.get(name).ok_or_else(|| not_found(name))
+    }
+
+    /// Saves `profile` as `name`, replacing an existing profile only if `overwrite` is set.
+    pub fn insert(
+        &mut self,
+        name: &str,
+        profile: SavedProfile,
+        overwrite: bool,
+    ) -> Result<(), Error> {
+        check_name(name)?;
+        profile.settings.validate()?;
+        if !overwrite && self.profiles.contains_key(name) {
+            return Err(Error::ParseError(format!(
+                "Profile: {:?} already exists",
+                name
+            )));
+        }
+
+        self.profiles.insert(name.to_string(), profile);
+        Ok(())
+    }
+
+    /// Deletes `name` and unsets it as the default of every device.
+    pub fn remove(&mut self, name: &str) -> Result<SavedProfile, Error> {
+        let profile = self.profiles.remove(name).ok_or_else(|| not_found(name))?;
+        self.defaults.retain(|_, default| default != name);
+        Ok(profile)
+    }
+
+    /// Renames `from` to `to`, keeping it the default of the same devices.
+    pub fn rename(&mut self, from: &str, to: &str) -> Result<(), Error> {
+        check_name(to)?;
+        if self.profiles.contains_key(to) {
+            return Err(Error::ParseError(format!(
+                "Profile: {:?} already exists",
+                to
+            )));
+        }
+
+        let profile = self.profiles.remove(from).ok_or_else(|| not_found(from))?;
+        self.profiles.insert(to.to_string(), profile);
+        for default in self
+            .defaults
+            .values_mut()
+            .filter(|default| *default == from)
+        {
+            *default = to.to_string();
+        }
+
+        Ok(())
+    }
+
+    pub fn set_default(&mut self, device: &DeviceKey, name: &str) -> Result<(), Error> {
+        self.get(name)?;
+        self.defaults.insert(device.to_string(), name.to_string());
+        Ok(())
+    }
+
+    /// Unsets the default of `device` and returns the profile it pointed to.
+    pub fn clear_default(&mut self, device: &DeviceKey) -> Option<String> {
+        self.defaults.remove(&device.to_string())
+    }
+
+    /// The default profile of `device`, falling back to the default of its model.
+    pub fn default_for(&self, device: &DeviceKey) -> Option<&str> {
+        self.defaults
+            .get(&device.to_string())
+            .or_else(|| self.defaults.get(&device.model().to_string()))
+            .map(String::as_str)
+    }
+
+    /// The devices `name` is the default profile of.
+    pub fn defaults_of(&self, name: &str) -> Vec<&str> {
+        self.defaults
+            .iter()
+            .filter(|(_, default)| *default == name)
+            .map(|(device, _)| device.as_str())
+            .collect()
+    }
+}
+
+/// `$XDG_CONFIG_HOME/atk-hub`, or `~/.config/atk-hub` if `XDG_CONFIG_HOME` is unset or not
+/// an absolute path.
+pub fn config_dir() -> Result<PathBuf, Error> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))
+        .ok_or_else(|| {
+            Error::ParseError("Config: Neither XDG_CONFIG_HOME nor HOME is set".to_string())
+        })?;
+
+    Ok(config.join("atk-hub"))
+}
+
+/// The file a [`Library`] is kept in.
+pub struct ProfileStore {
+    path: PathBuf,
+}
+
+impl ProfileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        ProfileStore { path: path.into() }
+    }
+
+    /// `profiles.json` in the [`config_dir`].
+    pub fn open_default() -> Result<Self, Error> {
+        Ok(ProfileStore::new(config_dir()?.join("profiles.json")))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the library, an empty one if the file does not exist yet.
+    pub fn load(&self) -> Result<Library, Box<dyn std::error::Error + Send + Sync>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => Library::from_json(&json)
+                .map_err(|e| format!("{}: {}", self.path.display(), e).into()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Library::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Replaces the file with `library`. A library migrated from an older format is backed
+    /// up next to it first, e.g. to `profiles.v1.json`.
+    pub fn save(&self, library: &Library) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        if let Some(format) = library.migrated_from {
+            let backup = self.path.with_extension(format!("v{}.json", format));
+            if !backup.exists() && self.path.exists() {
+                std::fs::copy(&self.path, backup)?;
+            }
+        }
+
+        // Written next to the library and renamed over it so a crash never leaves half a file.
+        let temporary = self.path.with_extension("json.tmp");
+        std::fs::write(&temporary, library.to_json()?)?;
+        std::fs::rename(temporary, &self.path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::simulated::SimulatedMouse;
+
+    fn manager() -> MouseManager {
+        MouseManager::new(SimulatedMouse::default()).unwrap()
+    }
+
+    fn key(serial: Option<&str>) -> DeviceKey {
+        DeviceKey {
+            cid: 3,
+            mid: 12,
+            serial: serial.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn activating_writes_only_what_differs() {
+        let manager = manager();
+        let saved = ProfileSnapshot::from(&*manager.profile());
+        assert!(saved.apply(&manager).unwrap().is_empty());
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset3, Dpi::new(1000))
+            .unwrap();
+        manager.set_far_distance_mode(true).unwrap();
+
+        let blocks = saved.apply(&manager).unwrap();
+        assert_eq!(
+            blocks,
+            vec![
+                Block::FarDistanceMode,
+                Block::Dpi(Pair::from(Preset::Preset3))
+            ]
+        );
+        assert_eq!(ProfileSnapshot::from(&*manager.profile()), saved);
+    }
+
+    #[test]
+    fn invalid_snapshots_are_rejected() {
+        let manager = manager();
+        let valid = ProfileSnapshot::from(&*manager.profile());
+
+        let mut snapshot = valid.clone();
+        snapshot.presets[0].dpi = 425;
+        assert!(snapshot.validate().is_err());
+
+        let mut snapshot = valid.clone();
+        snapshot.led.mode = "Rainbow".to_string();
+        assert!(snapshot.apply(&manager).is_err());
+
+        let mut snapshot = valid.clone();
+        snapshot.presets.pop();
+        assert!(snapshot.validate().is_err());
+
+        let mut snapshot = valid;
+        snapshot.silent_height = "twomm".to_string();
+        snapshot.validate().unwrap();
+    }
+
+    #[test]
+    fn renaming_and_removing_keep_defaults_consistent() {
+        let settings = ProfileSnapshot::from(&*manager().profile());
+        let mut library = Library::default();
+        library
+            .insert("fps", SavedProfile::new(settings.clone(), None), false)
+            .unwrap();
+        library
+            .insert("work", SavedProfile::new(settings.clone(), None), false)
+            .unwrap();
+        assert!(library
+            .insert("fps", SavedProfile::new(settings.clone(), None), false)
+            .is_err());
+        assert!(library
+            .insert(" fps", SavedProfile::new(settings, None), false)
+            .is_err());
+
+        library.set_default(&key(Some("A1")), "fps").unwrap();
+        library.set_default(&key(None), "work").unwrap();
+        assert_eq!(library.default_for(&key(Some("A1"))), Some("fps"));
+        assert_eq!(library.default_for(&key(Some("B2"))), Some("work"));
+        assert!(library.set_default(&key(None), "mmo").is_err());
+
+        assert!(library.rename("fps", "work").is_err());
+        library.rename("fps", "aim").unwrap();
+        assert_eq!(library.default_for(&key(Some("A1"))), Some("aim"));
+        assert_eq!(library.defaults_of("aim"), vec!["3:12:A1"]);
+
+        library.remove("aim").unwrap();
+        assert_eq!(library.default_for(&key(Some("A1"))), Some("work"));
+        assert!(library.remove("aim").is_err());
+    }
+
+    #[test]
+    fn unsupported_formats_are_rejected() {
+        assert!(Library::from_json(r#"{"profiles": {}}"#).is_err());
+        assert!(Library::from_json(r#"{"format": 0}"#).is_err());
+        assert!(Library::from_json(&format!(r#"{{"format": {}}}"#, LIBRARY_FORMAT + 1)).is_err());
+
+        let library = Library::from_json(&format!(r#"{{"format": {}}}"#, LIBRARY_FORMAT)).unwrap();
+        assert_eq!(library, Library::default());
+    }
+
+    #[test]
+    fn store_round_trips() {
+        let dir = std::env::temp_dir().join(format!("atk-hub-library-{}", std::process::id()));
+        let store = ProfileStore::new(dir.join("atk-hub").join("profiles.json"));
+        assert_eq!(store.load().unwrap(), Library::default());
+
+        let mut library = Library::default();
+        let settings = ProfileSnapshot::from(&*manager().profile());
+        library
+            .insert("mmo", SavedProfile::new(settings, Some(key(None))), false)
+            .unwrap();
+        store.save(&library).unwrap();
+        assert_eq!(store.load().unwrap(), library);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,679 @@
+//! JSON Schema of profile files and validation against it.
+//!
+//! [`profile_schema`] describes a [`SavedProfile`] as printed by `atk-hub profile show`,
+//! with the settings themselves under `$defs/settings`. Enumerations are listed with the
+//! names atk-hub writes, e.g. `"Breathing"`, even though loading also accepts other cases.
+//!
+//! [`validate`] checks a file against the generated schema and, given a device, against
+//! its [`Capabilities`]. Problems are located by JSON pointer, e.g.
+//! `/settings/presets/2/dpi`.
+
+use serde_json::{json, Map, Value};
+
+use super::*;
+use crate::{
+    capabilities::{Capabilities, Feature},
+    types::{Duration, DurationRange, TimeUnit},
+};
+
+/// Settings only some models have, by their location in a [`ProfileSnapshot`].
+const FEATURES: [(&str, Feature); 16] = [
+    ("/led/mode", Feature::DpiLed),
+    ("/led/brightness", Feature::DpiLed),
+    ("/led/breathing_rate", Feature::DpiLed),
+    ("/led/enabled", Feature::DpiLed),
+    (
+        "/mouse_performance/stabilization_time_ms",
+        Feature::StabilizationTime,
+    ),
+    ("/mouse_performance/motion_sync", Feature::MotionSync),
+    (
+        "/mouse_performance/close_led_time_ms",
+        Feature::CloseLedTime,
+    ),
+    (
+        "/mouse_performance/linear_correction",
+        Feature::LinearCorrection,
+    ),
+    ("/mouse_performance/ripple_control", Feature::RippleControl),
+    ("/sensor_performance/move_close_led", Feature::MoveCloseLed),
+    ("/sensor_performance/sensor_sleep", Feature::SensorSleep),
+    (
+        "/sensor_performance/sensor_sleep_time_ms",
+        Feature::SensorSleep,
+    ),
+    (
+        "/sensor_performance/performance_mode",
+        Feature::PerformanceMode,
+    ),
+    ("/sensor_performance/rf_tx_time_ms", Feature::RfTxTime),
+    ("/far_distance_mode", Feature::FarDistanceMode),
+    ("/silent_height", Feature::SilentHeight),
+];
+
+/// A problem found in a profile file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Violation {
+    /// JSON pointer to the offending value, empty for the whole document.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.pointer.is_empty() {
+            true => write!(f, "/: {}", self.message),
+            false => write!(f, "{}: {}", self.pointer, self.message),
+        }
+    }
+}
+
+impl Violation {
+    fn new(pointer: &str, message: impl std::fmt::Display) -> Self {
+        Violation {
+            pointer: pointer.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Appends `token` to `pointer`, escaped as in RFC 6901.
+fn child(pointer: &str, token: &str) -> String {
+    format!(
+        "{}/{}",
+        pointer,
+        token.replace('~', "~0").replace('/', "~1")
+    )
+}
+
+fn object(description: &str, properties: Value) -> Value {
+    let required: Vec<&String> = properties
+        .as_object()
+        .expect("properties are an object")
+        .keys()
+        .collect();
+
+    json!({
+        "type": "object",
+        "description": description,
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+fn boolean(description: &str) -> Value {
+    json!({ "type": "boolean", "description": description })
+}
+
+fn names<T: std::fmt::Debug>(description: &str, values: &[T]) -> Value {
+    let names: Vec<String> = values.iter().map(|value| format!("{:?}", value)).collect();
+    json!({ "type": "string", "description": description, "enum": names })
+}
+
+fn millis<T: TimeUnit>(description: &str, range: &DurationRange<T>) -> Value {
+    json!({
+        "type": "integer",
+        "description": description,
+        "minimum": range.min().as_millis(),
+        "maximum": range.max().as_millis(),
+        "multipleOf": range.step().as_millis(),
+    })
+}
+
+/// JSON Schema (draft 2020-12) of a saved profile.
+pub fn profile_schema() -> Value {
+    let settings = object(
+        "Every writable setting of a profile",
+        json!({
+            "presets": {
+                "type": "array",
+                "description": "DPI presets, in the order the DPI button cycles through them",
+                "items": { "$ref": "#/$defs/preset" },
+                "minItems": PRESETS.len(),
+                "maxItems": PRESETS.len(),
+            },
+            "led": { "$ref": "#/$defs/led" },
+            "mouse_performance": { "$ref": "#/$defs/mouse_performance" },
+            "sensor_performance": { "$ref": "#/$defs/sensor_performance" },
+            "far_distance_mode": boolean("Far distance mode"),
+            "silent_height": names("Silent height", &SILENT_HEIGHTS),
+        }),
+    );
+
+    let preset = object(
+        "DPI and colour of a preset",
+        json!({
+            "dpi": {
+                "type": "integer",
+                "minimum": DPI_STEP,
+                "maximum": MAX_DPI,
+                "multipleOf": DPI_STEP,
+            },
+            "color": {
+                "type": "string",
+                "description": "#rrggbb, a CSS colour name, rgb(), hsl() or hsv()",
+                "format": "color",
+            },
+        }),
+    );
+
+    let led = object(
+        "DPI indicator LED",
+        json!({
+            "mode": names("Effect", &LED_MODES),
+            "brightness": names("Brightness", &BRIGHTNESS_LEVELS),
+            "breathing_rate": names("Breathing speed", &BREATHING_RATES),
+            "enabled": boolean("Whether the LED is lit"),
+        }),
+    );
+
+    let mouse_performance = object(
+        "Mouse performance settings, times in milliseconds",
+        json!({
+            "stabilization_time_ms": millis("Key stabilization time", &STABILIZATION_TIME_RANGE),
+            "motion_sync": boolean("Motion sync"),
+            "close_led_time_ms": millis("Idle time before the LED turns off", &CLOSE_LED_TIME_RANGE),
+            "linear_correction": boolean("Linear correction"),
+            "ripple_control": boolean("Ripple control"),
+        }),
+    );
+
+    let sensor_performance = object(
+        "Sensor performance settings, times in milliseconds",
+        json!({
+            "move_close_led": boolean("Turn the LED off while moving"),
+            "sensor_sleep": boolean("Let the sensor sleep when idle"),
+            "sensor_sleep_time_ms": millis("Idle time before the sensor sleeps", &SENSOR_SLEEP_TIME_RANGE),
+            "performance_mode": boolean("Performance mode"),
+            "rf_tx_time_ms": millis("RF transmission time", &RF_TX_TIME_RANGE),
+        }),
+    );
+
+    let mut device = object(
+        "The mouse the profile was saved from",
+        json!({
+            "cid": { "type": "integer", "minimum": 0, "maximum": 255 },
+            "mid": { "type": "integer", "minimum": 0, "maximum": 255 },
+            "serial": { "type": "string" },
+        }),
+    );
+    device["required"] = json!(["cid", "mid"]);
+
+    let mut profile = object(
+        "A profile saved by atk-hub",
+        json!({
+            "created": {
+                "type": "integer",
+                "description": "Seconds since the Unix epoch",
+                "minimum": 0,
+            },
+            "device": { "$ref": "#/$defs/device" },
+            "settings": { "$ref": "#/$defs/settings" },
+        }),
+    );
+    profile["required"] = json!(["created", "settings"]);
+
+    let mut schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "atk-hub profile",
+        "$defs": {
+            "settings": settings,
+            "preset": preset,
+            "led": led,
+            "mouse_performance": mouse_performance,
+            "sensor_performance": sensor_performance,
+            "device": device,
+        },
+    });
+    schema
+        .as_object_mut()
+        .unwrap()
+        .extend(profile.as_object().unwrap().clone());
+
+    schema
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Checks values against the subset of JSON Schema that [`profile_schema`] uses.
+struct Validator<'a> {
+    root: &'a Value,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_> {
+    fn check(&mut self, schema: &Value, value: &Value, pointer: &str) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|path| self.root.pointer(path))
+                .expect("references of the profile schema resolve");
+            return self.check(target, value, pointer);
+        }
+
+        if let Some(expected) = schema.get("type").and_then(Value::as_str) {
+            if type_name(value) != expected {
+                let message = format!("Expected {}, got {}", expected, type_name(value));
+                return self.violations.push(Violation::new(pointer, message));
+            }
+        }
+
+        match value {
+            Value::Object(members) => self.check_object(schema, members, pointer),
+            Value::Array(items) => self.check_array(schema, items, pointer),
+            Value::Number(number) => self.check_integer(schema, number.as_i64(), pointer),
+            Value::String(text) => self.check_string(schema, text, pointer),
+            _ => {}
+        }
+    }
+
+    fn check_object(&mut self, schema: &Value, members: &Map<String, Value>, pointer: &str) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        for name in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !members.contains_key(name) {
+                let message = format!("Missing property {:?}", name);
+                self.violations.push(Violation::new(pointer, message));
+            }
+        }
+
+        for (name, member) in members {
+            match properties.and_then(|properties| properties.get(name)) {
+                Some(property) => self.check(property, member, &child(pointer, name)),
+                None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                    let message = format!("Unknown property {:?}", name);
+                    self.violations
+                        .push(Violation::new(&child(pointer, name), message));
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &Value, items: &[Value], pointer: &str) {
+        let count = items.len() as u64;
+        let min = schema.get("minItems").and_then(Value::as_u64);
+        let max = schema.get("maxItems").and_then(Value::as_u64);
+        if min.is_some_and(|min| count < min) || max.is_some_and(|max| count > max) {
+            let expected = match (min, max) {
+                (Some(min), Some(max)) if min == max => min.to_string(),
+                (Some(min), Some(max)) => format!("{} to {}", min, max),
+                (Some(min), None) => format!("at least {}", min),
+                (None, _) => format!("at most {}", max.unwrap_or_default()),
+            };
+            let message = format!("Expected {} items, got {}", expected, count);
+            self.violations.push(Violation::new(pointer, message));
+        }
+
+        if let Some(schema) = schema.get("items") {
+            for (index, item) in items.iter().enumerate() {
+                self.check(schema, item, &child(pointer, &index.to_string()));
+            }
+        }
+    }
+
+    fn check_integer(&mut self, schema: &Value, value: Option<i64>, pointer: &str) {
+        let Some(value) = value else {
+            return;
+        };
+        let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_i64);
+
+        let message = if let Some(min) = bound("minimum").filter(|&min| value < min) {
+            format!("{} is less than the minimum of {}", value, min)
+        } else if let Some(max) = bound("maximum").filter(|&max| value > max) {
+            format!("{} is greater than the maximum of {}", value, max)
+        } else if let Some(step) = bound("multipleOf").filter(|&step| value % step != 0) {
+            format!("{} is not a multiple of {}", value, step)
+        } else {
+            return;
+        };
+
+        self.violations.push(Violation::new(pointer, message));
+    }
+
+    fn check_string(&mut self, schema: &Value, text: &str, pointer: &str) {
+        if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+            if !values.iter().any(|value| value == text) {
+                let names: Vec<&str> = values.iter().filter_map(Value::as_str).collect();
+                let message = format!("Expected one of {}, got {:?}", names.join(", "), text);
+                self.violations.push(Violation::new(pointer, message));
+            }
+        }
+
+        if schema.get("format").and_then(Value::as_str) == Some("color") {
+            if let Err(e) = text.parse::<Color>() {
+                self.violations.push(Violation::new(pointer, e));
+            }
+        }
+    }
+}
+
+/// Checks `value` against [`profile_schema`], reporting pointers below `pointer`.
+pub fn check_schema(value: &Value, pointer: &str) -> Vec<Violation> {
+    let schema = profile_schema();
+    let mut validator = Validator {
+        root: &schema,
+        violations: Vec::new(),
+    };
+    validator.check(&schema, value, pointer);
+
+    validator.violations
+}
+
+/// Whether `name` is the name of one of `values`.
+fn listed<T: std::fmt::Debug>(values: &[T], name: &str) -> bool {
+    values.iter().any(|value| format!("{:?}", value) == name)
+}
+
+/// Finds the settings of `settings` that the model behind `capabilities` can not store.
+///
+/// Settings the model does not have are only reported when they differ from `current`,
+/// the configuration of the device, as writing them back unchanged is harmless. Pointers
+/// are reported below `pointer`.
+pub fn check_capabilities(
+    settings: &ProfileSnapshot,
+    current: &ProfileSnapshot,
+    capabilities: &Capabilities,
+    pointer: &str,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let at = |path: &str| format!("{}{}", pointer, path);
+
+    for (index, (preset, saved)) in settings.presets.iter().zip(&current.presets).enumerate() {
+        let path = at(&format!("/presets/{}", index));
+        if index < capabilities.presets as usize {
+            if let Err(e) = capabilities.check_dpi(Dpi::new(preset.dpi)) {
+                violations.push(Violation::new(&child(&path, "dpi"), e));
+            }
+            continue;
+        }
+
+        let color_changed = preset.color.parse::<Color>().ok() != saved.color.parse().ok();
+        for (field, changed) in [("dpi", preset.dpi != saved.dpi), ("color", color_changed)] {
+            if changed {
+                let error = capabilities.unsupported(format!("{:?}", PRESETS[index]));
+                violations.push(Violation::new(&child(&path, field), error));
+            }
+        }
+    }
+
+    let (ours, theirs) = (json!(settings), json!(current));
+    for (path, feature) in FEATURES {
+        if ours.pointer(path) != theirs.pointer(path) && !capabilities.supports(feature) {
+            let error = capabilities.unsupported(format!("{:?}", feature));
+            violations.push(Violation::new(&at(path), error));
+        }
+    }
+
+    let (mouse, saved_mouse) = (&settings.mouse_performance, &current.mouse_performance);
+    let (sensor, saved_sensor) = (&settings.sensor_performance, &current.sensor_performance);
+    let timings = [
+        check_timing(
+            &capabilities.stabilization_time_range(),
+            mouse.stabilization_time_ms,
+            saved_mouse.stabilization_time_ms,
+            at("/mouse_performance/stabilization_time_ms"),
+        ),
+        check_timing(
+            &capabilities.close_led_time_range(),
+            mouse.close_led_time_ms,
+            saved_mouse.close_led_time_ms,
+            at("/mouse_performance/close_led_time_ms"),
+        ),
+        check_timing(
+            &capabilities.sensor_sleep_time_range(),
+            sensor.sensor_sleep_time_ms,
+            saved_sensor.sensor_sleep_time_ms,
+            at("/sensor_performance/sensor_sleep_time_ms"),
+        ),
+        check_timing(
+            &capabilities.rf_tx_time_range(),
+            sensor.rf_tx_time_ms,
+            saved_sensor.rf_tx_time_ms,
+            at("/sensor_performance/rf_tx_time_ms"),
+        ),
+    ];
+    violations.extend(timings.into_iter().flatten());
+
+    let led = &capabilities.led;
+    for (field, value, saved, supported) in [
+        (
+            "mode",
+            &settings.led.mode,
+            &current.led.mode,
+            listed(&led.modes, &settings.led.mode),
+        ),
+        (
+            "brightness",
+            &settings.led.brightness,
+            &current.led.brightness,
+            listed(&led.brightness, &settings.led.brightness),
+        ),
+        (
+            "breathing_rate",
+            &settings.led.breathing_rate,
+            &current.led.breathing_rate,
+            listed(&led.breathing_rates, &settings.led.breathing_rate),
+        ),
+    ] {
+        if value != saved && !supported {
+            let error = capabilities.unsupported(value);
+            violations.push(Violation::new(&at(&format!("/led/{}", field)), error));
+        }
+    }
+
+    violations
+}
+
+/// Fails if a changed timing is outside the `range` of the model.
+fn check_timing<T: TimeUnit>(
+    range: &DurationRange<T>,
+    millis: u32,
+    saved: u32,
+    pointer: String,
+) -> Option<Violation> {
+    match millis != saved {
+        true => range
+            .check(Duration::from_millis(millis))
+            .err()
+            .map(|e| Violation::new(&pointer, e)),
+        false => None,
+    }
+}
+
+/// Checks one saved profile at `pointer`: the schema first, then the device.
+fn validate_profile(
+    value: &Value,
+    pointer: &str,
+    device: Option<(&Capabilities, &ProfileSnapshot)>,
+) -> Vec<Violation> {
+    let violations = check_schema(value, pointer);
+    if !violations.is_empty() {
+        return violations;
+    }
+
+    let settings = child(pointer, "settings");
+    let profile = match serde_json::from_value::<SavedProfile>(value.clone()) {
+        Ok(profile) => profile,
+        Err(e) => return vec![Violation::new(pointer, e)],
+    };
+    if let Err(e) = profile.settings.validate() {
+        return vec![Violation::new(&settings, e)];
+    }
+
+    match device {
+        Some((capabilities, current)) => {
+            check_capabilities(&profile.settings, current, capabilities, &settings)
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Checks a profile file, either a single profile as printed by `atk-hub profile show` or a
+/// whole [`Library`]. `device` adds the checks of [`check_capabilities`].
+///
+/// Fails only if `json` is not JSON at all, every other problem is returned.
+pub fn validate(
+    json: &str,
+    device: Option<(&Capabilities, &ProfileSnapshot)>,
+) -> Result<Vec<Violation>, serde_json::Error> {
+    let value: Value = serde_json::from_str(json)?;
+    if value.get("format").is_none() {
+        return Ok(validate_profile(&value, "", device));
+    }
+
+    let violations: Vec<Violation> = match value.get("profiles").and_then(Value::as_object) {
+        Some(profiles) => profiles
+            .iter()
+            .flat_map(|(name, profile)| {
+                validate_profile(profile, &child("/profiles", name), device)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+    if !violations.is_empty() {
+        return Ok(violations);
+    }
+
+    Ok(match Library::from_json(json) {
+        Ok(_) => Vec::new(),
+        Err(e) => vec![Violation::new("", e)],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{capabilities::LedCapabilities, transport::simulated::SimulatedMouse};
+
+    fn snapshot() -> ProfileSnapshot {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let snapshot = ProfileSnapshot::from(&*manager.profile());
+        snapshot
+    }
+
+    fn document() -> Value {
+        json!(SavedProfile::new(snapshot(), None))
+    }
+
+    fn pointers(violations: &[Violation]) -> Vec<&str> {
+        violations
+            .iter()
+            .map(|violation| violation.pointer.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn saved_profiles_match_the_schema() {
+        let json = document().to_string();
+        assert_eq!(validate(&json, None).unwrap(), vec![]);
+
+        let mut library = Library::default();
+        library
+            .insert("fps", SavedProfile::new(snapshot(), None), false)
+            .unwrap();
+        assert_eq!(validate(&library.to_json().unwrap(), None).unwrap(), vec![]);
+
+        assert!(validate("{ \"created\": ", None).is_err());
+    }
+
+    #[test]
+    fn violations_point_at_the_value() {
+        let mut document = document();
+        document["settings"]["presets"][2]["dpi"] = json!(425);
+        document["settings"]["presets"][5]["color"] = json!("#12345");
+        document["settings"]["led"]["mode"] = json!("Rainbow");
+        document["settings"]["mouse_performance"]["close_led_time_ms"] = json!(15000);
+        document["settings"]["sensor_performance"]["rf_tx_time_ms"] = json!("4ms");
+        document["settings"]["silent_hieght"] = json!("Off");
+        document["settings"]
+            .as_object_mut()
+            .unwrap()
+            .remove("silent_height");
+
+        let violations = validate(&document.to_string(), None).unwrap();
+        assert_eq!(
+            pointers(&violations),
+            [
+                "/settings",
+                "/settings/led/mode",
+                "/settings/mouse_performance/close_led_time_ms",
+                "/settings/presets/2/dpi",
+                "/settings/presets/5/color",
+                "/settings/sensor_performance/rf_tx_time_ms",
+                "/settings/silent_hieght",
+            ]
+        );
+        assert_eq!(violations[3].message, "425 is not a multiple of 50");
+        assert_eq!(
+            violations[0].to_string(),
+            "/settings: Missing property \"silent_height\""
+        );
+
+        let mut library = Library::default();
+        library
+            .insert("a/b", SavedProfile::new(snapshot(), None), false)
+            .unwrap();
+        let mut library: Value = serde_json::from_str(&library.to_json().unwrap()).unwrap();
+        library["profiles"]["a/b"]["settings"]["far_distance_mode"] = json!(1);
+        assert_eq!(
+            pointers(&validate(&library.to_string(), None).unwrap()),
+            ["/profiles/a~1b/settings/far_distance_mode"]
+        );
+    }
+
+    #[test]
+    fn capabilities_limit_what_may_change() {
+        let current = snapshot();
+        let capabilities = Capabilities {
+            max_dpi: 16000,
+            presets: 4,
+            led: LedCapabilities {
+                modes: vec![LedEffectMode::Static],
+                ..LedCapabilities::default()
+            },
+            features: [Feature::DpiLed, Feature::MotionSync].into_iter().collect(),
+            ..Capabilities::default()
+        };
+        let device = Some((&capabilities, &current));
+
+        let unchanged = document().to_string();
+        assert_eq!(validate(&unchanged, device).unwrap(), vec![]);
+
+        let mut document = document();
+        document["settings"]["presets"][1]["dpi"] = json!(26000);
+        document["settings"]["presets"][6]["color"] = json!("red");
+        document["settings"]["led"]["mode"] = json!("Breathing");
+        document["settings"]["mouse_performance"]["motion_sync"] = json!(true);
+        document["settings"]["far_distance_mode"] = json!(true);
+
+        let violations = validate(&document.to_string(), device).unwrap();
+        assert_eq!(
+            pointers(&violations),
+            [
+                "/settings/presets/1/dpi",
+                "/settings/presets/6/color",
+                "/settings/far_distance_mode",
+                "/settings/led/mode",
+            ]
+        );
+        assert!(violations[2].message.contains("FarDistanceMode"));
+    }
+}
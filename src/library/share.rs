@@ -0,0 +1,311 @@
+//! Share codes: a [`ProfileSnapshot`] packed into one line of text.
+//!
+//! A code is `ATK-` followed by the base32 (RFC 4648, unpadded) encoding of:
+//!
+//! | Bytes | Content                                                             |
+//! |-------|---------------------------------------------------------------------|
+//! | 1     | Format, [`SHARE_FORMAT`]                                            |
+//! | 8 × 5 | Per preset: DPI in steps of 50 (big endian u16), red, green, blue   |
+//! | 1     | LED mode, brightness, breathing rate and enabled, packed            |
+//! | 3     | Stabilization time, close LED time, mouse performance flags         |
+//! | 3     | Sensor sleep time, RF Tx time, sensor performance flags             |
+//! | 1     | Far distance mode and silent height, packed                         |
+//! | 2     | CRC-16/CCITT-FALSE of everything before it (big endian)             |
+//!
+//! Times are stored in the device's units, one byte each. Enumerations are stored as
+//! their index in the lists of known values.
+
+use super::*;
+
+/// Version of the share code layout, bumped on incompatible changes.
+pub const SHARE_FORMAT: u8 = 1;
+
+const PREFIX: &str = "ATK-";
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const PAYLOAD_LEN: usize = 1 + 8 * 5 + 1 + 3 + 3 + 1;
+
+fn invalid(reason: impl std::fmt::Display) -> Error {
+    Error::ParseError(format!("Share code: {}", reason))
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xffff, |crc, &byte| {
+        (0..8).fold(crc ^ ((byte as u16) << 8), |crc, _| {
+            if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            }
+        })
+    })
+}
+
+fn base32_encode(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &byte in data {
+        buffer = (buffer << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            text.push(ALPHABET[(buffer >> bits) as usize & 0x1f] as char);
+        }
+    }
+    if bits > 0 {
+        text.push(ALPHABET[(buffer << (5 - bits)) as usize & 0x1f] as char);
+    }
+    text
+}
+
+/// Decodes base32 ignoring case, whitespace and dashes.
+fn base32_decode(text: &str) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for c in text.chars().filter(|c| !c.is_whitespace() && *c != '-') {
+        let value = ALPHABET
+            .iter()
+            .position(|&letter| letter as char == c.to_ascii_uppercase())
+            .ok_or_else(|| invalid(format!("Invalid character {:?}", c)))?;
+        buffer = (buffer << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            data.push((buffer >> bits) as u8);
+        }
+    }
+    Ok(data)
+}
+
+fn index_of<T: Copy + std::fmt::Debug + PartialEq>(
+    kind: &str,
+    values: &[T],
+    name: &str,
+) -> Result<u8, Error> {
+    let value = by_name(kind, values, name)?;
+    Ok(values.iter().position(|&known| known == value).unwrap() as u8)
+}
+
+fn name_at<T: std::fmt::Debug>(kind: &str, values: &[T], index: u8) -> Result<String, Error> {
+    values
+        .get(index as usize)
+        .map(|value| format!("{:?}", value))
+        .ok_or_else(|| invalid(format!("Unknown {} {}", kind, index)))
+}
+
+fn flags(bits: &[bool]) -> u8 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |flags, (bit, &set)| flags | ((set as u8) << bit))
+}
+
+fn flag(flags: u8, bit: u8) -> bool {
+    (flags >> bit) & 1 != 0
+}
+
+impl ProfileSnapshot {
+    /// Encodes the snapshot as a share code, failing if a value can not be represented.
+    pub fn to_share_code(&self) -> Result<String, Error> {
+        self.validate()?;
+
+        let mut data = Vec::with_capacity(PAYLOAD_LEN + 2);
+        data.push(SHARE_FORMAT);
+        for (dpi, color) in self.gears()? {
+            data.extend((dpi.dpi() / DPI_STEP).to_be_bytes());
+            data.extend([color.red(), color.green(), color.blue()]);
+        }
+
+        let led = &self.led;
+        data.push(
+            index_of("LED mode", &LED_MODES, &led.mode)?
+                | (index_of("LED brightness", &BRIGHTNESS_LEVELS, &led.brightness)? << 1)
+                | (index_of("LED breathing rate", &BREATHING_RATES, &led.breathing_rate)? << 3)
+                | ((led.enabled as u8) << 5),
+        );
+
+        let mouse = &self.mouse_performance;
+        data.push(
+            STABILIZATION_TIME_RANGE.encode(Duration::<Milliseconds>::from_millis(
+                mouse.stabilization_time_ms,
+            ))?,
+        );
+        data.push(
+            CLOSE_LED_TIME_RANGE.encode(Duration::<Decaseconds>::from_millis(
+                mouse.close_led_time_ms,
+            ))?,
+        );
+        data.push(flags(&[
+            mouse.motion_sync,
+            mouse.linear_correction,
+            mouse.ripple_control,
+        ]));
+
+        let sensor = &self.sensor_performance;
+        data.push(
+            SENSOR_SLEEP_TIME_RANGE.encode(Duration::<Decaseconds>::from_millis(
+                sensor.sensor_sleep_time_ms,
+            ))?,
+        );
+        data.push(
+            RF_TX_TIME_RANGE.encode(Duration::<Milliseconds>::from_millis(sensor.rf_tx_time_ms))?,
+        );
+        data.push(flags(&[
+            sensor.move_close_led,
+            sensor.sensor_sleep,
+            sensor.performance_mode,
+        ]));
+
+        data.push(
+            self.far_distance_mode as u8
+                | (index_of("Silent height", &SILENT_HEIGHTS, &self.silent_height)? << 1),
+        );
+
+        data.extend(crc16(&data).to_be_bytes());
+        Ok(format!("{}{}", PREFIX, base32_encode(&data)))
+    }
+
+    /// Decodes and validates a share code, see [`ProfileSnapshot::to_share_code`].
+    pub fn from_share_code(code: &str) -> Result<Self, Error> {
+        let code = code.trim();
+        let body = code
+            .get(..PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+            .map(|_| &code[PREFIX.len()..])
+            .ok_or_else(|| invalid(format!("Expected a code starting with {}", PREFIX)))?;
+
+        let data = base32_decode(body)?;
+        if data.len() != PAYLOAD_LEN + 2 {
+            return Err(invalid("Wrong length, the code may be truncated"));
+        }
+        let (payload, checksum) = data.split_at(PAYLOAD_LEN);
+        if crc16(payload).to_be_bytes() != checksum {
+            return Err(invalid("Checksum mismatch, the code may be mistyped"));
+        }
+        if payload[0] != SHARE_FORMAT {
+            return Err(invalid(format!(
+                "Unsupported format {}, expected {}",
+                payload[0], SHARE_FORMAT
+            )));
+        }
+
+        let presets = payload[1..41]
+            .chunks(5)
+            .map(|preset| PresetSnapshot {
+                dpi: u16::from_be_bytes([preset[0], preset[1]]).saturating_mul(DPI_STEP),
+                color: Color::new(preset[2], preset[3], preset[4]).to_string(),
+            })
+            .collect();
+
+        let [led, stabilization, close_led, mouse, sensor_sleep, rf_tx, sensor, misc] =
+            payload[41..]
+        else {
+            unreachable!("the payload length was checked above");
+        };
+
+        let snapshot = ProfileSnapshot {
+            presets,
+            led: LedSnapshot {
+                mode: name_at("LED mode", &LED_MODES, led & 1)?,
+                brightness: name_at("LED brightness", &BRIGHTNESS_LEVELS, (led >> 1) & 3)?,
+                breathing_rate: name_at("LED breathing rate", &BREATHING_RATES, (led >> 3) & 3)?,
+                enabled: flag(led, 5),
+            },
+            mouse_performance: MousePerformanceSnapshot {
+                stabilization_time_ms: Duration::<Milliseconds>::new(stabilization as u32)
+                    .as_millis(),
+                motion_sync: flag(mouse, 0),
+                close_led_time_ms: Duration::<Decaseconds>::new(close_led as u32).as_millis(),
+                linear_correction: flag(mouse, 1),
+                ripple_control: flag(mouse, 2),
+            },
+            sensor_performance: SensorPerformanceSnapshot {
+                move_close_led: flag(sensor, 0),
+                sensor_sleep: flag(sensor, 1),
+                sensor_sleep_time_ms: Duration::<Decaseconds>::new(sensor_sleep as u32).as_millis(),
+                performance_mode: flag(sensor, 2),
+                rf_tx_time_ms: Duration::<Milliseconds>::new(rf_tx as u32).as_millis(),
+            },
+            far_distance_mode: flag(misc, 0),
+            silent_height: name_at("Silent height", &SILENT_HEIGHTS, (misc >> 1) & 3)?,
+        };
+
+        snapshot.validate()?;
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::simulated::SimulatedMouse;
+    use proptest::prelude::*;
+
+    fn snapshot() -> ProfileSnapshot {
+        let manager = MouseManager::new(SimulatedMouse::default()).unwrap();
+        let snapshot = ProfileSnapshot::from(&*manager.profile());
+        snapshot
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut snapshot = snapshot();
+        snapshot.presets[7].dpi = MAX_DPI;
+        snapshot.led.mode = "Breathing".to_string();
+        snapshot.led.breathing_rate = "Fast".to_string();
+        snapshot.mouse_performance.close_led_time_ms = 2_550_000;
+        snapshot.sensor_performance.rf_tx_time_ms = 17;
+        snapshot.sensor_performance.performance_mode = true;
+        snapshot.silent_height = "TwoMm".to_string();
+
+        let code = snapshot.to_share_code().unwrap();
+        assert!(code.starts_with("ATK-"));
+        assert_eq!(
+            code.len(),
+            PREFIX.len() + ((PAYLOAD_LEN + 2) * 8).div_ceil(5)
+        );
+        assert!(code[PREFIX.len()..].bytes().all(|c| ALPHABET.contains(&c)));
+        assert_eq!(ProfileSnapshot::from_share_code(&code).unwrap(), snapshot);
+
+        let pasted = format!("  {}\n", code.to_lowercase());
+        assert_eq!(ProfileSnapshot::from_share_code(&pasted).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn damaged_codes_are_rejected() {
+        let code = snapshot().to_share_code().unwrap();
+
+        let mut typo = code.clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        let error = ProfileSnapshot::from_share_code(std::str::from_utf8(&typo).unwrap());
+        assert!(error.unwrap_err().to_string().contains("Checksum"));
+
+        let truncated = &code[..code.len() - 4];
+        assert!(ProfileSnapshot::from_share_code(truncated).is_err());
+        assert!(ProfileSnapshot::from_share_code(&code[PREFIX.len()..]).is_err());
+        assert!(ProfileSnapshot::from_share_code("ATK-1NVALID").is_err());
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        // DPI 0 passes the checksum but not validation.
+        let mut data = base32_decode(&snapshot().to_share_code().unwrap()[PREFIX.len()..]).unwrap();
+        data.truncate(PAYLOAD_LEN);
+        data[1..3].copy_from_slice(&[0, 0]);
+        data.extend(crc16(&data).to_be_bytes());
+        let code = format!("{}{}", PREFIX, base32_encode(&data));
+        assert!(ProfileSnapshot::from_share_code(&code)
+            .unwrap_err()
+            .to_string()
+            .contains("DPI"));
+
+        let mut snapshot = snapshot();
+        snapshot.sensor_performance.rf_tx_time_ms = 256;
+        assert!(snapshot.to_share_code().is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn base32_round_trips(data in proptest::collection::vec(any::<u8>(), 0..64)) {
+            prop_assert_eq!(base32_decode(&base32_encode(&data)).unwrap(), data);
+        }
+    }
+}
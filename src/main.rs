@@ -1,5 +1,8 @@
-mod commands;
-mod manager;
-mod types;
+fn main() -> std::process::ExitCode {
+    if let Err(error) = atk_hub::cli::run() {
+        eprintln!("Error: {}", error);
+        return std::process::ExitCode::FAILURE;
+    }
 
-fn main() {}
+    std::process::ExitCode::SUCCESS
+}
@@ -0,0 +1,462 @@
+use std::{
+    sync::{mpsc::Receiver, Arc},
+    time::Duration,
+};
+
+use super::{
+    events::Event,
+    transaction::{Block, Change, Transaction},
+    CachedSetting, MouseManager, Profile,
+};
+use crate::{
+    capabilities::Capabilities,
+    color::{Color, ColorCorrection},
+    commands::prelude::*,
+    transport::Transport,
+};
+use libatk_rs::prelude::*;
+
+/// Errors returned by [`AsyncMouseManager`], the same boxed errors [`MouseManager`] returns,
+/// so they can be downcast the same way.
+pub type AsyncError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Time between two polls while waiting for a dormant mouse to wake up.
+pub const ONLINE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An async front end to [`MouseManager`].
+///
+/// Device I/O still blocks, so every call runs on tokio's blocking pool. Each call is a
+/// future that can be dropped or wrapped in [`tokio::time::timeout`]: the command in flight
+/// finishes on the pool, but nothing after it is sent. Waiting for the mouse to come online
+/// polls with [`ONLINE_POLL_INTERVAL`] between queries instead of spinning, so it can be
+/// cancelled at any point.
+///
+/// The handle is cheap to clone, all clones share the same device.
+#[derive(Clone)]
+pub struct AsyncMouseManager {
+    inner: Arc<MouseManager>,
+}
+
+impl From<MouseManager> for AsyncMouseManager {
+    fn from(manager: MouseManager) -> Self {
+        AsyncMouseManager {
+            inner: Arc::new(manager),
+        }
+    }
+}
+
+impl AsyncMouseManager {
+    pub async fn new(transport: impl Transport + 'static) -> Result<Self, AsyncError> {
+        let instance = Self::new_without_profile(transport);
+
+        instance.load_profile().await?;
+
+        Ok(instance)
+    }
+
+    /// See [`MouseManager::new_without_profile`].
+    pub fn new_without_profile(transport: impl Transport + 'static) -> Self {
+        MouseManager::new_without_profile(transport).into()
+    }
+
+    /// The blocking manager behind this handle.
+    pub fn blocking(&self) -> &Arc<MouseManager> {
+        &self.inner
+    }
+
+    /// Runs `func` on the blocking pool once the mouse is online.
+    ///
+    /// The mouse is waited for here, the blocking manager skips its own wait.
+    async fn run<U: Send + 'static>(
+        &self,
+        func: impl FnOnce(&MouseManager) -> Result<U, AsyncError> + Send + 'static,
+    ) -> Result<U, AsyncError> {
+        self.wait_for_mouse_online().await?;
+        self.spawn(|manager| manager.assuming_online(func)).await
+    }
+
+    async fn spawn<U: Send + 'static>(
+        &self,
+        func: impl FnOnce(&MouseManager) -> Result<U, AsyncError> + Send + 'static,
+    ) -> Result<U, AsyncError> {
+        let inner = Arc::clone(&self.inner);
+
+        tokio::task::spawn_blocking(move || func(&inner)).await?
+    }
+
+    pub async fn execute<T: CommandDescriptor + Send + 'static>(
+        &self,
+        cmd: Command<T>,
+    ) -> Result<Command<T>, AsyncError> {
+        self.spawn(move |manager| manager.execute(cmd)).await
+    }
+
+    async fn mouse_status(&self) -> Result<MouseStatus, AsyncError> {
+        self.spawn(|manager| {
+            Ok(manager
+                .execute(Command::<GetWirelessMouseOnline>::query())?
+                .mouse_status())
+        })
+        .await
+    }
+
+    /// Resolves once the mouse reports itself online, emitting
+    /// [`Event::Dormant`] if it is asleep.
+    ///
+    /// Sends the same queries as [`MouseManager`], so traces recorded with either replay
+    /// with both.
+    pub async fn wait_for_mouse_online(&self) -> Result<(), AsyncError> {
+        if self.mouse_status().await? == MouseStatus::Dormant {
+            self.inner.report_dormant();
+        }
+        while self.mouse_status().await? == MouseStatus::Dormant {
+            tokio::time::sleep(ONLINE_POLL_INTERVAL).await;
+        }
+
+        Ok(())
+    }
+
+    pub async fn load_profile(&self) -> Result<(), AsyncError> {
+        self.run(|manager| manager.load_profile()).await
+    }
+
+    /// A copy of the cached profile, since a lock guard can not be held across `.await`.
+    pub fn profile(&self) -> Profile {
+        self.inner.profile().clone()
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.inner.capabilities()
+    }
+
+    pub fn set_capabilities(&self, capabilities: Capabilities) {
+        self.inner.set_capabilities(capabilities)
+    }
+
+    /// See [`MouseManager::trace_failure`].
+    pub fn trace_failure(&self) -> Option<(String, u64)> {
+        self.inner.trace_failure()
+    }
+
+    /// See [`MouseManager::subscribe`].
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.inner.subscribe()
+    }
+
+    /// See [`MouseManager::poll_events`]. Does not wait for a dormant mouse either.
+    pub async fn poll_events(&self) -> Result<(), AsyncError> {
+        self.spawn(|manager| manager.poll_events()).await
+    }
+
+    pub fn color_correction(&self) -> ColorCorrection {
+        self.inner.color_correction()
+    }
+
+    pub fn set_color_correction(&self, correction: ColorCorrection) {
+        self.inner.set_color_correction(correction)
+    }
+
+    pub fn verify_retries(&self) -> Option<u32> {
+        self.inner.verify_retries()
+    }
+
+    pub fn set_verify_retries(&self, retries: Option<u32>) {
+        self.inner.set_verify_retries(retries)
+    }
+
+    /// The cached value of a setting.
+    pub fn setting<T: CachedSetting>(&self, key: T::Key) -> T {
+        self.inner.setting::<T>(key)
+    }
+
+    /// See [`MouseManager::load_setting`].
+    pub async fn load_setting<T>(&self, key: T::Key) -> Result<T, AsyncError>
+    where
+        T: CachedSetting + Send + 'static,
+        T::Key: Send,
+    {
+        self.run(move |manager| manager.load_setting::<T>(key))
+            .await
+    }
+
+    /// See [`MouseManager::write_setting`].
+    pub async fn write_setting<T: CachedSetting + Send + 'static>(
+        &self,
+        setting: T,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.write_setting(&setting))
+            .await
+    }
+
+    /// See [`MouseManager::verify_setting`].
+    pub async fn verify_setting<T: EepromSetting + Send + 'static>(
+        &self,
+        expected: T,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.verify_setting(&expected))
+            .await
+    }
+
+    /// Writes every change in one transaction, see [`MouseManager::transaction`].
+    ///
+    /// A failed commit returns the [`TransactionError`](super::transaction::TransactionError),
+    /// boxed.
+    pub async fn commit(
+        &self,
+        changes: impl IntoIterator<Item = Change>,
+    ) -> Result<Vec<Block>, AsyncError> {
+        let changes: Vec<Change> = changes.into_iter().collect();
+        self.run(move |manager| {
+            let transaction = changes
+                .into_iter()
+                .fold(manager.transaction(), Transaction::stage);
+            Ok(transaction.commit()?)
+        })
+        .await
+    }
+
+    pub async fn read_raw_eeprom(
+        &self,
+        address: EEPROMAddress,
+        len: usize,
+    ) -> Result<Vec<u8>, AsyncError> {
+        self.run(move |manager| manager.read_raw_eeprom(address, len))
+            .await
+    }
+
+    pub async fn write_raw_eeprom(
+        &self,
+        address: EEPROMAddress,
+        bytes: Vec<u8>,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.write_raw_eeprom(address, &bytes))
+            .await
+    }
+
+    pub async fn battery_level(&self) -> Result<GetBatteryStatus, AsyncError> {
+        self.run(|manager| manager.battery_level()).await
+    }
+
+    pub async fn connection_type(&self) -> Result<ConnectionType, AsyncError> {
+        self.run(|manager| manager.connection_type()).await
+    }
+
+    /// Puts the dongle in pairing mode. Like [`MouseManager::start_pairing`] this does not
+    /// wait for the mouse.
+    pub async fn start_pairing(&self) -> Result<(), AsyncError> {
+        self.spawn(|manager| manager.start_pairing()).await
+    }
+
+    pub async fn pairing_status(&self) -> Result<PairingStatus, AsyncError> {
+        self.spawn(|manager| manager.pairing_status()).await
+    }
+
+    pub async fn exit_pairing(&self) -> Result<(), AsyncError> {
+        self.spawn(|manager| manager.exit_pairing()).await
+    }
+
+    pub async fn set_mouse_performance_settings(
+        &self,
+        patch: MousePerfPatch,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_mouse_performance_settings(patch))
+            .await
+    }
+
+    pub async fn set_dpi_led_settings(&self, patch: LedPatch) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_dpi_led_settings(patch))
+            .await
+    }
+
+    pub async fn set_far_distance_mode(&self, mode: bool) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_far_distance_mode(mode))
+            .await
+    }
+
+    pub async fn set_silent_height(&self, height: SilentHeightMode) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_silent_height(height))
+            .await
+    }
+
+    pub async fn set_polling_rate(&self, rate: PollingRate) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_polling_rate(rate))
+            .await
+    }
+
+    pub async fn set_active_preset(&self, preset: Preset) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_active_preset(preset))
+            .await
+    }
+
+    pub async fn set_sensor_performance_settings(
+        &self,
+        patch: SensorPerfPatch,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_sensor_performance_settings(patch))
+            .await
+    }
+
+    pub async fn set_dpi_profile_color(
+        &self,
+        preset: Preset,
+        color: Color,
+    ) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_dpi_profile_color(preset, color))
+            .await
+    }
+
+    pub async fn set_dpi_profile_dpi(&self, preset: Preset, dpi: Dpi) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.set_dpi_profile_dpi(preset, dpi))
+            .await
+    }
+
+    pub async fn new_dpi_profile(&self, dpi: Dpi, color: Color) -> Result<(), AsyncError> {
+        self.run(move |manager| manager.new_dpi_profile(dpi, color))
+            .await
+    }
+
+    pub async fn set_distinct_dpi_profile_colors(&self) -> Result<(), AsyncError> {
+        self.run(|manager| manager.set_distinct_dpi_profile_colors())
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        manager::transaction::TransactionError,
+        trace::replay::{Replay, ReplayError},
+        transport::simulated::SimulatedMouse,
+    };
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/traces")
+            .join(name)
+    }
+
+    /// A mouse that never wakes up.
+    #[derive(Default)]
+    struct Dormant {
+        polls: AtomicUsize,
+    }
+
+    impl Transport for Arc<Dormant> {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+
+            let mut response = request.to_vec();
+            response[4] = 0x1;
+            response[5] = MouseStatus::Dormant as u8;
+            Ok(response)
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_setters() {
+        let replay = Arc::new(Replay::open(fixture("synthetic-setters.jsonl")).unwrap());
+        let manager = AsyncMouseManager::new(Arc::clone(&replay)).await.unwrap();
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset3, Dpi::new(800))
+            .await
+            .unwrap();
+        manager
+            .set_dpi_profile_color(Preset::Preset3, Color::new(0xff, 0, 0))
+            .await
+            .unwrap();
+        manager.set_far_distance_mode(true).await.unwrap();
+
+        replay.finish().unwrap();
+
+        let profile = manager.profile();
+        assert_eq!(
+            profile.dpi_profile(Pair::Pair2).0.to_string(),
+            "DPI: 800 | Color: #ff0000"
+        );
+        assert!(profile.far_distance_mode().far_distance_mode());
+    }
+
+    #[tokio::test]
+    async fn errors_keep_their_type() {
+        let replay = Replay::open(fixture("synthetic-setters.jsonl")).unwrap();
+        let manager = AsyncMouseManager::new(replay).await.unwrap();
+
+        let error = manager
+            .set_dpi_profile_dpi(Preset::Preset3, Dpi::new(1600))
+            .await
+            .unwrap_err();
+        assert!(error.downcast_ref::<ReplayError>().is_some(), "{}", error);
+
+        let manager = AsyncMouseManager::new(SimulatedMouse::default())
+            .await
+            .unwrap();
+        manager.set_capabilities(Capabilities {
+            presets: 2,
+            ..manager.capabilities()
+        });
+        let error = manager
+            .commit([Change::Dpi(Preset::Preset3, Dpi::new(800))])
+            .await
+            .unwrap_err();
+        let error = error
+            .downcast_ref::<TransactionError>()
+            .unwrap_or_else(|| panic!("{}", error));
+        assert_eq!(error.failed(), Block::Dpi(Pair::Pair2));
+    }
+
+    #[tokio::test]
+    async fn covers_transactions_settings_and_pairing() {
+        let mouse = Arc::new(SimulatedMouse::default());
+        let manager = AsyncMouseManager::new(Arc::clone(&mouse)).await.unwrap();
+
+        let blocks = manager
+            .commit([
+                Change::Dpi(Preset::Preset1, Dpi::new(1600)),
+                Change::SilentHeight(SilentHeightMode::TwoMm),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(
+            manager.profile().dpi_profile(Pair::Pair1).0.dpi(),
+            Dpi::new(1600)
+        );
+
+        manager.set_active_preset(Preset::Preset2).await.unwrap();
+        let info: MouseInfo = manager.load_setting(()).await.unwrap();
+        assert_eq!(info.active_profile(), Preset::Preset2 as u8);
+        manager.verify_setting(info.clone()).await.unwrap();
+        manager.write_setting(info).await.unwrap();
+
+        manager.start_pairing().await.unwrap();
+        assert_eq!(manager.pairing_status().await.unwrap().status, 0x1);
+        manager.exit_pairing().await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiting_for_online_can_time_out() {
+        let dormant = Arc::new(Dormant::default());
+        let manager = AsyncMouseManager::new_without_profile(Arc::clone(&dormant));
+        let events = manager.blocking().subscribe();
+
+        let result = tokio::time::timeout(
+            ONLINE_POLL_INTERVAL * 5,
+            manager.set_far_distance_mode(true),
+        )
+        .await;
+
+        assert!(result.is_err());
+        let polls = dormant.polls.load(Ordering::SeqCst);
+        assert!((2..=8).contains(&polls), "polled {} times", polls);
+        assert_eq!(events.try_iter().collect::<Vec<_>>(), [Event::Dormant]);
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc, PoisonError,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use super::{transaction::Block, MouseManager};
+use crate::commands::prelude::*;
+use libatk_rs::prelude::*;
+
+/// A change of the device state, or of the cached profile, seen by a [`MouseManager`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The mouse answered as online, either for the first time or after being dormant.
+    Online,
+    /// The mouse went to sleep, or is not paired with the dongle.
+    Dormant,
+    Battery {
+        level: u8,
+        charge: u8,
+    },
+    Connection(ConnectionType),
+    /// The DPI preset the mouse is using, e.g. after pressing the DPI button.
+    ActivePreset(Preset),
+    /// The whole profile was read from the device.
+    ProfileLoaded,
+    /// This process wrote `Block` and the cached profile now holds the device's response.
+    ProfileChanged(Block),
+    /// Polling the device failed, the watcher keeps trying.
+    PollFailed(String),
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Event::Online => write!(f, "Mouse is online"),
+            Event::Dormant => write!(f, "Mouse is dormant"),
+            Event::Battery { level, charge } => {
+                write!(f, "Battery Level: {}% | Charge: {}", level, charge)
+            }
+            Event::Connection(connection) => write!(f, "Connection Type: {:?}", connection),
+            Event::ActivePreset(preset) => write!(f, "Active Preset: {:?}", preset),
+            Event::ProfileLoaded => write!(f, "Profile loaded"),
+            Event::ProfileChanged(block) => write!(f, "{} changed", block),
+            Event::PollFailed(error) => write!(f, "Polling failed: {}", error),
+        }
+    }
+}
+
+/// The last device state reported to subscribers, so only changes are emitted.
+#[derive(Debug, Default)]
+pub(super) struct DeviceState {
+    online: Option<bool>,
+    battery: Option<(u8, u8)>,
+    connection: Option<ConnectionType>,
+    active_preset: Option<Preset>,
+}
+
+/// Polls a [`MouseManager`] on a background thread until dropped.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl MouseManager {
+    /// Returns a receiver for every [`Event`] emitted from now on.
+    ///
+    /// Events about the device itself are only emitted while something calls
+    /// [`MouseManager::poll_events`], see [`MouseManager::watch`]. Dropping the receiver
+    /// unsubscribes.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        receiver
+    }
+
+    pub(super) fn emit(&self, event: Event) {
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+
+    /// Emits [`Event::Dormant`] for a mouse found asleep while waiting for it, unless that
+    /// was already reported.
+    pub(super) fn report_dormant(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.online.replace(false) != Some(false) {
+            self.emit(Event::Dormant);
+        }
+    }
+
+    /// Queries the online status, battery, connection type and active preset once and emits
+    /// an event for everything that changed since the last poll.
+    ///
+    /// Unlike the setters this does not wait for a dormant mouse, it reports
+    /// [`Event::Dormant`] and returns.
+    pub fn poll_events(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let online = self
+            .execute(Command::<GetWirelessMouseOnline>::query())?
+            .mouse_status()
+            != MouseStatus::Dormant;
+
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.online.replace(online) != Some(online) {
+            self.emit(if online {
+                Event::Online
+            } else {
+                Event::Dormant
+            });
+        }
+        if !online {
+            return Ok(());
+        }
+
+        let battery = self.execute(Command::<GetBatteryStatus>::query())?.config();
+        let battery = (battery.level(), battery.charge());
+        if state.battery.replace(battery) != Some(battery) {
+            self.emit(Event::Battery {
+                level: battery.0,
+                charge: battery.1,
+            });
+        }
+
+        let connection = self
+            .execute(Command::<DownloadData>::query())?
+            .config()
+            .connection_type();
+        if state.connection.replace(connection) != Some(connection) {
+            self.emit(Event::Connection(connection));
+        }
+
+        let info = self.execute(Command::<MouseInfo>::query())?.config();
+        if let Ok(preset) = Preset::try_from(info.active_profile()) {
+            if state.active_preset.replace(preset) != Some(preset) {
+                self.emit(Event::ActivePreset(preset));
+            }
+        }
+        // A transaction holds the profile while it waits for the mouse, which reports
+        // dormancy through `state`: taking the profile while holding `state` deadlocks.
+        drop(state);
+        self.profile_mut().mouse_info = info;
+
+        Ok(())
+    }
+
+    /// Calls [`MouseManager::poll_events`] every `interval` on a background thread until the
+    /// returned [`Watcher`] is dropped. Failed polls are reported as [`Event::PollFailed`].
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> Watcher {
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let manager = Arc::clone(self);
+        let stopped = Arc::clone(&stop);
+        let handle = std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                if let Err(e) = manager.poll_events() {
+                    manager.emit(Event::PollFailed(e.to_string()));
+                }
+                std::thread::park_timeout(interval);
+            }
+        });
+
+        Watcher {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::Transport;
+    use std::sync::Mutex;
+
+    /// A mouse whose state is changed by the test.
+    struct Simulated {
+        online: bool,
+        /// Falls asleep and wakes up again on every online query.
+        flapping: bool,
+        battery: u8,
+        connection: u8,
+        active_preset: u8,
+    }
+
+    impl Transport for Arc<Mutex<Simulated>> {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            let mut mouse = self.lock().unwrap();
+            let data: Vec<u8> = match CommandId::try_from(request[0])? {
+                CommandId::GetWirelessMouseOnline => {
+                    let online = mouse.online;
+                    mouse.online ^= mouse.flapping;
+                    vec![online as u8]
+                }
+                CommandId::GetBatteryLevel => vec![mouse.battery, 0, 40],
+                CommandId::DownLoadData => vec![0, 0, 0, 0, 0, 0, mouse.connection, 0],
+                CommandId::GetEEPROM => {
+                    let pairs = [0x01, 4, mouse.active_preset];
+                    pairs.iter().flat_map(|&v| [v, 0x55 - v]).collect()
+                }
+                CommandId::SetEEPROM => request[5..5 + request[4] as usize].to_vec(),
+                id => return Err(format!("Unexpected {:?}", id).into()),
+            };
+
+            let mut response = request.to_vec();
+            response[4] = data.len() as u8;
+            response[5..5 + data.len()].copy_from_slice(&data);
+            Ok(response)
+        }
+    }
+
+    fn simulated() -> (Arc<Mutex<Simulated>>, MouseManager) {
+        let mouse = Arc::new(Mutex::new(Simulated {
+            online: true,
+            flapping: false,
+            battery: 80,
+            connection: 0x0,
+            active_preset: 0,
+        }));
+        let manager = MouseManager::new_without_profile(Arc::clone(&mouse));
+        (mouse, manager)
+    }
+
+    #[test]
+    fn polling_emits_only_changes() {
+        let (mouse, manager) = simulated();
+        let events = manager.subscribe();
+
+        manager.poll_events().unwrap();
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [
+                Event::Online,
+                Event::Battery {
+                    level: 80,
+                    charge: 0
+                },
+                Event::Connection(ConnectionType::Dongle1K),
+                Event::ActivePreset(Preset::Preset1),
+            ]
+        );
+
+        manager.poll_events().unwrap();
+        assert_eq!(events.try_iter().count(), 0);
+
+        {
+            let mut mouse = mouse.lock().unwrap();
+            mouse.battery = 79;
+            mouse.connection = 0x3;
+            mouse.active_preset = 2;
+        }
+        manager.poll_events().unwrap();
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [
+                Event::Battery {
+                    level: 79,
+                    charge: 0
+                },
+                Event::Connection(ConnectionType::Wired8K),
+                Event::ActivePreset(Preset::Preset3),
+            ]
+        );
+        assert_eq!(manager.profile().mouse_info().active_profile(), 2);
+
+        mouse.lock().unwrap().online = false;
+        manager.poll_events().unwrap();
+        assert_eq!(events.try_iter().collect::<Vec<_>>(), [Event::Dormant]);
+    }
+
+    #[test]
+    fn waiting_for_a_dormant_mouse_emits_dormant_once() {
+        let (mouse, manager) = simulated();
+        let events = manager.subscribe();
+        mouse.lock().unwrap().online = false;
+
+        let waker = std::thread::spawn({
+            let mouse = Arc::clone(&mouse);
+            move || {
+                std::thread::sleep(Duration::from_millis(20));
+                mouse.lock().unwrap().online = true;
+            }
+        });
+        manager
+            .set_dpi_profile_dpi(Preset::Preset4, Dpi::new(800))
+            .unwrap();
+        waker.join().unwrap();
+
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [
+                Event::Dormant,
+                Event::ProfileChanged(Block::Dpi(Pair::Pair2))
+            ]
+        );
+
+        manager.poll_events().unwrap();
+        assert_eq!(events.try_iter().next(), Some(Event::Online));
+    }
+
+    #[test]
+    fn watching_does_not_deadlock_a_transaction_waiting_for_the_mouse() {
+        let (mouse, manager) = simulated();
+        mouse.lock().unwrap().flapping = true;
+        let manager = Arc::new(manager);
+        let watcher = manager.watch(Duration::ZERO);
+
+        let (done, finished) = mpsc::channel();
+        std::thread::spawn({
+            let manager = Arc::clone(&manager);
+            move || {
+                for index in 0..2000 {
+                    manager
+                        .transaction()
+                        .dpi(Preset::Preset4, Dpi::new(800 + index % 2 * 800))
+                        .commit()
+                        .unwrap();
+                }
+                done.send(()).unwrap();
+            }
+        });
+
+        if finished.recv_timeout(Duration::from_secs(10)).is_err() {
+            // Dropping the watcher would join its deadlocked thread.
+            std::mem::forget(watcher);
+            panic!("The transaction and the watcher deadlocked");
+        }
+    }
+
+    #[test]
+    fn setters_emit_profile_changes() {
+        let (_, manager) = simulated();
+        let events = manager.subscribe();
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset4, Dpi::new(800))
+            .unwrap();
+
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            [Event::ProfileChanged(Block::Dpi(Pair::Pair2))]
+        );
+    }
+
+    #[test]
+    fn dropped_receivers_unsubscribe() {
+        let (_, manager) = simulated();
+        drop(manager.subscribe());
+        let events = manager.subscribe();
+
+        manager.poll_events().unwrap();
+
+        assert_eq!(manager.subscribers.lock().unwrap().len(), 1);
+        assert!(events.try_iter().count() > 0);
+    }
+
+    #[test]
+    fn watcher_polls_until_dropped() {
+        let (_, manager) = simulated();
+        let manager = Arc::new(manager);
+        let events = manager.subscribe();
+
+        let watcher = manager.watch(Duration::from_millis(1));
+        assert_eq!(
+            events.recv_timeout(Duration::from_secs(5)).unwrap(),
+            Event::Online
+        );
+        drop(watcher);
+
+        events.try_iter().for_each(drop);
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(events.try_iter().count(), 0);
+    }
+}
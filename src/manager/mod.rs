@@ -0,0 +1,1035 @@
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod events;
+pub mod transaction;
+pub mod verify;
+
+use std::{
+    cell::Cell,
+    sync::{
+        mpsc::Sender, Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+use crate::{
+    capabilities::Capabilities,
+    color::{Color, ColorCorrection},
+    commands::prelude::*,
+    eeprom::{RawEeprom, RawEepromExt},
+    trace::Tracer,
+    transport::{parse_command, Transport},
+};
+use libatk_rs::prelude::*;
+
+use events::{DeviceState, Event};
+use transaction::{Block, Change, Transaction};
+use verify::VerificationError;
+
+thread_local! {
+    /// Set while running a call whose caller already waited for the mouse to come online.
+    static ASSUME_ONLINE: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone)]
+pub struct Profile {
+    dpi: [DpiPairSetting; 4],
+    dpi_color: [ColorPairSetting; 4],
+    dpi_led: DpiLedSettings,
+    far_distance: FarDistanceMode,
+    mouse_info: MouseInfo,
+    mouse_perf: MousePerfSettings,
+    sensor_perf: SensorPerfSettings,
+    silent_mode: SilentHeight,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        const PAIRS: [Pair; 4] = [Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4];
+
+        Profile {
+            dpi: PAIRS.map(DpiPairSetting::from),
+            dpi_color: PAIRS.map(ColorPairSetting::from),
+            dpi_led: DpiLedSettings::default(),
+            far_distance: FarDistanceMode::default(),
+            mouse_info: MouseInfo::default(),
+            mouse_perf: MousePerfSettings::default(),
+            sensor_perf: SensorPerfSettings::default(),
+            silent_mode: SilentHeight::default(),
+        }
+    }
+}
+
+impl Profile {
+    /// The cached value of a setting, see [`MouseManager::setting`].
+    pub fn setting<T: CachedSetting>(&self, key: T::Key) -> &T {
+        T::cached(self, key)
+    }
+
+    pub fn dpi_led_settings(&self) -> &DpiLedSettings {
+        &self.dpi_led
+    }
+
+    pub fn far_distance_mode(&self) -> &FarDistanceMode {
+        &self.far_distance
+    }
+
+    pub fn mouse_info(&self) -> &MouseInfo {
+        &self.mouse_info
+    }
+
+    pub fn mouse_performance_settings(&self) -> &MousePerfSettings {
+        &self.mouse_perf
+    }
+
+    pub fn sensor_performance_settings(&self) -> &SensorPerfSettings {
+        &self.sensor_perf
+    }
+
+    pub fn silent_height(&self) -> &SilentHeight {
+        &self.silent_mode
+    }
+
+    pub fn dpi_pair_setting(&self, pair: Pair) -> &DpiPairSetting {
+        &self.dpi[pair as usize]
+    }
+
+    pub fn dpi_profile(&self, pair: Pair) -> (Gear, Gear) {
+        let dpi = &self.dpi[pair as usize];
+        let color = &self.dpi_color[pair as usize];
+        (
+            Gear::new(dpi.dpi(Slot::First), color.color(Slot::First)),
+            Gear::new(dpi.dpi(Slot::Second), color.color(Slot::Second)),
+        )
+    }
+}
+
+/// An [`EepromSetting`] kept in the [`Profile`], which the manager can load, write and verify
+/// without knowing its layout.
+pub trait CachedSetting: EepromSetting {
+    fn block(key: Self::Key) -> Block;
+
+    fn cached(profile: &Profile, key: Self::Key) -> &Self;
+
+    fn cached_mut(profile: &mut Profile, key: Self::Key) -> &mut Self;
+}
+
+impl CachedSetting for MousePerfSettings {
+    fn block(_: ()) -> Block {
+        Block::MousePerformance
+    }
+
+    fn cached(profile: &Profile, _: ()) -> &Self {
+        &profile.mouse_perf
+    }
+
+    fn cached_mut(profile: &mut Profile, _: ()) -> &mut Self {
+        &mut profile.mouse_perf
+    }
+}
+
+impl CachedSetting for SensorPerfSettings {
+    fn block(_: ()) -> Block {
+        Block::SensorPerformance
+    }
+
+    fn cached(profile: &Profile, _: ()) -> &Self {
+        &profile.sensor_perf
+    }
+
+    fn cached_mut(profile: &mut Profile, _: ()) -> &mut Self {
+        &mut profile.sensor_perf
+    }
+}
+
+impl CachedSetting for DpiLedSettings {
+    fn block(_: ()) -> Block {
+        Block::DpiLed
+    }
+
+    fn cached(profile: &Profile, _: ()) -> &Self {
+        &profile.dpi_led
+    }
+
+    fn cached_mut(profile: &mut Profile, _: ()) -> &mut Self {
+        &mut profile.dpi_led
+    }
+}
+
+impl CachedSetting for SilentHeight {
+    fn block(_: ()) -> Block {
+        Block::SilentHeight
+    }
+
+    fn cached(profile: &Profile, _: ()) -> &Self {
+        &profile.silent_mode
+    }
+
+    fn cached_mut(profile: &mut Profile, _: ()) -> &mut Self {
+        &mut profile.silent_mode
+    }
+}
+
+impl CachedSetting for MouseInfo {
+    fn block(_: ()) -> Block {
+        Block::MouseInfo
+    }
+
+    fn cached(profile: &Profile, _: ()) -> &Self {
+        &profile.mouse_info
+    }
+
+    fn cached_mut(profile: &mut Profile, _: ()) -> &mut Self {
+        &mut profile.mouse_info
+    }
+}
+
+impl CachedSetting for DpiPairSetting {
+    fn block(pair: Pair) -> Block {
+        Block::Dpi(pair)
+    }
+
+    fn cached(profile: &Profile, pair: Pair) -> &Self {
+        &profile.dpi[pair as usize]
+    }
+
+    fn cached_mut(profile: &mut Profile, pair: Pair) -> &mut Self {
+        &mut profile.dpi[pair as usize]
+    }
+}
+
+impl CachedSetting for ColorPairSetting {
+    fn block(pair: Pair) -> Block {
+        Block::Color(pair)
+    }
+
+    fn cached(profile: &Profile, pair: Pair) -> &Self {
+        &profile.dpi_color[pair as usize]
+    }
+
+    fn cached_mut(profile: &mut Profile, pair: Pair) -> &mut Self {
+        &mut profile.dpi_color[pair as usize]
+    }
+}
+
+/// Talks to a single mouse and caches its configuration.
+///
+/// The manager is `Send` and `Sync`; share it between threads with an
+/// [`Arc`](std::sync::Arc). Every request/response exchange holds the transport lock, so
+/// concurrent callers never interleave partial exchanges. Setters and transactions also hold
+/// the profile lock from reading the cache until the device's response is cached, so
+/// concurrent changes to the same block are not lost.
+pub struct MouseManager {
+    profile: RwLock<Profile>,
+    transport: Mutex<Box<dyn Transport>>,
+    color_correction: Mutex<ColorCorrection>,
+    verify_retries: Mutex<Option<u32>>,
+    capabilities: RwLock<Capabilities>,
+    tracer: Option<Tracer>,
+    subscribers: Mutex<Vec<Sender<Event>>>,
+    state: Mutex<DeviceState>,
+}
+
+impl MouseManager {
+    pub fn new(
+        transport: impl Transport + 'static,
+    ) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let instance = Self::new_without_profile(transport);
+
+        instance.load_profile()?;
+
+        Ok(instance)
+    }
+
+    /// Creates a manager without reading the configuration from the device.
+    ///
+    /// The cached profile holds default values until [`MouseManager::load_profile`] is called.
+    /// This is meant for raw EEPROM access, where the stored configuration may not parse.
+    pub fn new_without_profile(transport: impl Transport + 'static) -> Self {
+        Self {
+            profile: RwLock::new(Profile::default()),
+            transport: Mutex::new(Box::new(transport)),
+            color_correction: Mutex::new(ColorCorrection::default()),
+            verify_retries: Mutex::new(None),
+            capabilities: RwLock::new(Capabilities::default()),
+            tracer: None,
+            subscribers: Mutex::new(Vec::new()),
+            state: Mutex::new(DeviceState::default()),
+        }
+    }
+
+    /// Restricts the setters to what `capabilities` allows, see
+    /// [`Registry::detect`](crate::capabilities::Registry::detect). Until then every setting
+    /// is assumed to exist.
+    pub fn with_capabilities(self, capabilities: Capabilities) -> Self {
+        self.set_capabilities(capabilities);
+        self
+    }
+
+    /// Records every command sent through this manager, along with the device's response.
+    pub fn with_tracer(mut self, tracer: Tracer) -> Self {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    /// The first trace record that could not be written and the number of records dropped,
+    /// `None` while tracing works or is off.
+    pub fn trace_failure(&self) -> Option<(String, u64)> {
+        self.tracer.as_ref()?.failure()
+    }
+
+    pub fn execute<T: CommandDescriptor>(
+        &self,
+        cmd: Command<T>,
+    ) -> Result<Command<T>, Box<dyn std::error::Error + Send + Sync>> {
+        self.exchange(&**self.lock_transport(), cmd)
+    }
+
+    fn lock_transport(&self) -> MutexGuard<'_, Box<dyn Transport>> {
+        self.transport
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sends `cmd` over an already locked `transport`.
+    fn exchange<T: CommandDescriptor>(
+        &self,
+        transport: &dyn Transport,
+        cmd: Command<T>,
+    ) -> Result<Command<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let request = cmd.as_bytes();
+        let response = transport.exchange(&request);
+
+        if let Some(tracer) = &self.tracer {
+            let response = response
+                .as_deref()
+                .map_err(|e| e.as_ref() as &dyn std::error::Error);
+            tracer.record_or_keep(&request, response);
+        }
+
+        Ok(parse_command(&response?)?)
+    }
+
+    pub fn load_profile(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wait_for_mouse_online()?;
+
+        let mut profile = self.profile_mut();
+
+        /* TODO: Keys */
+
+        profile.mouse_perf = self.fetch(())?;
+
+        profile.sensor_perf = self.fetch(())?;
+
+        /* TODO: GetCurrConf */
+
+        profile.far_distance = self.execute(Command::<FarDistanceMode>::query())?.config();
+
+        profile.mouse_info = self.fetch(())?;
+
+        let pairs = [Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4];
+        for pair in pairs {
+            profile.dpi[pair as usize] = self.fetch(pair)?;
+        }
+        for pair in pairs {
+            profile.dpi_color[pair as usize] = self.fetch(pair)?;
+        }
+
+        profile.silent_mode = self.fetch(())?;
+
+        profile.dpi_led = self.fetch(())?;
+
+        self.emit(Event::ProfileLoaded);
+
+        Ok(())
+    }
+
+    /// Runs `func` once the mouse is online.
+    pub(crate) fn wrapper<U>(
+        &self,
+        func: impl Fn(&Self) -> Result<U, Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Result<U, Box<dyn std::error::Error + Send + Sync>> {
+        self.wait_for_mouse_online()?;
+        func(self)
+    }
+
+    /// Blocks until the mouse is online, emitting [`Event::Dormant`] if it is asleep so that
+    /// callers can ask the user to wake it up.
+    fn wait_for_mouse_online(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if ASSUME_ONLINE.get() {
+            return Ok(());
+        }
+
+        let cmd = Command::<GetWirelessMouseOnline>::query();
+        let status = self.execute(cmd.clone())?.mouse_status();
+        if status == MouseStatus::Dormant {
+            self.report_dormant();
+        }
+        while self.execute(cmd.clone())?.mouse_status() == MouseStatus::Dormant {}
+
+        Ok(())
+    }
+
+    /// Like [`wait_for_mouse_online`](Self::wait_for_mouse_online), but gives up once
+    /// `timeout` has passed.
+    fn wait_for_mouse_online_within(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if ASSUME_ONLINE.get() {
+            return Ok(());
+        }
+
+        let deadline = std::time::Instant::now() + timeout;
+        let cmd = Command::<GetWirelessMouseOnline>::query();
+        while self.execute(cmd.clone())?.mouse_status() == MouseStatus::Dormant {
+            if std::time::Instant::now() >= deadline {
+                return Err(format!("Mouse did not come online within {:?}", timeout).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `func` on this thread without waiting for the mouse to come online first.
+    #[cfg(feature = "async")]
+    fn assuming_online<U>(&self, func: impl FnOnce(&Self) -> U) -> U {
+        struct Reset;
+
+        impl Drop for Reset {
+            fn drop(&mut self) {
+                ASSUME_ONLINE.set(false);
+            }
+        }
+
+        ASSUME_ONLINE.set(true);
+        let _reset = Reset;
+        func(self)
+    }
+
+    pub fn profile(&self) -> RwLockReadGuard<'_, Profile> {
+        self.profile.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the cached profile for a read-modify-write. Do not call [`MouseManager::profile`]
+    /// while holding it.
+    fn profile_mut(&self) -> RwLockWriteGuard<'_, Profile> {
+        self.profile.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn set_capabilities(&self, capabilities: Capabilities) {
+        *self
+            .capabilities
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = capabilities;
+    }
+
+    pub fn color_correction(&self) -> ColorCorrection {
+        *self
+            .color_correction
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Sets the correction applied to every colour before it is written to the device.
+    ///
+    /// Colours already stored on the device are left untouched.
+    pub fn set_color_correction(&self, correction: ColorCorrection) {
+        *self
+            .color_correction
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = correction;
+    }
+
+    pub fn verify_retries(&self) -> Option<u32> {
+        *self
+            .verify_retries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Enables read-back verification of every EEPROM write.
+    ///
+    /// With `Some(retries)` each write is followed by a `GetEEPROM` of the same region and
+    /// repeated up to `retries` more times until the bytes match. `None` disables verification.
+    pub fn set_verify_retries(&self, retries: Option<u32>) {
+        *self
+            .verify_retries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = retries;
+    }
+
+    /// Sends a `SetEEPROM` command, verifying it if enabled, and returns the response.
+    ///
+    /// When verification is enabled the read-back command is returned instead, so the cache
+    /// always reflects what is actually stored on the device.
+    fn write_eeprom<T: CommandDescriptor>(
+        &self,
+        command: Command<T>,
+    ) -> Result<Command<T>, Box<dyn std::error::Error + Send + Sync>> {
+        let Some(retries) = self.verify_retries() else {
+            return self.execute(command);
+        };
+
+        // Keep other threads from writing the region between the write and the read-back.
+        let transport = self.lock_transport();
+
+        let len = command.data_len();
+        let mut query = Command::<T>::default();
+        query.set_id(CommandId::GetEEPROM);
+        query.set_eeprom_address(command.eeprom_address());
+        query.set_data_len(len)?;
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.exchange(&**transport, command.clone())?;
+            let readback = self.exchange(&**transport, query.clone())?;
+
+            let expected = &command.data()[..len];
+            let actual = readback.data().get(..len).unwrap_or(readback.data());
+            if expected == actual {
+                return Ok(readback);
+            }
+
+            if attempt > retries {
+                return Err(VerificationError::new(
+                    command.eeprom_address(),
+                    expected,
+                    actual,
+                    attempt,
+                )
+                .into());
+            }
+        }
+    }
+
+    /// Starts a transaction which writes several settings at once and rolls back the
+    /// already written ones if a later write fails.
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Writes `block` as stored in `source` to the device and caches the response in `profile`.
+    fn write_block(
+        &self,
+        block: Block,
+        source: &Profile,
+        profile: &mut Profile,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match block {
+            Block::MousePerformance => self.store(profile, &source.mouse_perf),
+            Block::SensorPerformance => self.store(profile, &source.sensor_perf),
+            Block::DpiLed => self.store(profile, &source.dpi_led),
+            Block::FarDistanceMode => {
+                let command = source.far_distance.builder().build();
+                let response = self.execute(command)?;
+                profile.far_distance = response.config();
+                self.emit(Event::ProfileChanged(block));
+
+                Ok(())
+            }
+            Block::SilentHeight => self.store(profile, &source.silent_mode),
+            Block::MouseInfo => self.store(profile, &source.mouse_info),
+            Block::Dpi(pair) => self.store(profile, &source.dpi[pair as usize]),
+            Block::Color(pair) => self.store(profile, &source.dpi_color[pair as usize]),
+        }
+    }
+
+    /// Reads the setting stored under `key` without waiting for the mouse or caching it.
+    fn fetch<T: EepromSetting>(
+        &self,
+        key: T::Key,
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.execute(T::query_command(key))?;
+
+        Ok(T::from_response(&response)?)
+    }
+
+    /// Writes `setting`, verifying it if enabled, and caches what the device stored.
+    fn store<T: CachedSetting>(
+        &self,
+        profile: &mut Profile,
+        setting: &T,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let response = self.write_eeprom(setting.write_command()?)?;
+
+        *T::cached_mut(profile, setting.key()) = T::from_response(&response)?;
+        self.emit(Event::ProfileChanged(T::block(setting.key())));
+
+        Ok(())
+    }
+
+    /// The cached value of a setting.
+    pub fn setting<T: CachedSetting>(&self, key: T::Key) -> T {
+        self.profile().setting::<T>(key).clone()
+    }
+
+    /// Reads a setting from the device and caches it.
+    pub fn load_setting<T: CachedSetting>(
+        &self,
+        key: T::Key,
+    ) -> Result<T, Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            let setting = self.fetch::<T>(key)?;
+            *T::cached_mut(&mut profile, key) = setting.clone();
+
+            Ok(setting)
+        })
+    }
+
+    /// Writes a whole setting, skipping the write when the cache already holds it.
+    ///
+    /// Unlike the typed setters this does not check the [`Capabilities`] of the mouse.
+    pub fn write_setting<T: CachedSetting>(
+        &self,
+        setting: &T,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            if T::cached(&profile, setting.key()) == setting {
+                return Ok(());
+            }
+
+            self.store(&mut profile, setting)
+        })
+    }
+
+    /// Reads a setting back and fails if the device does not store the same bytes.
+    pub fn verify_setting<T: EepromSetting>(
+        &self,
+        expected: &T,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let response = self.execute(T::query_command(expected.key()))?;
+            let expected_bytes = expected.to_bytes()?;
+            let actual = response.data().get(..T::LEN).unwrap_or(response.data());
+            if actual != expected_bytes {
+                return Err(VerificationError::new(
+                    T::address(expected.key()),
+                    &expected_bytes,
+                    actual,
+                    1,
+                )
+                .into());
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Reads `len` bytes starting at `address` with a plain `GetEEPROM`.
+    pub fn read_raw_eeprom(
+        &self,
+        address: EEPROMAddress,
+        len: usize,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let response = self.execute(Command::<RawEeprom>::query(address, len)?)?;
+
+            response
+                .data()
+                .get(..len)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| {
+                    format!(
+                        "Short EEPROM read at {:?}: expected {} bytes, got {}",
+                        address,
+                        len,
+                        response.data().len()
+                    )
+                    .into()
+                })
+        })
+    }
+
+    /// Writes `bytes` verbatim starting at `address`. The cached profile is not updated.
+    pub fn write_raw_eeprom(
+        &self,
+        address: EEPROMAddress,
+        bytes: &[u8],
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let command = Command::<RawEeprom>::builder(address, bytes)?.build();
+            self.write_eeprom(command)?;
+
+            Ok(())
+        })
+    }
+
+    pub fn battery_level(
+        &self,
+    ) -> Result<GetBatteryStatus, Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let resp = self.execute(Command::<GetBatteryStatus>::query())?;
+
+            Ok(resp.config())
+        })
+    }
+
+    pub fn connection_type(
+        &self,
+    ) -> Result<ConnectionType, Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let resp = self.execute(Command::<DownloadData>::query())?;
+
+            Ok(resp.config().connection_type())
+        })
+    }
+
+    /// Puts the dongle in pairing mode for this model of mouse.
+    ///
+    /// Pairing talks to the dongle, so this does not wait for the mouse to come online.
+    pub fn start_pairing(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let ids = self.execute(Command::<GetMouseCidMid>::query())?.config();
+        self.execute(
+            Command::<StartPairing>::builder()
+                .cid(ids.cid())
+                .mid(ids.mid())
+                .build(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn pairing_status(
+        &self,
+    ) -> Result<PairingStatus, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self
+            .execute(Command::<GetPairingStatus>::query())?
+            .try_config()?)
+    }
+
+    pub fn exit_pairing(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.execute(Command::<ExitPairing>::query())?;
+
+        Ok(())
+    }
+
+    pub fn set_mouse_performance_settings(
+        &self,
+        patch: MousePerfPatch,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if patch.is_empty() {
+            return Ok(());
+        }
+
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::MousePerformance(patch), &profile)?;
+            let setting = profile.mouse_perf.apply(&patch)?;
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn set_dpi_led_settings(
+        &self,
+        patch: LedPatch,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if patch.is_empty() {
+            return Ok(());
+        }
+
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::DpiLed(patch), &profile)?;
+            let setting = profile.dpi_led.apply(&patch);
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn set_far_distance_mode(
+        &self,
+        mode: bool,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::FarDistanceMode(mode), &profile)?;
+            let command = profile
+                .far_distance_mode()
+                .builder()
+                .far_distance_mode(mode)
+                .build();
+            let response = self.execute(command)?;
+
+            profile.far_distance = response.config();
+            self.emit(Event::ProfileChanged(Block::FarDistanceMode));
+
+            Ok(())
+        })
+    }
+
+    pub fn set_silent_height(
+        &self,
+        height: SilentHeightMode,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::SilentHeight(height), &profile)?;
+            self.store(&mut profile, &SilentHeight::from(height))
+        })
+    }
+
+    /// Sets the polling rate, if the model supports it over the current connection.
+    pub fn set_polling_rate(
+        &self,
+        rate: PollingRate,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let connection = self.connection_type()?;
+        self.capabilities().check_polling_rate(connection, rate)?;
+
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            let setting = profile.mouse_info.with_poll_rate(rate);
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    /// Switches the mouse to `preset`, as if the DPI button was pressed.
+    pub fn set_active_preset(
+        &self,
+        preset: Preset,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.capabilities().check_preset(preset)?;
+
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            let setting = profile.mouse_info.with_active_profile(preset as u8);
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn set_sensor_performance_settings(
+        &self,
+        patch: SensorPerfPatch,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if patch.is_empty() {
+            return Ok(());
+        }
+
+        self.wrapper(|_| {
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::SensorPerformance(patch), &profile)?;
+            let setting = profile.sensor_perf.apply(&patch)?;
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn set_dpi_profile_color(
+        &self,
+        preset: Preset,
+        color: Color,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let pair = Pair::from(preset);
+            let slot = Slot::from(preset);
+
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::Color(preset, color), &profile)?;
+            let setting = profile.dpi_color[pair as usize]
+                .with_color(self.color_correction().apply(color), slot);
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn set_dpi_profile_dpi(
+        &self,
+        preset: Preset,
+        dpi: Dpi,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let pair = Pair::from(preset);
+            let slot = Slot::from(preset);
+
+            let mut profile = self.profile_mut();
+            self.capabilities()
+                .check(&Change::Dpi(preset, dpi), &profile)?;
+            let setting = profile.dpi[pair as usize].with_dpi(dpi, slot);
+
+            self.store(&mut profile, &setting)
+        })
+    }
+
+    pub fn new_dpi_profile(
+        &self,
+        dpi: Dpi,
+        color: Color,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let num_profile = {
+                let mut profile = self.profile_mut();
+                // The index of the last preset in use, not the number of presets.
+                let num_profile = profile.mouse_info().num_profile();
+                if num_profile + 1 >= self.capabilities().presets {
+                    return Err("Maximum number of profiles reached".into());
+                }
+
+                let setting = profile.mouse_info.with_num_profile(num_profile + 1);
+                self.store(&mut profile, &setting)?;
+                num_profile
+            };
+
+            let profile = Preset::try_from(num_profile + 1)?;
+
+            self.set_dpi_profile_dpi(profile, dpi)?;
+            self.set_dpi_profile_color(profile, color)?;
+
+            Ok(())
+        })
+    }
+
+    /// Assigns evenly spaced, distinct colours to every DPI preset the model has, see
+    /// [`Capabilities::presets`].
+    pub fn set_distinct_dpi_profile_colors(
+        &self,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        self.wrapper(|_| {
+            let colors = Color::distinct(self.capabilities().presets as usize);
+            let mut profile = self.profile_mut();
+
+            for (index, colors) in colors.chunks(2).enumerate() {
+                let pair = Pair::from(Preset::try_from(index as u8 * 2)?);
+
+                let mut setting = profile.dpi_color[pair as usize].clone();
+                for (&color, slot) in colors.iter().zip([Slot::First, Slot::Second]) {
+                    setting = setting.with_color(self.color_correction().apply(color), slot);
+                }
+                self.store(&mut profile, &setting)?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    /// Answers every request with itself, reporting the mouse as online, and fails the
+    /// exchange if another one is already in flight.
+    #[derive(Default)]
+    struct Echo {
+        busy: AtomicBool,
+        exchanges: AtomicUsize,
+    }
+
+    impl Transport for Arc<Echo> {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            if self.busy.swap(true, Ordering::SeqCst) {
+                return Err("Interleaved exchange".into());
+            }
+            thread::yield_now();
+
+            let mut response = request.to_vec();
+            if response[0] == CommandId::GetWirelessMouseOnline as u8 {
+                response[4] = 0x1;
+                response[5] = 0x1;
+            }
+
+            self.exchanges.fetch_add(1, Ordering::SeqCst);
+            self.busy.store(false, Ordering::SeqCst);
+            Ok(response)
+        }
+    }
+
+    #[test]
+    fn manager_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<MouseManager>();
+    }
+
+    #[test]
+    fn distinct_colors_cover_only_the_presets_of_the_model() {
+        let manager = MouseManager::new(crate::transport::simulated::SimulatedMouse::default())
+            .unwrap()
+            .with_capabilities(Capabilities {
+                presets: 3,
+                ..Capabilities::default()
+            });
+        let untouched = manager.profile().dpi_profile(Pair::Pair2).1.color();
+
+        manager.set_distinct_dpi_profile_colors().unwrap();
+
+        let profile = manager.profile();
+        let (first, second) = profile.dpi_profile(Pair::Pair1);
+        assert_eq!(first.color(), Color::new(0xff, 0, 0));
+        assert_eq!(second.color(), Color::new(0, 0xff, 0));
+        assert_eq!(
+            profile.dpi_profile(Pair::Pair2).0.color(),
+            Color::new(0, 0, 0xff)
+        );
+        assert_eq!(profile.dpi_profile(Pair::Pair2).1.color(), untouched);
+    }
+
+    #[test]
+    fn concurrent_callers_do_not_interleave() {
+        let echo = Arc::new(Echo::default());
+        let manager = Arc::new(MouseManager::new_without_profile(Arc::clone(&echo)));
+
+        let handles: Vec<_> = (0..8u8)
+            .map(|index| {
+                let manager = Arc::clone(&manager);
+                thread::spawn(move || {
+                    let preset = Preset::try_from(index).unwrap();
+                    let dpi = Dpi::new(400 + index as u16 * 100);
+                    for _ in 0..25 {
+                        manager.set_dpi_profile_dpi(preset, dpi).unwrap();
+                        manager
+                            .execute(Command::<FarDistanceMode>::query())
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+
+        // Waiting for the mouse polls twice, then the write and the query: 4 per iteration.
+        assert_eq!(echo.exchanges.load(Ordering::SeqCst), 8 * 25 * 4);
+
+        // Presets sharing a pair were written concurrently, none of the updates may be lost.
+        let profile = manager.profile();
+        for (index, pair) in [Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4]
+            .into_iter()
+            .enumerate()
+        {
+            let setting = profile.dpi_pair_setting(pair);
+            assert_eq!(setting.dpi(Slot::First), Dpi::new(400 + index as u16 * 200));
+            assert_eq!(
+                setting.dpi(Slot::Second),
+                Dpi::new(500 + index as u16 * 200)
+            );
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use super::{MouseManager, Profile};
+use crate::{color::Color, commands::prelude::*};
+use libatk_rs::prelude::*;
+
+/// Writes of a block a rollback attempts before giving up on restoring it.
+const ROLLBACK_ATTEMPTS: u32 = 3;
+
+/// How long a rollback waits for a dormant mouse before each attempt.
+const ROLLBACK_ONLINE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// A single staged modification of the device configuration.
+#[derive(Debug, Clone)]
+pub enum Change {
+    MousePerformance(MousePerfPatch),
+    SensorPerformance(SensorPerfPatch),
+    DpiLed(LedPatch),
+    FarDistanceMode(bool),
+    SilentHeight(SilentHeightMode),
+    Dpi(Preset, Dpi),
+    Color(Preset, Color),
+}
+
+/// A region of the device configuration that is written as a whole.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Block {
+    MousePerformance,
+    SensorPerformance,
+    DpiLed,
+    FarDistanceMode,
+    SilentHeight,
+    MouseInfo,
+    Dpi(Pair),
+    Color(Pair),
+}
+
+impl std::fmt::Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Block::MousePerformance => write!(f, "Mouse performance settings"),
+            Block::SensorPerformance => write!(f, "Sensor performance settings"),
+            Block::DpiLed => write!(f, "DPI LED settings"),
+            Block::FarDistanceMode => write!(f, "Far distance mode"),
+            Block::SilentHeight => write!(f, "Silent height"),
+            Block::MouseInfo => write!(f, "Polling rate and presets"),
+            Block::Dpi(pair) => write!(f, "DPI {:?}", pair),
+            Block::Color(pair) => write!(f, "DPI color {:?}", pair),
+        }
+    }
+}
+
+impl Change {
+    pub fn block(&self) -> Block {
+        match self {
+            Change::MousePerformance(_) => Block::MousePerformance,
+            Change::SensorPerformance(_) => Block::SensorPerformance,
+            Change::DpiLed(_) => Block::DpiLed,
+            Change::FarDistanceMode(_) => Block::FarDistanceMode,
+            Change::SilentHeight(_) => Block::SilentHeight,
+            Change::Dpi(preset, _) => Block::Dpi(Pair::from(*preset)),
+            Change::Color(preset, _) => Block::Color(Pair::from(*preset)),
+        }
+    }
+
+    fn apply(&self, manager: &MouseManager, profile: &mut Profile) -> Result<(), Error> {
+        manager.capabilities().check(self, profile)?;
+
+        match self {
+            Change::MousePerformance(patch) => {
+                profile.mouse_perf = profile.mouse_perf.apply(patch)?
+            }
+            Change::SensorPerformance(patch) => {
+                profile.sensor_perf = profile.sensor_perf.apply(patch)?
+            }
+            Change::DpiLed(patch) => profile.dpi_led = profile.dpi_led.apply(patch),
+            Change::FarDistanceMode(mode) => profile.far_distance = FarDistanceMode::from(*mode),
+            Change::SilentHeight(mode) => profile.silent_mode = SilentHeight::from(*mode),
+            Change::Dpi(preset, dpi) => {
+                let pair = Pair::from(*preset) as usize;
+                profile.dpi[pair] = profile.dpi[pair].with_dpi(*dpi, Slot::from(*preset));
+            }
+            Change::Color(preset, color) => {
+                let pair = Pair::from(*preset) as usize;
+                let color = manager.color_correction().apply(*color);
+                profile.dpi_color[pair] =
+                    profile.dpi_color[pair].with_color(color, Slot::from(*preset));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returned when a transaction could not be committed.
+///
+/// By the time this is returned, every block that was written (including the one that
+/// failed, whose state is unknown) has been restored from the pre-transaction snapshot
+/// where possible.
+#[derive(Debug)]
+pub struct TransactionError {
+    failed: Block,
+    cause: Box<dyn std::error::Error + Send + Sync>,
+    rolled_back: Vec<Block>,
+    rollback_failures: Vec<(Block, Box<dyn std::error::Error + Send + Sync>)>,
+}
+
+impl TransactionError {
+    /// The block whose write (or validation) failed.
+    pub fn failed(&self) -> Block {
+        self.failed
+    }
+
+    pub fn cause(&self) -> &dyn std::error::Error {
+        self.cause.as_ref()
+    }
+
+    /// Blocks that were successfully restored to their pre-transaction value.
+    pub fn rolled_back(&self) -> &[Block] {
+        &self.rolled_back
+    }
+
+    /// Blocks that could not be restored, leaving the device in a mixed state.
+    pub fn rollback_failures(&self) -> &[(Block, Box<dyn std::error::Error + Send + Sync>)] {
+        &self.rollback_failures
+    }
+
+    pub fn is_consistent(&self) -> bool {
+        self.rollback_failures.is_empty()
+    }
+}
+
+impl std::fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Transaction failed at {}: {}", self.failed, self.cause)?;
+
+        if !self.rolled_back.is_empty() {
+            let blocks: Vec<String> = self.rolled_back.iter().map(Block::to_string).collect();
+            write!(f, "\nRolled back: {}", blocks.join(", "))?;
+        }
+
+        for (block, error) in &self.rollback_failures {
+            write!(f, "\nFailed to roll back {}: {}", block, error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for TransactionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.cause.as_ref())
+    }
+}
+
+/// Stages changes and writes them to the device in order, restoring the previous
+/// configuration if any write fails.
+///
+/// Changes to the same block are merged, so each block is written at most once.
+pub struct Transaction<'a> {
+    manager: &'a MouseManager,
+    changes: Vec<Change>,
+}
+
+impl<'a> Transaction<'a> {
+    pub(super) fn new(manager: &'a MouseManager) -> Self {
+        Transaction {
+            manager,
+            changes: Vec::new(),
+        }
+    }
+
+    pub fn stage(mut self, change: Change) -> Self {
+        self.changes.push(change);
+        self
+    }
+
+    pub fn mouse_performance_settings(self, patch: MousePerfPatch) -> Self {
+        self.stage(Change::MousePerformance(patch))
+    }
+
+    pub fn sensor_performance_settings(self, patch: SensorPerfPatch) -> Self {
+        self.stage(Change::SensorPerformance(patch))
+    }
+
+    pub fn dpi_led_settings(self, patch: LedPatch) -> Self {
+        self.stage(Change::DpiLed(patch))
+    }
+
+    pub fn far_distance_mode(self, mode: bool) -> Self {
+        self.stage(Change::FarDistanceMode(mode))
+    }
+
+    pub fn silent_height(self, mode: SilentHeightMode) -> Self {
+        self.stage(Change::SilentHeight(mode))
+    }
+
+    pub fn dpi(self, preset: Preset, dpi: Dpi) -> Self {
+        self.stage(Change::Dpi(preset, dpi))
+    }
+
+    pub fn color(self, preset: Preset, color: Color) -> Self {
+        self.stage(Change::Color(preset, color))
+    }
+
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    /// Blocks that will be written, in the order they were first staged.
+    pub fn blocks(&self) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        for change in &self.changes {
+            if !blocks.contains(&change.block()) {
+                blocks.push(change.block());
+            }
+        }
+        blocks
+    }
+
+    /// Writes every staged block and returns them in the order they were written.
+    pub fn commit(self) -> Result<Vec<Block>, TransactionError> {
+        let blocks = self.blocks();
+        let Some(&first) = blocks.first() else {
+            return Ok(blocks);
+        };
+
+        // Held until the last write (or rollback) is cached, so concurrent setters can not
+        // slip in between and be overwritten by the rollback.
+        let mut profile = self.manager.profile_mut();
+        let snapshot = profile.clone();
+        let mut target = snapshot.clone();
+        for change in &self.changes {
+            change
+                .apply(self.manager, &mut target)
+                .map_err(|e| TransactionError {
+                    failed: change.block(),
+                    cause: e.into(),
+                    rolled_back: Vec::new(),
+                    rollback_failures: Vec::new(),
+                })?;
+        }
+
+        self.manager
+            .wait_for_mouse_online()
+            .map_err(|cause| TransactionError {
+                failed: first,
+                cause,
+                rolled_back: Vec::new(),
+                rollback_failures: Vec::new(),
+            })?;
+
+        for (index, &block) in blocks.iter().enumerate() {
+            if let Err(cause) = self.manager.write_block(block, &target, &mut profile) {
+                return Err(self.rollback(&snapshot, &mut profile, &blocks[..=index], cause));
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    fn rollback(
+        &self,
+        snapshot: &Profile,
+        profile: &mut Profile,
+        written: &[Block],
+        cause: Box<dyn std::error::Error + Send + Sync>,
+    ) -> TransactionError {
+        let mut error = TransactionError {
+            failed: *written
+                .last()
+                .expect("at least the failed block was attempted"),
+            cause,
+            rolled_back: Vec::new(),
+            rollback_failures: Vec::new(),
+        };
+
+        for &block in written.iter().rev() {
+            match self.restore(block, snapshot, profile) {
+                Ok(()) => error.rolled_back.push(block),
+                Err(e) => error.rollback_failures.push((block, e)),
+            }
+        }
+
+        error
+    }
+
+    /// Writes `block` back from `snapshot`. The failed write may have been caused by the
+    /// mouse dozing off, so each attempt first waits for it to come back online.
+    fn restore(
+        &self,
+        block: Block,
+        snapshot: &Profile,
+        profile: &mut Profile,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = self
+                .manager
+                .wait_for_mouse_online_within(ROLLBACK_ONLINE_TIMEOUT)
+                .and_then(|()| self.manager.write_block(block, snapshot, profile));
+
+            match result {
+                Err(_) if attempt < ROLLBACK_ATTEMPTS => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{simulated::SimulatedMouse, Transport};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// A simulated mouse whose `SetEEPROM` writes fail when `fails` says so, counting them
+    /// from zero. The mouse dozes off on each failure and wakes up after `naps` online
+    /// queries.
+    struct Flaky {
+        mouse: SimulatedMouse,
+        fails: fn(usize) -> bool,
+        naps: usize,
+        writes: AtomicUsize,
+        asleep: AtomicUsize,
+    }
+
+    impl Flaky {
+        fn new(fails: fn(usize) -> bool, naps: usize) -> Arc<Self> {
+            Arc::new(Flaky {
+                mouse: SimulatedMouse::default(),
+                fails,
+                naps,
+                writes: AtomicUsize::new(0),
+                asleep: AtomicUsize::new(0),
+            })
+        }
+
+        fn eeprom(&self) -> Vec<u8> {
+            self.mouse.with_state(|state| state.eeprom.clone())
+        }
+    }
+
+    impl Transport for Arc<Flaky> {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            if request[0] == CommandId::GetWirelessMouseOnline as u8 {
+                match self.asleep.load(Ordering::SeqCst) {
+                    0 => self.mouse.with_state(|state| state.online = true),
+                    naps => self.asleep.store(naps - 1, Ordering::SeqCst),
+                }
+            }
+
+            if request[0] == CommandId::SetEEPROM as u8
+                && (self.fails)(self.writes.fetch_add(1, Ordering::SeqCst))
+            {
+                self.mouse.with_state(|state| state.online = self.naps == 0);
+                self.asleep.store(self.naps, Ordering::SeqCst);
+                return Err("Write failed".into());
+            }
+
+            self.mouse.exchange(request)
+        }
+    }
+
+    fn commit(manager: &MouseManager) -> TransactionError {
+        manager
+            .transaction()
+            .dpi(Preset::Preset1, Dpi::new(1600))
+            .silent_height(SilentHeightMode::TwoMm)
+            .commit()
+            .unwrap_err()
+    }
+
+    fn assert_unchanged(manager: &MouseManager, eeprom: &[u8], flaky: &Flaky) {
+        assert_eq!(flaky.eeprom(), eeprom);
+
+        let profile = manager.profile();
+        assert_eq!(profile.dpi_profile(Pair::Pair1).0.dpi(), Dpi::new(400));
+        assert_eq!(
+            profile.silent_height().silent_height(),
+            SilentHeightMode::Off
+        );
+    }
+
+    #[test]
+    fn failed_commit_restores_the_failed_block() {
+        let flaky = Flaky::new(|write| write == 0, 0);
+        let manager = MouseManager::new(Arc::clone(&flaky)).unwrap();
+        let eeprom = flaky.eeprom();
+
+        let error = commit(&manager);
+        assert_eq!(error.failed(), Block::Dpi(Pair::Pair1));
+        assert_eq!(error.cause().to_string(), "Write failed");
+        assert_eq!(error.rolled_back(), [Block::Dpi(Pair::Pair1)]);
+        assert!(error.is_consistent());
+        assert_unchanged(&manager, &eeprom, &flaky);
+    }
+
+    #[test]
+    fn rollback_waits_for_the_mouse_to_come_back_online() {
+        let flaky = Flaky::new(|write| write == 1, 3);
+        let manager = MouseManager::new(Arc::clone(&flaky)).unwrap();
+        let eeprom = flaky.eeprom();
+
+        let error = commit(&manager);
+        assert_eq!(error.failed(), Block::SilentHeight);
+        assert_eq!(
+            error.rolled_back(),
+            [Block::SilentHeight, Block::Dpi(Pair::Pair1)]
+        );
+        assert!(error.is_consistent());
+        assert_unchanged(&manager, &eeprom, &flaky);
+    }
+
+    #[test]
+    fn failed_rollback_is_retried_and_reported() {
+        let flaky = Flaky::new(|write| write >= 1, 0);
+        let manager = MouseManager::new(Arc::clone(&flaky)).unwrap();
+
+        let error = commit(&manager);
+        assert_eq!(error.failed(), Block::SilentHeight);
+        assert!(error.rolled_back().is_empty());
+        let failures: Vec<Block> = error
+            .rollback_failures()
+            .iter()
+            .map(|(block, _)| *block)
+            .collect();
+        assert_eq!(failures, [Block::SilentHeight, Block::Dpi(Pair::Pair1)]);
+        assert!(!error.is_consistent());
+        assert_eq!(
+            flaky.writes.load(Ordering::SeqCst),
+            2 + 2 * ROLLBACK_ATTEMPTS as usize
+        );
+        assert!(error
+            .to_string()
+            .contains("Failed to roll back DPI Pair1: Write failed"));
+    }
+}
@@ -0,0 +1,177 @@
+use libatk_rs::prelude::*;
+
+/// Returned when the data read back from an EEPROM region does not match what was written.
+#[derive(Debug)]
+pub struct VerificationError {
+    address: EEPROMAddress,
+    expected: Vec<u8>,
+    actual: Vec<u8>,
+    attempts: u32,
+}
+
+impl VerificationError {
+    pub(super) fn new(
+        address: EEPROMAddress,
+        expected: &[u8],
+        actual: &[u8],
+        attempts: u32,
+    ) -> Self {
+        VerificationError {
+            address,
+            expected: expected.to_vec(),
+            actual: actual.to_vec(),
+            attempts,
+        }
+    }
+
+    pub fn address(&self) -> EEPROMAddress {
+        self.address
+    }
+
+    /// The bytes that were written, including their checksums.
+    pub fn expected(&self) -> &[u8] {
+        &self.expected
+    }
+
+    /// The bytes read back after the last attempt.
+    pub fn actual(&self) -> &[u8] {
+        &self.actual
+    }
+
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+}
+
+impl std::fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "EEPROM write to {:?} did not stick after {} attempt(s): wrote {:02X?}, read back {:02X?}",
+            self.address, self.attempts, self.expected, self.actual
+        )
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::prelude::*,
+        manager::MouseManager,
+        transport::{simulated::SimulatedMouse, Frame, Transport},
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// A simulated mouse that garbles the first byte of its first `garbled` writes after
+    /// acknowledging them, as if they did not stick.
+    struct Forgetful {
+        mouse: SimulatedMouse,
+        garbled: usize,
+        writes: AtomicUsize,
+        reads: AtomicUsize,
+    }
+
+    impl Forgetful {
+        fn new(garbled: usize) -> Arc<Self> {
+            Arc::new(Forgetful {
+                mouse: SimulatedMouse::default(),
+                garbled,
+                writes: AtomicUsize::new(0),
+                reads: AtomicUsize::new(0),
+            })
+        }
+
+        fn counts(&self) -> (usize, usize) {
+            (
+                self.writes.load(Ordering::SeqCst),
+                self.reads.load(Ordering::SeqCst),
+            )
+        }
+    }
+
+    impl Transport for Arc<Forgetful> {
+        fn exchange(
+            &self,
+            request: &[u8],
+        ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+            let response = self.mouse.exchange(request)?;
+
+            let command = Command::<Frame>::try_from(request)?;
+            let counter = match command.id() {
+                CommandId::SetEEPROM => &self.writes,
+                CommandId::GetEEPROM => &self.reads,
+                _ => return Ok(response),
+            };
+            let count = counter.fetch_add(1, Ordering::SeqCst);
+            if matches!(command.id(), CommandId::SetEEPROM) && count < self.garbled {
+                let start = command.eeprom_address() as usize;
+                self.mouse.with_state(|state| state.eeprom[start] ^= 0xff);
+            }
+
+            Ok(response)
+        }
+    }
+
+    fn manager(mouse: &Arc<Forgetful>, retries: Option<u32>) -> MouseManager {
+        let manager = MouseManager::new(Arc::clone(mouse)).unwrap();
+        manager.set_verify_retries(retries);
+        mouse.reads.store(0, Ordering::SeqCst);
+        manager
+    }
+
+    #[test]
+    fn a_wrong_read_back_is_written_again() {
+        let mouse = Forgetful::new(1);
+        let manager = manager(&mouse, Some(2));
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset1, Dpi::new(1600))
+            .unwrap();
+
+        assert_eq!(mouse.counts(), (2, 2));
+        assert_eq!(
+            manager.profile().dpi_profile(Pair::Pair1).0.dpi(),
+            Dpi::new(1600)
+        );
+    }
+
+    #[test]
+    fn a_write_that_never_sticks_fails_with_the_mismatch() {
+        let mouse = Forgetful::new(usize::MAX);
+        let manager = manager(&mouse, Some(2));
+
+        let error = manager
+            .set_dpi_profile_dpi(Preset::Preset1, Dpi::new(1600))
+            .unwrap_err();
+        let error = error.downcast_ref::<VerificationError>().unwrap();
+
+        assert_eq!(error.attempts(), 3);
+        assert_eq!(mouse.counts(), (3, 3));
+        assert_eq!(error.expected().len(), error.actual().len());
+        assert_eq!(error.actual()[0], error.expected()[0] ^ 0xff);
+        assert_eq!(error.expected()[1..], error.actual()[1..]);
+        assert!(error.to_string().contains("after 3 attempt(s)"));
+        assert_eq!(
+            manager.profile().dpi_profile(Pair::Pair1).0.dpi(),
+            Dpi::new(400)
+        );
+    }
+
+    #[test]
+    fn without_retries_writes_are_not_read_back() {
+        let mouse = Forgetful::new(usize::MAX);
+        let manager = manager(&mouse, None);
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset1, Dpi::new(1600))
+            .unwrap();
+
+        assert_eq!(mouse.counts(), (1, 0));
+    }
+}
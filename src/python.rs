@@ -0,0 +1,889 @@
+//! Python bindings, built as the `atk_hub` module with maturin.
+//!
+//! Device I/O releases the GIL, so several mice can be configured from Python threads at
+//! once. Errors are raised as subclasses of `atk_hub.AtkError`, file errors as `OSError` and
+//! invalid arguments as `ValueError`.
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+use crate::{
+    color::Color,
+    commands::prelude::*,
+    discovery::{self, DiscoveredDevice},
+    manager::{transaction::TransactionError, verify::VerificationError, MouseManager, Profile},
+    trace::replay::{Replay, ReplayError},
+    transport::simulated::SimulatedMouse,
+    types::{Decaseconds, Duration, Milliseconds},
+};
+use libatk_rs::prelude::*;
+
+/// Mirrors the crate's errors, registered on the module under the same names.
+mod exceptions {
+    use pyo3::{create_exception, exceptions::PyException};
+
+    create_exception!(
+        atk_hub,
+        AtkError,
+        PyException,
+        "Base class of the errors raised by atk_hub."
+    );
+    create_exception!(
+        atk_hub,
+        DeviceError,
+        AtkError,
+        "Opening or talking to the HID device failed."
+    );
+    create_exception!(
+        atk_hub,
+        ProtocolError,
+        AtkError,
+        "The device answered with a malformed frame."
+    );
+    create_exception!(
+        atk_hub,
+        InvalidValueError,
+        AtkError,
+        "A value is out of range, or data read from the device does not parse."
+    );
+    create_exception!(
+        atk_hub,
+        VerificationError,
+        AtkError,
+        "Read-back verification of a write failed."
+    );
+    create_exception!(
+        atk_hub,
+        ReplayError,
+        AtkError,
+        "The request does not match the replayed trace."
+    );
+    create_exception!(
+        atk_hub,
+        TransactionError,
+        AtkError,
+        "Applying a profile failed, the written blocks were rolled back where possible."
+    );
+}
+
+/// A crate error taken apart on the thread that raised it, since `Box<dyn Error>` can not
+/// cross back to the thread holding the GIL.
+enum Failure {
+    Device(String),
+    Protocol(String),
+    InvalidValue(String),
+    Verification(String),
+    Replay(String),
+    Transaction(String),
+    Io(std::io::Error),
+    Other(String),
+}
+
+impl From<Box<dyn std::error::Error + Send + Sync>> for Failure {
+    fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        let message = error.to_string();
+
+        if let Some(error) = error.downcast_ref::<Error>() {
+            return match error {
+                Error::HidError(_) => Failure::Device(message),
+                Error::ParseError(_) => Failure::InvalidValue(message),
+                _ => Failure::Protocol(message),
+            };
+        }
+
+        if error.is::<VerificationError>() {
+            Failure::Verification(message)
+        } else if error.is::<ReplayError>() {
+            Failure::Replay(message)
+        } else if error.is::<TransactionError>() {
+            Failure::Transaction(message)
+        } else {
+            match error.downcast::<std::io::Error>() {
+                Ok(error) => Failure::Io(*error),
+                Err(_) => Failure::Other(message),
+            }
+        }
+    }
+}
+
+impl From<Failure> for PyErr {
+    fn from(failure: Failure) -> Self {
+        match failure {
+            Failure::Device(message) => exceptions::DeviceError::new_err(message),
+            Failure::Protocol(message) => exceptions::ProtocolError::new_err(message),
+            Failure::InvalidValue(message) => exceptions::InvalidValueError::new_err(message),
+            Failure::Verification(message) => exceptions::VerificationError::new_err(message),
+            Failure::Replay(message) => exceptions::ReplayError::new_err(message),
+            Failure::Transaction(message) => exceptions::TransactionError::new_err(message),
+            // Raised as the matching `OSError` subclass, e.g. `FileNotFoundError`.
+            Failure::Io(error) => error.into(),
+            Failure::Other(message) => exceptions::AtkError::new_err(message),
+        }
+    }
+}
+
+/// Runs `func` with the GIL released.
+fn run<U: Send>(
+    py: Python<'_>,
+    func: impl FnOnce() -> Result<U, Box<dyn std::error::Error + Send + Sync>> + Send,
+) -> PyResult<U> {
+    py.allow_threads(|| func().map_err(Failure::from))
+        .map_err(Into::into)
+}
+
+#[pyclass(name = "Dpi", module = "atk_hub", frozen, eq, hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PyDpi {
+    value: u16,
+}
+
+#[pymethods]
+impl PyDpi {
+    #[new]
+    fn new(value: u16) -> PyResult<Self> {
+        if value < 50 || !value.is_multiple_of(50) {
+            return Err(PyValueError::new_err(format!(
+                "DPI {} is not a positive multiple of 50",
+                value
+            )));
+        }
+        Ok(PyDpi { value })
+    }
+
+    #[getter]
+    fn value(&self) -> u16 {
+        self.value
+    }
+
+    fn __int__(&self) -> u16 {
+        self.value
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Dpi({})", self.value)
+    }
+}
+
+impl From<Dpi> for PyDpi {
+    fn from(dpi: Dpi) -> Self {
+        PyDpi { value: dpi.dpi() }
+    }
+}
+
+/// A `Dpi` or a plain `int`.
+#[derive(FromPyObject)]
+enum DpiArg {
+    Dpi(PyDpi),
+    Value(u16),
+}
+
+impl DpiArg {
+    fn dpi(self) -> PyResult<Dpi> {
+        let dpi = match self {
+            DpiArg::Dpi(dpi) => dpi,
+            DpiArg::Value(value) => PyDpi::new(value)?,
+        };
+        Ok(Dpi::new(dpi.value))
+    }
+}
+
+#[pyclass(name = "Color", module = "atk_hub", frozen, eq, hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PyColor {
+    inner: Color,
+}
+
+#[pymethods]
+impl PyColor {
+    #[new]
+    fn new(red: u8, green: u8, blue: u8) -> Self {
+        Color::new(red, green, blue).into()
+    }
+
+    /// Parses hex, colour names, `rgb()`, `hsv()` and `hsl()`, like the command line.
+    #[staticmethod]
+    fn parse(text: &str) -> PyResult<Self> {
+        text.parse::<Color>()
+            .map(Into::into)
+            .map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    /// `count` colours spread evenly around the hue circle.
+    #[staticmethod]
+    fn distinct(count: usize) -> Vec<Self> {
+        Color::distinct(count).into_iter().map(Into::into).collect()
+    }
+
+    #[getter]
+    fn red(&self) -> u8 {
+        self.inner.red()
+    }
+
+    #[getter]
+    fn green(&self) -> u8 {
+        self.inner.green()
+    }
+
+    #[getter]
+    fn blue(&self) -> u8 {
+        self.inner.blue()
+    }
+
+    fn __str__(&self) -> String {
+        self.inner.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Color('{}')", self.inner)
+    }
+}
+
+impl From<Color> for PyColor {
+    fn from(inner: Color) -> Self {
+        PyColor { inner }
+    }
+}
+
+/// A `Color` or a string accepted by `Color.parse`.
+#[derive(FromPyObject)]
+enum ColorArg {
+    Color(PyColor),
+    Text(String),
+}
+
+impl ColorArg {
+    fn color(self) -> PyResult<Color> {
+        match self {
+            ColorArg::Color(color) => Ok(color.inner),
+            ColorArg::Text(text) => PyColor::parse(&text).map(|color| color.inner),
+        }
+    }
+}
+
+#[pyclass(name = "LedSettings", module = "atk_hub", get_all, set_all, eq)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyLedSettings {
+    mode: LedEffectMode,
+    brightness: LedBrightnessLevel,
+    breathing_rate: LedBreathingRate,
+    enabled: bool,
+}
+
+#[pymethods]
+impl PyLedSettings {
+    #[new]
+    fn new(
+        mode: LedEffectMode,
+        brightness: LedBrightnessLevel,
+        breathing_rate: LedBreathingRate,
+        enabled: bool,
+    ) -> Self {
+        PyLedSettings {
+            mode,
+            brightness,
+            breathing_rate,
+            enabled,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "LedSettings(mode=LedEffectMode.{:?}, brightness=LedBrightnessLevel.{:?}, \
+             breathing_rate=LedBreathingRate.{:?}, enabled={})",
+            self.mode,
+            self.brightness,
+            self.breathing_rate,
+            py_bool(self.enabled)
+        )
+    }
+}
+
+impl From<&DpiLedSettings> for PyLedSettings {
+    fn from(settings: &DpiLedSettings) -> Self {
+        PyLedSettings {
+            mode: settings.mode(),
+            brightness: settings.brightness(),
+            breathing_rate: settings.breathing_rate(),
+            enabled: settings.enabled(),
+        }
+    }
+}
+
+impl From<&PyLedSettings> for LedPatch {
+    fn from(settings: &PyLedSettings) -> Self {
+        LedPatch::new()
+            .mode(settings.mode)
+            .brightness(settings.brightness)
+            .breathing_rate(settings.breathing_rate)
+            .enabled(settings.enabled)
+    }
+}
+
+/// Times are in milliseconds, the close LED time in whole multiples of 10 seconds.
+#[pyclass(name = "MousePerformance", module = "atk_hub", get_all, set_all, eq)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PyMousePerformance {
+    stabilization_time_ms: u32,
+    motion_sync: bool,
+    close_led_time_ms: u32,
+    linear_correction: bool,
+    ripple_control: bool,
+}
+
+#[pymethods]
+impl PyMousePerformance {
+    #[new]
+    fn new(
+        stabilization_time_ms: u32,
+        motion_sync: bool,
+        close_led_time_ms: u32,
+        linear_correction: bool,
+        ripple_control: bool,
+    ) -> Self {
+        PyMousePerformance {
+            stabilization_time_ms,
+            motion_sync,
+            close_led_time_ms,
+            linear_correction,
+            ripple_control,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "MousePerformance(stabilization_time_ms={}, motion_sync={}, close_led_time_ms={}, \
+             linear_correction={}, ripple_control={})",
+            self.stabilization_time_ms,
+            py_bool(self.motion_sync),
+            self.close_led_time_ms,
+            py_bool(self.linear_correction),
+            py_bool(self.ripple_control)
+        )
+    }
+}
+
+impl From<&MousePerfSettings> for PyMousePerformance {
+    fn from(settings: &MousePerfSettings) -> Self {
+        PyMousePerformance {
+            stabilization_time_ms: settings.stabilization_time().as_millis(),
+            motion_sync: settings.motion_sync(),
+            close_led_time_ms: settings.close_led_time().as_millis(),
+            linear_correction: settings.linear_correction(),
+            ripple_control: settings.ripple_control(),
+        }
+    }
+}
+
+impl From<&PyMousePerformance> for MousePerfPatch {
+    fn from(settings: &PyMousePerformance) -> Self {
+        MousePerfPatch::new()
+            .stabilization_time(Duration::<Milliseconds>::from_millis(
+                settings.stabilization_time_ms,
+            ))
+            .motion_sync(settings.motion_sync)
+            .close_led_time(Duration::<Decaseconds>::from_millis(
+                settings.close_led_time_ms,
+            ))
+            .linear_correction(settings.linear_correction)
+            .ripple_control(settings.ripple_control)
+    }
+}
+
+/// Times are in milliseconds, the sensor sleep time in whole multiples of 10 seconds.
+#[pyclass(name = "SensorPerformance", module = "atk_hub", get_all, set_all, eq)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct PySensorPerformance {
+    move_close_led: bool,
+    sensor_sleep: bool,
+    sensor_sleep_time_ms: u32,
+    performance_mode: bool,
+    rf_tx_time_ms: u32,
+}
+
+#[pymethods]
+impl PySensorPerformance {
+    #[new]
+    fn new(
+        move_close_led: bool,
+        sensor_sleep: bool,
+        sensor_sleep_time_ms: u32,
+        performance_mode: bool,
+        rf_tx_time_ms: u32,
+    ) -> Self {
+        PySensorPerformance {
+            move_close_led,
+            sensor_sleep,
+            sensor_sleep_time_ms,
+            performance_mode,
+            rf_tx_time_ms,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "SensorPerformance(move_close_led={}, sensor_sleep={}, sensor_sleep_time_ms={}, \
+             performance_mode={}, rf_tx_time_ms={})",
+            py_bool(self.move_close_led),
+            py_bool(self.sensor_sleep),
+            self.sensor_sleep_time_ms,
+            py_bool(self.performance_mode),
+            self.rf_tx_time_ms
+        )
+    }
+}
+
+impl From<&SensorPerfSettings> for PySensorPerformance {
+    fn from(settings: &SensorPerfSettings) -> Self {
+        PySensorPerformance {
+            move_close_led: settings.move_close_led(),
+            sensor_sleep: settings.sensor_sleep(),
+            sensor_sleep_time_ms: settings.sensor_sleep_time().as_millis(),
+            performance_mode: settings.performance_mode(),
+            rf_tx_time_ms: settings.rf_tx_time().as_millis(),
+        }
+    }
+}
+
+impl From<&PySensorPerformance> for SensorPerfPatch {
+    fn from(settings: &PySensorPerformance) -> Self {
+        SensorPerfPatch::new()
+            .move_close_led(settings.move_close_led)
+            .sensor_sleep(settings.sensor_sleep)
+            .sensor_sleep_time(Duration::<Decaseconds>::from_millis(
+                settings.sensor_sleep_time_ms,
+            ))
+            .performance_mode(settings.performance_mode)
+            .rf_tx_time(Duration::<Milliseconds>::from_millis(
+                settings.rf_tx_time_ms,
+            ))
+    }
+}
+
+fn py_bool(value: bool) -> &'static str {
+    if value {
+        "True"
+    } else {
+        "False"
+    }
+}
+
+const PRESETS: [Preset; 8] = [
+    Preset::Preset1,
+    Preset::Preset2,
+    Preset::Preset3,
+    Preset::Preset4,
+    Preset::Preset5,
+    Preset::Preset6,
+    Preset::Preset7,
+    Preset::Preset8,
+];
+
+fn gear(profile: &Profile, preset: Preset) -> Gear {
+    let (first, second) = profile.dpi_profile(preset.into());
+    match Slot::from(preset) {
+        Slot::First => first,
+        Slot::Second => second,
+    }
+}
+
+/// A snapshot of a mouse's configuration. Pass it to `MouseManager.apply` to copy it to
+/// another mouse.
+#[pyclass(name = "Profile", module = "atk_hub", frozen)]
+#[derive(Debug, Clone)]
+pub struct PyProfile {
+    inner: Profile,
+}
+
+#[pymethods]
+impl PyProfile {
+    #[getter]
+    fn polling_rate(&self) -> PollingRate {
+        self.inner.mouse_info().poll_rate()
+    }
+
+    /// `None` if the stored value is not a valid preset.
+    #[getter]
+    fn active_preset(&self) -> Option<Preset> {
+        Preset::try_from(self.inner.mouse_info().active_profile()).ok()
+    }
+
+    /// The DPI of each preset, in order.
+    #[getter]
+    fn dpi(&self) -> Vec<PyDpi> {
+        PRESETS
+            .iter()
+            .map(|&preset| gear(&self.inner, preset).dpi().into())
+            .collect()
+    }
+
+    /// The colour of each preset, in order.
+    #[getter]
+    fn colors(&self) -> Vec<PyColor> {
+        PRESETS
+            .iter()
+            .map(|&preset| gear(&self.inner, preset).color().into())
+            .collect()
+    }
+
+    #[getter]
+    fn led(&self) -> PyLedSettings {
+        self.inner.dpi_led_settings().into()
+    }
+
+    #[getter]
+    fn mouse_performance(&self) -> PyMousePerformance {
+        self.inner.mouse_performance_settings().into()
+    }
+
+    #[getter]
+    fn sensor_performance(&self) -> PySensorPerformance {
+        self.inner.sensor_performance_settings().into()
+    }
+
+    #[getter]
+    fn far_distance_mode(&self) -> bool {
+        self.inner.far_distance_mode().far_distance_mode()
+    }
+
+    #[getter]
+    fn silent_height(&self) -> SilentHeightMode {
+        self.inner.silent_height().silent_height()
+    }
+}
+
+#[pyclass(name = "BatteryStatus", module = "atk_hub", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyBatteryStatus {
+    /// Percent.
+    level: u8,
+    charge: u8,
+    voltage: f32,
+}
+
+#[pymethods]
+impl PyBatteryStatus {
+    fn __repr__(&self) -> String {
+        format!(
+            "BatteryStatus(level={}, charge={}, voltage={})",
+            self.level, self.charge, self.voltage
+        )
+    }
+}
+
+/// A HID interface that may be an ATK mouse, see `discover`.
+#[pyclass(name = "DeviceInfo", module = "atk_hub", frozen, get_all)]
+#[derive(Debug, Clone)]
+pub struct PyDeviceInfo {
+    vendor_id: u16,
+    product_id: u16,
+    usage_page: u16,
+    usage: u16,
+    manufacturer: String,
+    product: String,
+}
+
+#[pymethods]
+impl PyDeviceInfo {
+    fn open(&self, py: Python<'_>) -> PyResult<PyMouseManager> {
+        PyMouseManager::open(
+            py,
+            self.vendor_id,
+            self.product_id,
+            self.usage_page,
+            self.usage,
+        )
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "DeviceInfo({:04x}:{:04x}:{:04x}:{:04x} '{}' '{}')",
+            self.vendor_id,
+            self.product_id,
+            self.usage_page,
+            self.usage,
+            self.manufacturer,
+            self.product
+        )
+    }
+}
+
+impl From<DiscoveredDevice> for PyDeviceInfo {
+    fn from(device: DiscoveredDevice) -> Self {
+        PyDeviceInfo {
+            vendor_id: device.vendor_id,
+            product_id: device.product_id,
+            usage_page: device.usage_page,
+            usage: device.usage,
+            manufacturer: device.manufacturer,
+            product: device.product,
+        }
+    }
+}
+
+/// Lists HID interfaces that may be ATK mice.
+#[pyfunction]
+fn discover(py: Python<'_>) -> PyResult<Vec<PyDeviceInfo>> {
+    let devices = run(py, || Ok(discovery::discover()?))?;
+    Ok(devices.into_iter().map(Into::into).collect())
+}
+
+/// Talks to a single mouse and caches its configuration.
+///
+/// Reading a setting returns the cached value, assigning one writes it to the mouse.
+#[pyclass(name = "MouseManager", module = "atk_hub", frozen)]
+pub struct PyMouseManager {
+    inner: MouseManager,
+}
+
+impl PyMouseManager {
+    fn with_profile<U>(&self, func: impl FnOnce(&Profile) -> U) -> U {
+        func(&self.inner.profile())
+    }
+}
+
+#[pymethods]
+impl PyMouseManager {
+    /// Opens the HID interface with the given identifiers and reads its configuration.
+    #[staticmethod]
+    fn open(
+        py: Python<'_>,
+        vendor_id: u16,
+        product_id: u16,
+        usage_page: u16,
+        usage: u16,
+    ) -> PyResult<Self> {
+        let inner = run(py, || {
+            MouseManager::new(Device::new(vendor_id, product_id, usage_page, usage)?)
+        })?;
+        Ok(PyMouseManager { inner })
+    }
+
+    /// Answers commands from a trace recorded with `--trace`.
+    #[staticmethod]
+    fn replay(py: Python<'_>, path: std::path::PathBuf) -> PyResult<Self> {
+        let inner = run(py, || MouseManager::new(Replay::open(path)?))?;
+        Ok(PyMouseManager { inner })
+    }
+
+    /// A mouse living in memory, for trying out scripts without hardware.
+    #[staticmethod]
+    fn simulated(py: Python<'_>) -> PyResult<Self> {
+        let inner = run(py, || MouseManager::new(SimulatedMouse::default()))?;
+        Ok(PyMouseManager { inner })
+    }
+
+    /// Reads the configuration from the mouse again.
+    fn load_profile(&self, py: Python<'_>) -> PyResult<()> {
+        run(py, || self.inner.load_profile())
+    }
+
+    /// A snapshot of the cached configuration.
+    #[getter]
+    fn profile(&self) -> PyProfile {
+        PyProfile {
+            inner: self.inner.profile().clone(),
+        }
+    }
+
+    /// Writes every setting of `profile` in one transaction and returns the names of the
+    /// blocks written. On failure the blocks already written are rolled back.
+    fn apply(&self, py: Python<'_>, profile: &PyProfile) -> PyResult<Vec<String>> {
+        let profile = &profile.inner;
+        run(py, || {
+            let mut transaction = self
+                .inner
+                .transaction()
+                .mouse_performance_settings(
+                    (&PyMousePerformance::from(profile.mouse_performance_settings())).into(),
+                )
+                .sensor_performance_settings(
+                    (&PySensorPerformance::from(profile.sensor_performance_settings())).into(),
+                )
+                .dpi_led_settings((&PyLedSettings::from(profile.dpi_led_settings())).into())
+                .far_distance_mode(profile.far_distance_mode().far_distance_mode())
+                .silent_height(profile.silent_height().silent_height());
+            for preset in PRESETS {
+                let gear = gear(profile, preset);
+                transaction = transaction
+                    .dpi(preset, gear.dpi())
+                    .color(preset, gear.color());
+            }
+
+            let blocks = transaction.commit()?;
+            Ok(blocks.iter().map(ToString::to_string).collect())
+        })
+    }
+
+    /// Queries the mouse.
+    #[getter]
+    fn battery(&self, py: Python<'_>) -> PyResult<PyBatteryStatus> {
+        let battery = run(py, || self.inner.battery_level())?;
+        Ok(PyBatteryStatus {
+            level: battery.level(),
+            charge: battery.charge(),
+            voltage: battery.voltage(),
+        })
+    }
+
+    /// Queries the mouse.
+    #[getter]
+    fn connection_type(&self, py: Python<'_>) -> PyResult<ConnectionType> {
+        run(py, || self.inner.connection_type())
+    }
+
+    fn set_dpi(&self, py: Python<'_>, preset: Preset, dpi: DpiArg) -> PyResult<()> {
+        let dpi = dpi.dpi()?;
+        run(py, || self.inner.set_dpi_profile_dpi(preset, dpi))
+    }
+
+    /// The manager's colour correction is applied before writing.
+    fn set_color(&self, py: Python<'_>, preset: Preset, color: ColorArg) -> PyResult<()> {
+        let color = color.color()?;
+        run(py, || self.inner.set_dpi_profile_color(preset, color))
+    }
+
+    #[getter]
+    fn led(&self) -> PyLedSettings {
+        self.with_profile(|profile| profile.dpi_led_settings().into())
+    }
+
+    #[setter]
+    fn set_led(&self, py: Python<'_>, settings: PyLedSettings) -> PyResult<()> {
+        run(py, || self.inner.set_dpi_led_settings((&settings).into()))
+    }
+
+    #[getter]
+    fn mouse_performance(&self) -> PyMousePerformance {
+        self.with_profile(|profile| profile.mouse_performance_settings().into())
+    }
+
+    #[setter]
+    fn set_mouse_performance(&self, py: Python<'_>, settings: PyMousePerformance) -> PyResult<()> {
+        run(py, || {
+            self.inner
+                .set_mouse_performance_settings((&settings).into())
+        })
+    }
+
+    #[getter]
+    fn sensor_performance(&self) -> PySensorPerformance {
+        self.with_profile(|profile| profile.sensor_performance_settings().into())
+    }
+
+    #[setter]
+    fn set_sensor_performance(
+        &self,
+        py: Python<'_>,
+        settings: PySensorPerformance,
+    ) -> PyResult<()> {
+        run(py, || {
+            self.inner
+                .set_sensor_performance_settings((&settings).into())
+        })
+    }
+
+    #[getter]
+    fn far_distance_mode(&self) -> bool {
+        self.with_profile(|profile| profile.far_distance_mode().far_distance_mode())
+    }
+
+    #[setter]
+    fn set_far_distance_mode(&self, py: Python<'_>, enabled: bool) -> PyResult<()> {
+        run(py, || self.inner.set_far_distance_mode(enabled))
+    }
+
+    #[getter]
+    fn silent_height(&self) -> SilentHeightMode {
+        self.with_profile(|profile| profile.silent_height().silent_height())
+    }
+
+    #[setter]
+    fn set_silent_height(&self, py: Python<'_>, height: SilentHeightMode) -> PyResult<()> {
+        run(py, || self.inner.set_silent_height(height))
+    }
+}
+
+#[pymodule]
+fn atk_hub(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = module.py();
+
+    module.add_class::<PyMouseManager>()?;
+    module.add_class::<PyProfile>()?;
+    module.add_class::<PyDpi>()?;
+    module.add_class::<PyColor>()?;
+    module.add_class::<PyLedSettings>()?;
+    module.add_class::<PyMousePerformance>()?;
+    module.add_class::<PySensorPerformance>()?;
+    module.add_class::<PyBatteryStatus>()?;
+    module.add_class::<PyDeviceInfo>()?;
+    module.add_class::<LedEffectMode>()?;
+    module.add_class::<LedBrightnessLevel>()?;
+    module.add_class::<LedBreathingRate>()?;
+    module.add_class::<PollingRate>()?;
+    module.add_class::<SilentHeightMode>()?;
+    module.add_class::<ConnectionType>()?;
+    module.add_class::<Preset>()?;
+    module.add_function(wrap_pyfunction!(discover, module)?)?;
+
+    module.add("AtkError", py.get_type::<exceptions::AtkError>())?;
+    module.add("DeviceError", py.get_type::<exceptions::DeviceError>())?;
+    module.add("ProtocolError", py.get_type::<exceptions::ProtocolError>())?;
+    module.add(
+        "InvalidValueError",
+        py.get_type::<exceptions::InvalidValueError>(),
+    )?;
+    module.add(
+        "VerificationError",
+        py.get_type::<exceptions::VerificationError>(),
+    )?;
+    module.add("ReplayError", py.get_type::<exceptions::ReplayError>())?;
+    module.add(
+        "TransactionError",
+        py.get_type::<exceptions::TransactionError>(),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::{PyDict, PyModule};
+    use std::{ffi::CString, path::PathBuf};
+
+    /// Runs every `test_*` function of `tests/python/test_atk_hub.py`.
+    #[test]
+    fn python_tests() {
+        pyo3::prepare_freethreaded_python();
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/python/test_atk_hub.py");
+        let source = CString::new(std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "atk_hub").unwrap();
+            atk_hub(&module).unwrap();
+            py.import("sys")
+                .unwrap()
+                .getattr("modules")
+                .unwrap()
+                .set_item("atk_hub", module)
+                .unwrap();
+
+            let globals = PyDict::new(py);
+            py.run(&source, Some(&globals), None)
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+
+            let mut count = 0;
+            for (name, test) in globals.iter() {
+                let name: String = name.extract().unwrap();
+                if name.starts_with("test_") {
+                    test.call0().unwrap_or_else(|e| {
+                        e.print(py);
+                        panic!("{} failed: {}", name, e)
+                    });
+                    count += 1;
+                }
+            }
+            assert!(count > 0);
+        });
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{commands::prelude::*, transport::parse_command};
+use libatk_rs::prelude::*;
+
+/// Decodes a raw 16 byte frame into the typed value it carries, formatted with `Debug`.
+///
+/// `response` selects how the frame is read: queries carry no meaningful data, so only
+/// responses and `Set*` requests are decoded. Returns `None` for frames without a known
+/// layout and for data that fails its checksums or holds unknown values.
+pub fn decode(frame: &[u8], response: bool) -> Option<String> {
+    let id = CommandId::try_from(*frame.first()?).ok()?;
+
+    match id {
+        CommandId::SetEEPROM => decode_eeprom(frame),
+        CommandId::GetEEPROM if response => decode_eeprom(frame),
+        // Requests carry the mode past their data length of 0.
+        CommandId::SetFarDistanceMode if !response => Some(format!(
+            "{:?}",
+            FarDistanceMode::from(*frame.get(5)? == 0x1)
+        )),
+        CommandId::SetFarDistanceMode => {
+            typed(frame, 1, |c: Command<FarDistanceMode>| Ok(c.config()))
+        }
+        CommandId::GetFarDistanceMode if response => {
+            typed(frame, 1, |c: Command<FarDistanceMode>| Ok(c.config()))
+        }
+        CommandId::GetBatteryLevel if response => {
+            typed(frame, 2, |c: Command<GetBatteryStatus>| Ok(c.config()))
+        }
+        CommandId::GetWirelessMouseOnline if response => {
+            typed(frame, 1, |c: Command<GetWirelessMouseOnline>| {
+                Ok(c.mouse_status())
+            })
+        }
+        CommandId::GetMouseCIDMID if response => {
+            typed(frame, 2, |c: Command<GetMouseCidMid>| Ok(c.config()))
+        }
+        CommandId::GetMouseVersion if response => {
+            typed(frame, 2, |c: Command<GetMouseVersion>| Ok(c.config()))
+        }
+        CommandId::DownLoadData if response => {
+            typed(frame, 7, |c: Command<DownloadData>| Ok(c.config()))
+        }
+        _ => None,
+    }
+}
+
+fn decode_eeprom(frame: &[u8]) -> Option<String> {
+    let address = u16::from_be_bytes([*frame.get(2)?, *frame.get(3)?]);
+
+    match EEPROMAddress::try_from(address).ok()? {
+        EEPROMAddress::ReportRate => typed(frame, 6, |c: Command<MouseInfo>| c.try_config()),
+        EEPROMAddress::SilentHeight => typed(frame, 2, |c: Command<SilentHeight>| c.try_config()),
+        EEPROMAddress::DpiPair1
+        | EEPROMAddress::DpiPair3
+        | EEPROMAddress::DpiPair5
+        | EEPROMAddress::DpiPair7 => typed(frame, 8, |c: Command<DpiPairSetting>| c.try_config()),
+        EEPROMAddress::DpiPair1Color
+        | EEPROMAddress::DpiPair3Color
+        | EEPROMAddress::DpiPair5Color
+        | EEPROMAddress::DpiPair7Color => {
+            typed(frame, 8, |c: Command<ColorPairSetting>| c.try_config())
+        }
+        EEPROMAddress::DpiRgbLightingEffects => {
+            typed(frame, 8, |c: Command<DpiLedSettings>| c.try_config())
+        }
+        EEPROMAddress::StabilizationTime => {
+            typed(frame, 10, |c: Command<MousePerfSettings>| c.try_config())
+        }
+        EEPROMAddress::MoveCloseLights => {
+            typed(frame, 10, |c: Command<SensorPerfSettings>| c.try_config())
+        }
+        _ => None,
+    }
+}
+
+/// Parses `frame` as a `Command<T>` and formats the value extracted by `config`.
+///
+/// The typed parsers index into the data, so frames shorter than `min_len` are skipped.
+fn typed<T: CommandDescriptor, V: std::fmt::Debug>(
+    frame: &[u8],
+    min_len: usize,
+    config: impl FnOnce(Command<T>) -> Result<V, Error>,
+) -> Option<String> {
+    let command = parse_command::<T>(frame).ok()?;
+    if command.data().len() < min_len {
+        return None;
+    }
+
+    config(command).ok().map(|value| format!("{:?}", value))
+}
@@ -0,0 +1,401 @@
+pub mod decode;
+pub mod replay;
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Read, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::Instant,
+};
+
+use libatk_rs::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Written once at the start of a binary trace, followed by the format version.
+pub const BINARY_MAGIC: &[u8; 8] = b"ATKTRACE";
+
+/// Version of the binary trace format, bumped on incompatible changes.
+pub const BINARY_FORMAT: u8 = 1;
+
+/// Length of a command frame, without the report ID.
+pub const FRAME_LEN: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceFormat {
+    /// One JSON object per line, with the decoded value of every frame.
+    JsonLines,
+    /// Fixed size records holding only the raw frames, see [`TraceRecord::write_binary`].
+    Binary,
+}
+
+impl std::str::FromStr for TraceFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "json" | "jsonl" | "json-lines" => Ok(TraceFormat::JsonLines),
+            "bin" | "binary" => Ok(TraceFormat::Binary),
+            _ => Err(Error::ParseError(format!(
+                "TraceFormat: Expected json or binary, got {}",
+                s
+            ))),
+        }
+    }
+}
+
+/// A single command sent to the device and what came back.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    /// Microseconds since the trace was started.
+    pub timestamp_us: u64,
+    pub command: String,
+    pub command_id: u8,
+    pub address: u16,
+    pub data_len: u8,
+    pub request: String,
+    pub response: Option<String>,
+    /// The typed value carried by the request (for writes) or the response.
+    pub decoded: Option<String>,
+    pub error: Option<String>,
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl TraceRecord {
+    pub fn new(
+        timestamp_us: u64,
+        request: &[u8],
+        response: Result<&[u8], &dyn std::error::Error>,
+    ) -> Self {
+        let byte = |index: usize| request.get(index).copied().unwrap_or_default();
+        let command_id = byte(0);
+
+        let decoded = match response {
+            Ok(response) => decode::decode(response, true),
+            Err(_) => None,
+        }
+        .or_else(|| decode::decode(request, false));
+
+        TraceRecord {
+            timestamp_us,
+            command: CommandId::try_from(command_id)
+                .map(|id| format!("{:?}", id))
+                .unwrap_or_else(|_| format!("{:#04x}", command_id)),
+            command_id,
+            address: u16::from_be_bytes([byte(2), byte(3)]),
+            data_len: byte(4),
+            request: to_hex(request),
+            response: response.ok().map(to_hex),
+            decoded,
+            error: response.err().map(ToString::to_string),
+        }
+    }
+
+    /// Writes the record as `timestamp_us: u64 LE`, `flags: u8`, the request frame and,
+    /// depending on the flags, the response frame or the error message as
+    /// `len: u16 LE` followed by UTF-8.
+    ///
+    /// Flags: `0x1` response present, `0x2` error present. Decoded values are not stored,
+    /// they can be recomputed from the frames.
+    pub fn write_binary(&self, output: &mut impl Write) -> std::io::Result<()> {
+        let invalid =
+            |e: Error| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string());
+
+        let mut flags = 0u8;
+        if self.response.is_some() {
+            flags |= 0x1;
+        }
+        if self.error.is_some() {
+            flags |= 0x2;
+        }
+
+        output.write_all(&self.timestamp_us.to_le_bytes())?;
+        output.write_all(&[flags])?;
+        output.write_all(&frame(&self.request).map_err(invalid)?)?;
+        if let Some(response) = &self.response {
+            output.write_all(&frame(response).map_err(invalid)?)?;
+        }
+        if let Some(error) = &self.error {
+            let len = error.len().min(u16::MAX as usize);
+            output.write_all(&(len as u16).to_le_bytes())?;
+            output.write_all(&error.as_bytes()[..len])?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a record written by [`TraceRecord::write_binary`], or `None` at the end of
+    /// the input.
+    pub fn read_binary(input: &mut impl Read) -> std::io::Result<Option<Self>> {
+        let mut timestamp = [0u8; 8];
+        match input.read_exact(&mut timestamp) {
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+
+        let mut flags = [0u8; 1];
+        input.read_exact(&mut flags)?;
+
+        let mut request = [0u8; FRAME_LEN];
+        input.read_exact(&mut request)?;
+
+        let mut response = [0u8; FRAME_LEN];
+        let response = match flags[0] & 0x1 {
+            0 => None,
+            _ => input
+                .read_exact(&mut response)
+                .map(|_| &response[..])
+                .map(Some)?,
+        };
+
+        let error = match flags[0] & 0x2 {
+            0 => None,
+            _ => {
+                let mut len = [0u8; 2];
+                input.read_exact(&mut len)?;
+                let mut message = vec![0u8; u16::from_le_bytes(len) as usize];
+                input.read_exact(&mut message)?;
+                Some(Error::ParseError(
+                    String::from_utf8_lossy(&message).into_owned(),
+                ))
+            }
+        };
+
+        let timestamp_us = u64::from_le_bytes(timestamp);
+        let mut record = match (response, &error) {
+            (Some(response), _) => TraceRecord::new(timestamp_us, &request, Ok(response)),
+            (None, Some(error)) => TraceRecord::new(timestamp_us, &request, Err(error)),
+            (None, None) => TraceRecord::new(
+                timestamp_us,
+                &request,
+                Err(&Error::ParseError(String::new())),
+            ),
+        };
+        record.error = error.map(|error| error.to_string());
+
+        Ok(Some(record))
+    }
+
+    pub fn request_frame(&self) -> Result<[u8; FRAME_LEN], Error> {
+        frame(&self.request)
+    }
+
+    pub fn response_frame(&self) -> Result<Option<[u8; FRAME_LEN]>, Error> {
+        self.response.as_deref().map(frame).transpose()
+    }
+}
+
+/// Reads a trace written by [`Tracer`] in either format.
+pub fn read(
+    path: impl AsRef<Path>,
+) -> Result<Vec<TraceRecord>, Box<dyn std::error::Error + Send + Sync>> {
+    let mut input = BufReader::new(File::open(path)?);
+
+    if input.fill_buf()?.starts_with(BINARY_MAGIC) {
+        let mut header = [0u8; BINARY_MAGIC.len() + 1];
+        input.read_exact(&mut header)?;
+        if header[BINARY_MAGIC.len()] != BINARY_FORMAT {
+            return Err(Error::ParseError(format!(
+                "Trace: Unsupported binary format {}",
+                header[BINARY_MAGIC.len()]
+            ))
+            .into());
+        }
+
+        let mut records = Vec::new();
+        while let Some(record) = TraceRecord::read_binary(&mut input)? {
+            records.push(record);
+        }
+        return Ok(records);
+    }
+
+    let mut records = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        if !line.trim().is_empty() {
+            records.push(serde_json::from_str(&line)?);
+        }
+    }
+
+    Ok(records)
+}
+
+/// Parses a hex frame back into its fixed size form, padding short frames with zeroes.
+pub(crate) fn frame(hex: &str) -> Result<[u8; FRAME_LEN], Error> {
+    let mut frame = [0u8; FRAME_LEN];
+    for (index, byte) in hex.split_whitespace().enumerate() {
+        let slot = frame
+            .get_mut(index)
+            .ok_or_else(|| Error::ParseError(format!("Trace: Frame is too long: {}", hex)))?;
+        *slot = u8::from_str_radix(byte, 16)
+            .map_err(|_| Error::ParseError(format!("Trace: Invalid byte {:?}", byte)))?;
+    }
+
+    Ok(frame)
+}
+
+type FailureHandler = Box<dyn Fn(&std::io::Error) + Send + Sync>;
+
+/// Writes a [`TraceRecord`] for every command executed by a
+/// [`MouseManager`](crate::manager::MouseManager).
+///
+/// Records are flushed as they are written, so the trace survives a crash. A record that
+/// can not be written does not fail the command, see [`Tracer::failure`].
+pub struct Tracer {
+    output: Mutex<Box<dyn Write + Send>>,
+    format: TraceFormat,
+    start: Instant,
+    /// The first write that failed, later records are still attempted.
+    failure: Mutex<Option<String>>,
+    dropped: AtomicU64,
+    on_failure: Option<FailureHandler>,
+}
+
+impl Tracer {
+    pub fn new(mut output: Box<dyn Write + Send>, format: TraceFormat) -> std::io::Result<Self> {
+        if format == TraceFormat::Binary {
+            output.write_all(BINARY_MAGIC)?;
+            output.write_all(&[BINARY_FORMAT])?;
+            output.flush()?;
+        }
+
+        Ok(Tracer {
+            output: Mutex::new(output),
+            format,
+            start: Instant::now(),
+            failure: Mutex::new(None),
+            dropped: AtomicU64::new(0),
+            on_failure: None,
+        })
+    }
+
+    /// Calls `handler` with the first record write that fails, e.g. to warn the user.
+    pub fn on_failure(mut self, handler: impl Fn(&std::io::Error) + Send + Sync + 'static) -> Self {
+        self.on_failure = Some(Box::new(handler));
+        self
+    }
+
+    /// The first failed write and the number of records dropped in total.
+    pub fn failure(&self) -> Option<(String, u64)> {
+        let failure = self.failure.lock().unwrap_or_else(PoisonError::into_inner);
+        failure
+            .clone()
+            .map(|message| (message, self.dropped.load(Ordering::SeqCst)))
+    }
+
+    /// Like [`record`](Self::record), but keeps a failure for [`failure`](Self::failure)
+    /// instead of returning it, so a broken trace does not fail the traced command.
+    pub fn record_or_keep(&self, request: &[u8], response: Result<&[u8], &dyn std::error::Error>) {
+        let Err(e) = self.record(request, response) else {
+            return;
+        };
+
+        if self.dropped.fetch_add(1, Ordering::SeqCst) == 0 {
+            *self.failure.lock().unwrap_or_else(PoisonError::into_inner) = Some(e.to_string());
+            if let Some(handler) = &self.on_failure {
+                handler(&e);
+            }
+        }
+    }
+
+    pub fn create(path: impl AsRef<Path>, format: TraceFormat) -> std::io::Result<Self> {
+        Self::new(Box::new(BufWriter::new(File::create(path)?)), format)
+    }
+
+    pub fn record(
+        &self,
+        request: &[u8],
+        response: Result<&[u8], &dyn std::error::Error>,
+    ) -> std::io::Result<TraceRecord> {
+        let timestamp_us = self.start.elapsed().as_micros() as u64;
+        let record = TraceRecord::new(timestamp_us, request, response);
+
+        let mut output = self.output.lock().unwrap_or_else(PoisonError::into_inner);
+        match self.format {
+            TraceFormat::JsonLines => {
+                serde_json::to_writer(&mut *output, &record)?;
+                output.write_all(b"\n")?;
+            }
+            TraceFormat::Binary => record.write_binary(&mut *output)?,
+        }
+        output.flush()?;
+
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::prelude::*, manager::MouseManager, transport::simulated::SimulatedMouse,
+    };
+    use std::sync::{atomic::AtomicUsize, Arc};
+
+    /// A trace file on a full disk.
+    struct Full;
+
+    impl Write for Full {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("No space left on device"))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_trace_writes_do_not_fail_commands() {
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&warnings);
+        let tracer = Tracer::new(Box::new(Full), TraceFormat::JsonLines)
+            .unwrap()
+            .on_failure(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+        let manager =
+            MouseManager::new_without_profile(SimulatedMouse::default()).with_tracer(tracer);
+        assert_eq!(manager.trace_failure(), None);
+
+        manager.load_profile().unwrap();
+        let mode = manager
+            .execute(Command::<FarDistanceMode>::query())
+            .unwrap()
+            .config();
+        assert!(!mode.far_distance_mode());
+
+        let (message, dropped) = manager.trace_failure().unwrap();
+        assert_eq!(message, "No space left on device");
+        assert!(dropped > 1);
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn records_round_trip_through_both_formats() {
+        let request = Command::<FarDistanceMode>::query().as_bytes();
+        let mut response = request.clone();
+        response[5] = 0x1;
+
+        let record = TraceRecord::new(42, &request, Ok(&response));
+        assert_eq!(record.command, "GetFarDistanceMode");
+
+        let mut binary = Vec::new();
+        record.write_binary(&mut binary).unwrap();
+        let read = TraceRecord::read_binary(&mut binary.as_slice()).unwrap();
+        assert_eq!(read, Some(record.clone()));
+
+        let json = serde_json::to_string(&record).unwrap();
+        assert_eq!(serde_json::from_str::<TraceRecord>(&json).unwrap(), record);
+    }
+}
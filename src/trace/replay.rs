@@ -0,0 +1,294 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::{to_hex, TraceRecord};
+use crate::transport::Transport;
+
+/// Returned when a request does not match the recorded session.
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The request differs from the one recorded at `position`.
+    Mismatch {
+        position: usize,
+        expected: Vec<u8>,
+        actual: Vec<u8>,
+        /// Where the request was recorded instead, if it appears later in the trace.
+        recorded_at: Option<usize>,
+    },
+    /// Every recorded exchange has already been replayed.
+    Exhausted { position: usize, actual: Vec<u8> },
+    /// The session ended with exchanges that were never requested.
+    Unplayed { position: usize, remaining: usize },
+    /// The recorded exchange failed on the real device.
+    Recorded { position: usize, error: String },
+}
+
+/// Marks the bytes that differ between `expected` and `actual`.
+fn markers(expected: &[u8], actual: &[u8]) -> String {
+    (0..expected.len().max(actual.len()))
+        .map(|index| match expected.get(index) == actual.get(index) {
+            true => "  ",
+            false => "^^",
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end()
+        .to_string()
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::Mismatch {
+                position,
+                expected,
+                actual,
+                recorded_at,
+            } => {
+                writeln!(f, "Request #{} does not match the trace", position)?;
+                writeln!(f, "  expected: {}", to_hex(expected))?;
+                writeln!(f, "  actual:   {}", to_hex(actual))?;
+                write!(f, "            {}", markers(expected, actual))?;
+                if let Some(recorded_at) = recorded_at {
+                    write!(
+                        f,
+                        "\nThe request was recorded later, at #{}: requests are out of order",
+                        recorded_at
+                    )?;
+                }
+                Ok(())
+            }
+            ReplayError::Exhausted { position, actual } => write!(
+                f,
+                "Request #{} is past the end of the trace: {}",
+                position,
+                to_hex(actual)
+            ),
+            ReplayError::Unplayed {
+                position,
+                remaining,
+            } => write!(
+                f,
+                "{} recorded exchanges were never requested, starting at #{}",
+                remaining, position
+            ),
+            ReplayError::Recorded { position, error } => {
+                write!(f, "Recorded exchange #{} failed: {}", position, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Replays a recorded session in place of a device.
+///
+/// Requests must match the trace byte for byte and in order, each one is answered with
+/// the recorded response.
+pub struct Replay {
+    records: Vec<TraceRecord>,
+    position: AtomicUsize,
+}
+
+impl Replay {
+    pub fn new(records: Vec<TraceRecord>) -> Self {
+        Replay {
+            records,
+            position: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(Self::new(super::read(path)?))
+    }
+
+    /// Number of exchanges that have not been replayed yet.
+    pub fn remaining(&self) -> usize {
+        self.records.len() - self.position.load(Ordering::SeqCst)
+    }
+
+    /// Fails if the session stopped before the end of the trace.
+    pub fn finish(&self) -> Result<(), ReplayError> {
+        match self.remaining() {
+            0 => Ok(()),
+            remaining => Err(ReplayError::Unplayed {
+                position: self.position.load(Ordering::SeqCst),
+                remaining,
+            }),
+        }
+    }
+}
+
+impl Transport for Replay {
+    fn exchange(
+        &self,
+        request: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let position = self.position.load(Ordering::SeqCst);
+        let Some(record) = self.records.get(position) else {
+            return Err(ReplayError::Exhausted {
+                position,
+                actual: request.to_vec(),
+            }
+            .into());
+        };
+
+        let expected = record.request_frame()?;
+        if expected[..] != *request {
+            let recorded_at = self.records[position..]
+                .iter()
+                .position(|record| {
+                    record
+                        .request_frame()
+                        .is_ok_and(|frame| frame[..] == *request)
+                })
+                .map(|offset| position + offset);
+
+            return Err(ReplayError::Mismatch {
+                position,
+                expected: expected.to_vec(),
+                actual: request.to_vec(),
+                recorded_at,
+            }
+            .into());
+        }
+
+        self.position.store(position + 1, Ordering::SeqCst);
+
+        match (record.response_frame()?, &record.error) {
+            (Some(response), _) => Ok(response.to_vec()),
+            (None, error) => Err(ReplayError::Recorded {
+                position,
+                error: error.clone().unwrap_or_default(),
+            }
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use super::*;
+    use crate::{color::Color, commands::prelude::*, manager::MouseManager};
+    use libatk_rs::prelude::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/traces")
+            .join(name)
+    }
+
+    fn replay(name: &str) -> Arc<Replay> {
+        Arc::new(Replay::open(fixture(name)).expect("fixture should parse"))
+    }
+
+    #[test]
+    fn replays_load_profile() {
+        let fixtures: Vec<PathBuf> = std::fs::read_dir(fixture(""))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.to_string_lossy().ends_with("-load-profile.jsonl"))
+            .collect();
+        assert!(!fixtures.is_empty());
+
+        for path in fixtures {
+            let replay = Arc::new(Replay::open(&path).unwrap());
+            MouseManager::new(Arc::clone(&replay))
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            replay
+                .finish()
+                .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        }
+    }
+
+    #[test]
+    fn replays_setters() {
+        let replay = replay("synthetic-setters.jsonl");
+        let manager = MouseManager::new(Arc::clone(&replay)).unwrap();
+
+        manager
+            .set_dpi_profile_dpi(Preset::Preset3, Dpi::new(800))
+            .unwrap();
+        manager
+            .set_dpi_profile_color(Preset::Preset3, Color::new(0xff, 0, 0))
+            .unwrap();
+        manager.set_far_distance_mode(true).unwrap();
+        replay.finish().unwrap();
+
+        let profile = manager.profile();
+        assert_eq!(
+            profile.dpi_profile(Pair::Pair2).0.to_string(),
+            "DPI: 800 | Color: #ff0000"
+        );
+        assert!(profile.far_distance_mode().far_distance_mode());
+    }
+
+    #[test]
+    fn mismatch_shows_byte_diff() {
+        let manager = MouseManager::new_without_profile(replay("synthetic-load-profile.jsonl"));
+
+        let error = manager
+            .execute(Command::<GetBatteryStatus>::query())
+            .unwrap_err();
+        let error = error.downcast_ref::<ReplayError>().unwrap();
+
+        assert!(matches!(
+            error,
+            ReplayError::Mismatch {
+                position: 0,
+                recorded_at: None,
+                ..
+            }
+        ));
+        let message = error.to_string();
+        assert!(message.contains("expected: 03 00"), "{}", message);
+        assert!(message.contains("actual:   04 00"), "{}", message);
+        assert!(message.contains("^^"), "{}", message);
+    }
+
+    #[test]
+    fn out_of_order_request_points_at_recording() {
+        let manager = MouseManager::new_without_profile(replay("synthetic-load-profile.jsonl"));
+
+        let error = manager
+            .execute(Command::<DpiLedSettings>::query())
+            .unwrap_err();
+
+        match error.downcast_ref::<ReplayError>() {
+            Some(ReplayError::Mismatch {
+                recorded_at: Some(position),
+                ..
+            }) => assert!(*position > 0),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn unplayed_exchanges_fail_finish() {
+        let replay = replay("synthetic-setters.jsonl");
+        MouseManager::new(Arc::clone(&replay)).unwrap();
+
+        assert!(matches!(
+            replay.finish(),
+            Err(ReplayError::Unplayed { remaining, .. }) if remaining > 0
+        ));
+    }
+
+    #[test]
+    fn exhausted_trace_fails() {
+        let replay = replay("synthetic-load-profile.jsonl");
+        let manager = MouseManager::new(Arc::clone(&replay)).unwrap();
+
+        let error = manager
+            .execute(Command::<GetWirelessMouseOnline>::query())
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ReplayError>(),
+            Some(ReplayError::Exhausted { .. })
+        ));
+    }
+}
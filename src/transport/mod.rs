@@ -0,0 +1,100 @@
+pub mod simulated;
+
+use libatk_rs::prelude::*;
+
+/// Moves raw command frames to a device and back.
+///
+/// Frames are the 16 bytes produced by [`Command::as_bytes`], without the report ID.
+///
+/// Transports only need to be `Send`, the [`MouseManager`](crate::manager::MouseManager)
+/// serializes access to them.
+pub trait Transport: Send {
+    /// Sends `request` and returns the device's response frame.
+    fn exchange(&self, request: &[u8])
+        -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Descriptor for frames whose command type is not known, e.g. when forwarding raw bytes.
+pub struct Frame;
+
+impl CommandDescriptor for Frame {}
+
+/// Parses `request` into the command it was serialized from.
+///
+/// [`Command::try_from`] keeps only the first `data_len` data bytes, but some commands carry
+/// data past their length, e.g. the mode of `SetFarDistanceMode`. Those bytes are restored
+/// so the command serializes to exactly `request` again.
+pub fn parse_frame(
+    request: &[u8],
+) -> Result<Command<Frame>, Box<dyn std::error::Error + Send + Sync>> {
+    let parsed = parse_command::<Frame>(request)?;
+
+    let mut command = Command::<Frame>::default();
+    command.set_id(parsed.id());
+    command.set_eeprom_address(parsed.eeprom_address());
+    command.set_data_len(DATA_LEN)?;
+    command.set_data(&request[DATA_OFFSET..DATA_OFFSET + DATA_LEN], 0x0)?;
+    command.set_data_len(parsed.data_len())?;
+
+    if command.as_bytes() != request {
+        return Err(format!("Frame can not be sent as is: {:02x?}", request).into());
+    }
+
+    Ok(command)
+}
+
+/// Like [`Command::try_from`], but rejects a data length past the data instead of panicking.
+pub fn parse_command<T: CommandDescriptor>(frame: &[u8]) -> Result<Command<T>, Error> {
+    match frame.get(DATA_OFFSET - 1) {
+        Some(&len) if len as usize > DATA_LEN => Err(Error::ParseError(format!(
+            "Frame: Data length {} exceeds {}: {:02x?}",
+            len, DATA_LEN, frame
+        ))),
+        _ => Command::try_from(frame),
+    }
+}
+
+/// Offset of the data in a frame.
+const DATA_OFFSET: usize = 0x5;
+
+/// Data bytes in a frame, whatever its data length says.
+pub(crate) const DATA_LEN: usize = 0xa;
+
+impl Transport for Device {
+    fn exchange(
+        &self,
+        request: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        self.send(&parse_frame(request)?)?;
+
+        Ok(self.read()?)
+    }
+}
+
+impl<T: Transport + Sync + ?Sized> Transport for std::sync::Arc<T> {
+    fn exchange(
+        &self,
+        request: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        (**self).exchange(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::prelude::*;
+
+    #[test]
+    fn frames_keep_data_past_their_length() {
+        let request = Command::<FarDistanceMode>::builder()
+            .far_distance_mode(true)
+            .build()
+            .as_bytes();
+        assert_eq!(parse_frame(&request).unwrap().as_bytes(), request);
+
+        let mut status = request.clone();
+        status[0x1] = 0x1;
+        assert!(parse_frame(&status).is_err());
+    }
+}
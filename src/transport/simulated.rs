@@ -0,0 +1,203 @@
+use std::sync::{Mutex, PoisonError};
+
+use super::{parse_frame, Frame, Transport};
+use crate::{
+    color::Color,
+    commands::prelude::*,
+    eeprom::{EEPROM_END, MAX_CHUNK_LEN},
+};
+use libatk_rs::prelude::*;
+
+/// Seconds a simulated pairing takes, counted down by each status query.
+const PAIRING_TIME: u8 = 3;
+
+/// DPI of each preset in a fresh simulated mouse.
+const DEFAULT_DPI: [u16; 8] = [400, 800, 1600, 3200, 4800, 6400, 12800, 26000];
+
+/// The device state a [`SimulatedMouse`] answers from.
+#[derive(Debug, Clone)]
+pub struct SimulatedState {
+    pub eeprom: Vec<u8>,
+    pub online: bool,
+    pub battery_level: u8,
+    pub battery_charge: u8,
+    pub connection: ConnectionType,
+    pub far_distance: bool,
+    pub cid: u8,
+    pub mid: u8,
+    pub firmware: (u8, u8),
+    /// Pairing status and seconds left, `None` while not pairing.
+    pub pairing: Option<(u8, u8)>,
+}
+
+impl Default for SimulatedState {
+    fn default() -> Self {
+        let mut state = SimulatedState {
+            eeprom: vec![0xff; EEPROM_END as usize],
+            online: true,
+            battery_level: 100,
+            battery_charge: 0,
+            connection: ConnectionType::Dongle1K,
+            far_distance: false,
+            cid: 0x03,
+            mid: 0x11,
+            firmware: (0x01, 0x07),
+            pairing: None,
+        };
+
+        let defaults = "the default timings are in range";
+        state.store(
+            MousePerfSettings::default()
+                .builder()
+                .expect(defaults)
+                .build(),
+        );
+        state.store(
+            SensorPerfSettings::default()
+                .builder()
+                .expect(defaults)
+                .build(),
+        );
+        state.store(DpiLedSettings::default().builder().build());
+        state.store(SilentHeight::default().builder().build());
+        state.store(
+            Command::<MouseInfo>::builder()
+                .poll_rate(PollingRate::Hz1000)
+                .num_profile(DEFAULT_DPI.len() as u8 - 1)
+                .active_profile(0)
+                .build(),
+        );
+
+        let colors = Color::distinct(DEFAULT_DPI.len());
+        for (index, pair) in [Pair::Pair1, Pair::Pair2, Pair::Pair3, Pair::Pair4]
+            .into_iter()
+            .enumerate()
+        {
+            state.store(
+                Command::<DpiPairSetting>::builder(pair)
+                    .dpi(Dpi::new(DEFAULT_DPI[index * 2]), Slot::First)
+                    .dpi(Dpi::new(DEFAULT_DPI[index * 2 + 1]), Slot::Second)
+                    .build(),
+            );
+            state.store(
+                Command::<ColorPairSetting>::builder(pair)
+                    .color(colors[index * 2], Slot::First)
+                    .color(colors[index * 2 + 1], Slot::Second)
+                    .build(),
+            );
+        }
+
+        state
+    }
+}
+
+impl SimulatedState {
+    /// Writes the data of a `SetEEPROM` command to the EEPROM image.
+    fn store<T: CommandDescriptor>(&mut self, command: Command<T>) {
+        let start = command.eeprom_address() as usize;
+        let data = &command.data()[..command.data_len()];
+        self.eeprom[start..start + data.len()].copy_from_slice(data);
+    }
+
+    /// Answers `request` and returns the response's data.
+    fn answer(&mut self, request: &Command<Frame>) -> Result<Vec<u8>, Error> {
+        let start = request.eeprom_address() as usize;
+        let len = request.data_len().min(MAX_CHUNK_LEN);
+
+        let data = match request.id() {
+            CommandId::GetWirelessMouseOnline => vec![self.online as u8],
+            _ if !self.online => Vec::new(),
+            CommandId::DownLoadData => {
+                vec![0, 0, 0, 0, self.cid, self.mid, self.connection as u8, 0]
+            }
+            CommandId::GetBatteryLevel => vec![self.battery_level, self.battery_charge, 40],
+            CommandId::GetMouseCIDMID => vec![self.cid, self.mid],
+            CommandId::GetMouseVersion => vec![self.firmware.0, self.firmware.1],
+            CommandId::GetEEPROM => self
+                .eeprom
+                .get(start..start + len)
+                .ok_or(Error::InvalidEEPROMAddress(start as u16))?
+                .to_vec(),
+            CommandId::SetEEPROM => {
+                let data = &request.data()[..len];
+                self.eeprom
+                    .get_mut(start..start + len)
+                    .ok_or(Error::InvalidEEPROMAddress(start as u16))?
+                    .copy_from_slice(data);
+                data.to_vec()
+            }
+            CommandId::GetFarDistanceMode => vec![self.far_distance as u8],
+            CommandId::SetFarDistanceMode => {
+                self.far_distance = request.data()[0] == 0x1;
+                vec![self.far_distance as u8]
+            }
+            CommandId::SetWirelessDonglePair => {
+                self.pairing = Some((0x1, PAIRING_TIME));
+                Vec::new()
+            }
+            CommandId::GetWirelessDonglePairResult => {
+                let (status, left) = self.pairing.unwrap_or_default();
+                if status == 0x1 {
+                    self.pairing = Some(match left {
+                        0 | 1 => (0x2, 0),
+                        left => (0x1, left - 1),
+                    });
+                }
+                vec![status, left]
+            }
+            CommandId::DongleExitPair => {
+                self.pairing = None;
+                Vec::new()
+            }
+            id => {
+                return Err(Error::ParseError(format!(
+                    "Simulated mouse: Unsupported command {:?}",
+                    id
+                )))
+            }
+        };
+
+        Ok(data)
+    }
+}
+
+/// A mouse living in memory, for tests and for developing against the API without hardware.
+///
+/// Answers the commands used by the [`MouseManager`](crate::manager::MouseManager) from a
+/// [`SimulatedState`], starting with a valid configuration. A dormant mouse answers the
+/// online query only, everything else comes back empty.
+#[derive(Debug, Default)]
+pub struct SimulatedMouse {
+    state: Mutex<SimulatedState>,
+}
+
+impl SimulatedMouse {
+    pub fn new(state: SimulatedState) -> Self {
+        SimulatedMouse {
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Runs `func` on the state, e.g. to drain the battery or put the mouse to sleep.
+    pub fn with_state<U>(&self, func: impl FnOnce(&mut SimulatedState) -> U) -> U {
+        func(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+}
+
+impl Transport for SimulatedMouse {
+    fn exchange(
+        &self,
+        request: &[u8],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
+        let request = parse_frame(request)?;
+        let data = self.with_state(|state| state.answer(&request))?;
+
+        let mut response = Command::<Frame>::default();
+        response.set_id(request.id());
+        response.set_eeprom_address(request.eeprom_address());
+        response.set_data_len(data.len())?;
+        response.set_data(&data, 0x0)?;
+
+        Ok(response.as_bytes())
+    }
+}
@@ -0,0 +1,877 @@
+use std::sync::Arc;
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    capabilities::{Capabilities, Feature},
+    color::{Color, Hsv},
+    commands::prelude::*,
+    manager::{events::Event, MouseManager, Profile},
+    types::{Decaseconds, Duration, DurationRange, Milliseconds, TimeUnit},
+};
+use libatk_rs::prelude::*;
+
+pub const PRESETS: [Preset; 8] = [
+    Preset::Preset1,
+    Preset::Preset2,
+    Preset::Preset3,
+    Preset::Preset4,
+    Preset::Preset5,
+    Preset::Preset6,
+    Preset::Preset7,
+    Preset::Preset8,
+];
+
+/// DPI changes by one step per key press, ten with shift or page up/down.
+const DPI_STEP: u16 = 50;
+/// The largest DPI the preset encoding holds.
+const MAX_DPI: u16 = 1024 * DPI_STEP;
+/// Degrees the hue of a colour turns per key press.
+const HUE_STEP: f32 = 15.0;
+
+/// An editable value shown by the configurator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Dpi(Preset),
+    Color(Preset),
+    LedMode,
+    LedBrightness,
+    LedBreathingRate,
+    LedEnabled,
+    StabilizationTime,
+    MotionSync,
+    CloseLedTime,
+    LinearCorrection,
+    RippleControl,
+    MoveCloseLed,
+    SensorSleep,
+    SensorSleepTime,
+    PerformanceMode,
+    RfTxTime,
+    SilentHeight,
+    FarDistanceMode,
+}
+
+/// The settings shown outside of the DPI table, by section.
+pub const SECTIONS: [(&str, &[Field]); 4] = [
+    (
+        "LED",
+        &[
+            Field::LedMode,
+            Field::LedBrightness,
+            Field::LedBreathingRate,
+            Field::LedEnabled,
+        ],
+    ),
+    (
+        "Mouse performance",
+        &[
+            Field::StabilizationTime,
+            Field::MotionSync,
+            Field::CloseLedTime,
+            Field::LinearCorrection,
+            Field::RippleControl,
+        ],
+    ),
+    (
+        "Sensor performance",
+        &[
+            Field::MoveCloseLed,
+            Field::SensorSleep,
+            Field::SensorSleepTime,
+            Field::PerformanceMode,
+            Field::RfTxTime,
+        ],
+    ),
+    ("Other", &[Field::SilentHeight, Field::FarDistanceMode]),
+];
+
+impl Field {
+    /// Every field, in the order the selection moves through them.
+    pub fn all() -> Vec<Field> {
+        PRESETS
+            .iter()
+            .flat_map(|&preset| [Field::Dpi(preset), Field::Color(preset)])
+            .chain(
+                SECTIONS
+                    .iter()
+                    .flat_map(|(_, fields)| fields.iter().copied()),
+            )
+            .collect()
+    }
+
+    /// The fields `capabilities` allows editing, in selection order.
+    pub fn supported(capabilities: &Capabilities) -> Vec<Field> {
+        Field::all()
+            .into_iter()
+            .filter(|field| match field {
+                Field::Dpi(preset) | Field::Color(preset) => {
+                    capabilities.check_preset(*preset).is_ok()
+                }
+                field => field
+                    .feature()
+                    .is_none_or(|feature| capabilities.supports(feature)),
+            })
+            .collect()
+    }
+
+    /// The capability the field needs, `None` when every mouse has it.
+    fn feature(&self) -> Option<Feature> {
+        match self {
+            Field::Dpi(_) | Field::Color(_) => None,
+            Field::LedMode | Field::LedBrightness | Field::LedBreathingRate | Field::LedEnabled => {
+                Some(Feature::DpiLed)
+            }
+            Field::StabilizationTime => Some(Feature::StabilizationTime),
+            Field::MotionSync => Some(Feature::MotionSync),
+            Field::CloseLedTime => Some(Feature::CloseLedTime),
+            Field::LinearCorrection => Some(Feature::LinearCorrection),
+            Field::RippleControl => Some(Feature::RippleControl),
+            Field::MoveCloseLed => Some(Feature::MoveCloseLed),
+            Field::SensorSleep | Field::SensorSleepTime => Some(Feature::SensorSleep),
+            Field::PerformanceMode => Some(Feature::PerformanceMode),
+            Field::RfTxTime => Some(Feature::RfTxTime),
+            Field::SilentHeight => Some(Feature::SilentHeight),
+            Field::FarDistanceMode => Some(Feature::FarDistanceMode),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Dpi(_) => "DPI",
+            Field::Color(_) => "Colour",
+            Field::LedMode => "Effect",
+            Field::LedBrightness => "Brightness",
+            Field::LedBreathingRate => "Breathing rate",
+            Field::LedEnabled => "Enabled",
+            Field::StabilizationTime => "Stabilization time",
+            Field::MotionSync => "Motion sync",
+            Field::CloseLedTime => "Close LED time",
+            Field::LinearCorrection => "Linear correction",
+            Field::RippleControl => "Ripple control",
+            Field::MoveCloseLed => "Move close LED",
+            Field::SensorSleep => "Sensor sleep",
+            Field::SensorSleepTime => "Sensor sleep time",
+            Field::PerformanceMode => "Performance mode",
+            Field::RfTxTime => "RF Tx time",
+            Field::SilentHeight => "Silent height",
+            Field::FarDistanceMode => "Far distance mode",
+        }
+    }
+
+    /// Whether pressing enter asks for a value instead of toggling or cycling it.
+    fn is_typed(&self) -> bool {
+        matches!(
+            self,
+            Field::Dpi(_)
+                | Field::Color(_)
+                | Field::StabilizationTime
+                | Field::CloseLedTime
+                | Field::SensorSleepTime
+                | Field::RfTxTime
+        )
+    }
+}
+
+/// The configurable part of a [`Profile`], as edited by the configurator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub gears: [Gear; 8],
+    pub led: DpiLedSettings,
+    pub mouse_performance: MousePerfSettings,
+    pub sensor_performance: SensorPerfSettings,
+    pub silent_height: SilentHeightMode,
+    pub far_distance_mode: bool,
+}
+
+impl From<&Profile> for Settings {
+    fn from(profile: &Profile) -> Self {
+        Settings {
+            gears: PRESETS.map(|preset| {
+                let (first, second) = profile.dpi_profile(preset.into());
+                match Slot::from(preset) {
+                    Slot::First => first,
+                    Slot::Second => second,
+                }
+            }),
+            led: profile.dpi_led_settings().clone(),
+            mouse_performance: profile.mouse_performance_settings().clone(),
+            sensor_performance: profile.sensor_performance_settings().clone(),
+            silent_height: profile.silent_height().silent_height(),
+            far_distance_mode: profile.far_distance_mode().far_distance_mode(),
+        }
+    }
+}
+
+fn cycle<T: Copy + PartialEq>(values: &[T], current: T, step: i32) -> T {
+    let index = values.iter().position(|&v| v == current).unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+/// Moves `millis` by `step` units of `T`, staying within `range`.
+fn step_duration<T: TimeUnit>(range: &DurationRange<T>, millis: u32, step: i32) -> Duration<T> {
+    let unit = (millis / T::FACTOR) as i64 + step as i64;
+    let unit = unit.clamp(range.min().as_unit() as i64, range.max().as_unit() as i64);
+    Duration::new(unit as u32)
+}
+
+impl Settings {
+    fn gear(&self, preset: Preset) -> &Gear {
+        &self.gears[preset as usize]
+    }
+
+    /// The value of `field` as shown on screen.
+    pub fn value(&self, field: Field) -> String {
+        let on_off = |value: bool| if value { "On" } else { "Off" }.to_string();
+        let led = &self.led;
+        let mouse = &self.mouse_performance;
+        let sensor = &self.sensor_performance;
+
+        match field {
+            Field::Dpi(preset) => self.gear(preset).dpi().to_string(),
+            Field::Color(preset) => self.gear(preset).color().to_string(),
+            Field::LedMode => format!("{:?}", led.mode()),
+            Field::LedBrightness => format!("{:?}", led.brightness()),
+            Field::LedBreathingRate => format!("{:?}", led.breathing_rate()),
+            Field::LedEnabled => on_off(led.enabled()),
+            Field::StabilizationTime => mouse.stabilization_time().to_string(),
+            Field::MotionSync => on_off(mouse.motion_sync()),
+            Field::CloseLedTime => mouse.close_led_time().to_string(),
+            Field::LinearCorrection => on_off(mouse.linear_correction()),
+            Field::RippleControl => on_off(mouse.ripple_control()),
+            Field::MoveCloseLed => on_off(sensor.move_close_led()),
+            Field::SensorSleep => on_off(sensor.sensor_sleep()),
+            Field::SensorSleepTime => sensor.sensor_sleep_time().to_string(),
+            Field::PerformanceMode => on_off(sensor.performance_mode()),
+            Field::RfTxTime => sensor.rf_tx_time().to_string(),
+            Field::SilentHeight => format!("{:?}", self.silent_height),
+            Field::FarDistanceMode => on_off(self.far_distance_mode),
+        }
+    }
+
+    fn set_gear(&mut self, preset: Preset, dpi: Dpi, color: Color) {
+        self.gears[preset as usize] = Gear::new(dpi, color);
+    }
+
+    fn patch_led(&mut self, patch: LedPatch) {
+        self.led = self.led.apply(&patch);
+    }
+
+    fn patch_mouse(&mut self, patch: MousePerfPatch) -> Result<(), Error> {
+        self.mouse_performance = self.mouse_performance.apply(&patch)?;
+        Ok(())
+    }
+
+    fn patch_sensor(&mut self, patch: SensorPerfPatch) -> Result<(), Error> {
+        self.sensor_performance = self.sensor_performance.apply(&patch)?;
+        Ok(())
+    }
+
+    /// Moves `field` by `step`: DPI in steps of 50, colours around the hue circle, durations
+    /// in steps of their unit. Toggles switches and cycles through choices.
+    fn adjust(&mut self, field: Field, step: i32) -> Result<(), Error> {
+        let led = self.led.clone();
+        let mouse = self.mouse_performance.clone();
+        let sensor = self.sensor_performance.clone();
+
+        match field {
+            Field::Dpi(preset) => {
+                let gear = self.gear(preset).clone();
+                let dpi = gear.dpi().dpi() as i32 + step * DPI_STEP as i32;
+                let dpi = dpi.clamp(DPI_STEP as i32, MAX_DPI as i32) as u16;
+                self.set_gear(preset, Dpi::new(dpi), gear.color());
+            }
+            Field::Color(preset) => {
+                let gear = self.gear(preset).clone();
+                let hsv = Hsv::from(gear.color());
+                let hue = hsv.hue() + step as f32 * HUE_STEP;
+                let color = Hsv::new(hue, hsv.saturation(), hsv.value()).into();
+                self.set_gear(preset, gear.dpi(), color);
+            }
+            Field::LedMode => self.patch_led(LedPatch::new().mode(cycle(
+                &[LedEffectMode::Static, LedEffectMode::Breathing],
+                led.mode(),
+                step,
+            ))),
+            Field::LedBrightness => self.patch_led(LedPatch::new().brightness(cycle(
+                &[
+                    LedBrightnessLevel::Low,
+                    LedBrightnessLevel::Medium,
+                    LedBrightnessLevel::High,
+                ],
+                led.brightness(),
+                step,
+            ))),
+            Field::LedBreathingRate => self.patch_led(LedPatch::new().breathing_rate(cycle(
+                &[
+                    LedBreathingRate::Slow,
+                    LedBreathingRate::Medium,
+                    LedBreathingRate::Fast,
+                ],
+                led.breathing_rate(),
+                step,
+            ))),
+            Field::LedEnabled => self.patch_led(LedPatch::new().enabled(!led.enabled())),
+            Field::StabilizationTime => {
+                self.patch_mouse(MousePerfPatch::new().stabilization_time(step_duration(
+                    &STABILIZATION_TIME_RANGE,
+                    mouse.stabilization_time().as_millis(),
+                    step,
+                )))?
+            }
+            Field::MotionSync => {
+                self.patch_mouse(MousePerfPatch::new().motion_sync(!mouse.motion_sync()))?
+            }
+            Field::CloseLedTime => {
+                self.patch_mouse(MousePerfPatch::new().close_led_time(step_duration(
+                    &CLOSE_LED_TIME_RANGE,
+                    mouse.close_led_time().as_millis(),
+                    step,
+                )))?
+            }
+            Field::LinearCorrection => self
+                .patch_mouse(MousePerfPatch::new().linear_correction(!mouse.linear_correction()))?,
+            Field::RippleControl => {
+                self.patch_mouse(MousePerfPatch::new().ripple_control(!mouse.ripple_control()))?
+            }
+            Field::MoveCloseLed => {
+                self.patch_sensor(SensorPerfPatch::new().move_close_led(!sensor.move_close_led()))?
+            }
+            Field::SensorSleep => {
+                self.patch_sensor(SensorPerfPatch::new().sensor_sleep(!sensor.sensor_sleep()))?
+            }
+            Field::SensorSleepTime => {
+                self.patch_sensor(SensorPerfPatch::new().sensor_sleep_time(step_duration(
+                    &SENSOR_SLEEP_TIME_RANGE,
+                    sensor.sensor_sleep_time().as_millis(),
+                    step,
+                )))?
+            }
+            Field::PerformanceMode => self.patch_sensor(
+                SensorPerfPatch::new().performance_mode(!sensor.performance_mode()),
+            )?,
+            Field::RfTxTime => self.patch_sensor(SensorPerfPatch::new().rf_tx_time(
+                step_duration(&RF_TX_TIME_RANGE, sensor.rf_tx_time().as_millis(), step),
+            ))?,
+            Field::SilentHeight => {
+                self.silent_height = cycle(
+                    &[
+                        SilentHeightMode::Off,
+                        SilentHeightMode::OneMm,
+                        SilentHeightMode::TwoMm,
+                    ],
+                    self.silent_height,
+                    step,
+                )
+            }
+            Field::FarDistanceMode => self.far_distance_mode = !self.far_distance_mode,
+        }
+
+        Ok(())
+    }
+
+    /// Sets a typed field from `input`, in the formats the command line accepts.
+    fn parse(&mut self, field: Field, input: &str) -> Result<(), Error> {
+        match field {
+            Field::Dpi(preset) => {
+                let dpi = input
+                    .trim()
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|dpi| (DPI_STEP..=MAX_DPI).contains(dpi) && dpi % DPI_STEP == 0)
+                    .ok_or_else(|| {
+                        Error::ParseError(format!(
+                            "DPI: {} is not a multiple of {} up to {}",
+                            input, DPI_STEP, MAX_DPI
+                        ))
+                    })?;
+                let color = self.gear(preset).color();
+                self.set_gear(preset, Dpi::new(dpi), color);
+            }
+            Field::Color(preset) => {
+                let dpi = self.gear(preset).dpi();
+                self.set_gear(preset, dpi, input.parse()?);
+            }
+            Field::StabilizationTime => self.patch_mouse(
+                MousePerfPatch::new().stabilization_time(input.parse::<Duration<Milliseconds>>()?),
+            )?,
+            Field::CloseLedTime => self.patch_mouse(
+                MousePerfPatch::new().close_led_time(input.parse::<Duration<Decaseconds>>()?),
+            )?,
+            Field::SensorSleepTime => self.patch_sensor(
+                SensorPerfPatch::new().sensor_sleep_time(input.parse::<Duration<Decaseconds>>()?),
+            )?,
+            Field::RfTxTime => self.patch_sensor(
+                SensorPerfPatch::new().rf_tx_time(input.parse::<Duration<Milliseconds>>()?),
+            )?,
+            field => self.adjust(field, 1)?,
+        }
+
+        Ok(())
+    }
+
+    fn led_patch(&self) -> LedPatch {
+        LedPatch::new()
+            .mode(self.led.mode())
+            .brightness(self.led.brightness())
+            .breathing_rate(self.led.breathing_rate())
+            .enabled(self.led.enabled())
+    }
+
+    fn mouse_patch(&self) -> MousePerfPatch {
+        let settings = &self.mouse_performance;
+        MousePerfPatch::new()
+            .stabilization_time(settings.stabilization_time())
+            .motion_sync(settings.motion_sync())
+            .close_led_time(settings.close_led_time().convert())
+            .linear_correction(settings.linear_correction())
+            .ripple_control(settings.ripple_control())
+    }
+
+    fn sensor_patch(&self) -> SensorPerfPatch {
+        let settings = &self.sensor_performance;
+        SensorPerfPatch::new()
+            .move_close_led(settings.move_close_led())
+            .sensor_sleep(settings.sensor_sleep())
+            .sensor_sleep_time(settings.sensor_sleep_time().convert())
+            .performance_mode(settings.performance_mode())
+            .rf_tx_time(settings.rf_tx_time())
+    }
+}
+
+/// The device state reported by the watcher, `None` until first seen.
+#[derive(Debug, Default, Clone)]
+pub struct DeviceStatus {
+    pub online: Option<bool>,
+    pub battery: Option<(u8, u8)>,
+    pub connection: Option<ConnectionType>,
+    pub active_preset: Option<Preset>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub text: String,
+    pub is_error: bool,
+}
+
+/// State of the configurator: the settings on the device, the edited copy and the
+/// selection. Edits stay pending until applied with the manager's setters.
+pub struct App {
+    manager: Arc<MouseManager>,
+    fields: Vec<Field>,
+    pub saved: Settings,
+    pub draft: Settings,
+    pub polling_rate: PollingRate,
+    pub selected: usize,
+    /// Text typed for the selected field, while entering a value.
+    pub input: Option<String>,
+    pub status: DeviceStatus,
+    pub message: Option<Message>,
+    /// Set by the first quit with pending changes, the second one quits.
+    quit_requested: bool,
+    pub quit: bool,
+}
+
+impl App {
+    pub fn new(manager: Arc<MouseManager>) -> Self {
+        let profile = manager.profile().clone();
+        let settings = Settings::from(&profile);
+        let fields = Field::supported(&manager.capabilities());
+
+        App {
+            manager,
+            fields,
+            saved: settings.clone(),
+            draft: settings,
+            polling_rate: profile.mouse_info().poll_rate(),
+            selected: 0,
+            input: None,
+            status: DeviceStatus::default(),
+            message: None,
+            quit_requested: false,
+            quit: false,
+        }
+    }
+
+    /// Whether the mouse has `field`, unsupported fields are not shown.
+    pub fn shows(&self, field: Field) -> bool {
+        self.fields.contains(&field)
+    }
+
+    pub fn selected_field(&self) -> Field {
+        self.fields[self.selected]
+    }
+
+    pub fn is_pending(&self, field: Field) -> bool {
+        self.draft.value(field) != self.saved.value(field)
+    }
+
+    pub fn pending(&self) -> Vec<Field> {
+        self.fields
+            .iter()
+            .copied()
+            .filter(|&field| self.is_pending(field))
+            .collect()
+    }
+
+    fn info(&mut self, text: impl Into<String>) {
+        self.message = Some(Message {
+            text: text.into(),
+            is_error: false,
+        });
+    }
+
+    fn error(&mut self, text: impl Into<String>) {
+        self.message = Some(Message {
+            text: text.into(),
+            is_error: true,
+        });
+    }
+
+    fn edit(&mut self, func: impl FnOnce(&mut Settings, Field) -> Result<(), Error>) {
+        let field = self.selected_field();
+        let mut draft = self.draft.clone();
+        match func(&mut draft, field) {
+            Ok(()) => {
+                self.draft = draft;
+                self.message = None;
+            }
+            Err(e) => self.error(e.to_string()),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) {
+        if let Some(input) = &mut self.input {
+            match key.code {
+                KeyCode::Enter => {
+                    let input = self.input.take().unwrap_or_default();
+                    self.edit(|draft, field| draft.parse(field, &input));
+                }
+                KeyCode::Esc => self.input = None,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            }
+            return;
+        }
+
+        let coarse = key.modifiers.contains(KeyModifiers::SHIFT);
+        let step = if coarse { 10 } else { 1 };
+        if !matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) {
+            self.quit_requested = false;
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = (self.selected + self.fields.len() - 1) % self.fields.len()
+            }
+            KeyCode::Down | KeyCode::Char('j') | KeyCode::Tab => {
+                self.selected = (self.selected + 1) % self.fields.len()
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.edit(|draft, field| draft.adjust(field, -step))
+            }
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.edit(|draft, field| draft.adjust(field, step))
+            }
+            KeyCode::PageDown => self.edit(|draft, field| draft.adjust(field, -10)),
+            KeyCode::PageUp => self.edit(|draft, field| draft.adjust(field, 10)),
+            KeyCode::Enter | KeyCode::Char(' ') => {
+                let field = self.selected_field();
+                if field.is_typed() {
+                    self.input = Some(String::new());
+                } else {
+                    self.edit(|draft, field| draft.adjust(field, 1));
+                }
+            }
+            KeyCode::Backspace | KeyCode::Delete => {
+                let field = self.selected_field();
+                self.revert_field(field);
+            }
+            KeyCode::Char('a') => self.apply(),
+            KeyCode::Char('r') => self.revert(),
+            KeyCode::Char('R') => self.reload(),
+            KeyCode::Char('q') | KeyCode::Esc => {
+                let pending = self.pending().len();
+                if pending == 0 || self.quit_requested {
+                    self.quit = true;
+                } else {
+                    self.quit_requested = true;
+                    self.error(format!(
+                        "{} unapplied changes, press q again to quit without applying",
+                        pending
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Updates the device status from a [`MouseManager`] event.
+    pub fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Online => self.status.online = Some(true),
+            Event::Dormant => self.status.online = Some(false),
+            Event::Battery { level, charge } => self.status.battery = Some((level, charge)),
+            Event::Connection(connection) => self.status.connection = Some(connection),
+            Event::ActivePreset(preset) => self.status.active_preset = Some(preset),
+            Event::PollFailed(error) => self.error(format!("Polling failed: {}", error)),
+            Event::ProfileLoaded | Event::ProfileChanged(_) => {}
+        }
+    }
+
+    /// Copies the device's value of `field` back to the draft.
+    fn revert_field(&mut self, field: Field) {
+        let saved = &self.saved;
+        let draft = &mut self.draft;
+        match field {
+            Field::Dpi(preset) | Field::Color(preset) => {
+                let (dpi, color) = match field {
+                    Field::Dpi(_) => (saved.gear(preset).dpi(), draft.gear(preset).color()),
+                    _ => (draft.gear(preset).dpi(), saved.gear(preset).color()),
+                };
+                draft.set_gear(preset, dpi, color);
+            }
+            Field::LedMode => draft.patch_led(LedPatch::new().mode(saved.led.mode())),
+            Field::LedBrightness => {
+                draft.patch_led(LedPatch::new().brightness(saved.led.brightness()))
+            }
+            Field::LedBreathingRate => {
+                draft.patch_led(LedPatch::new().breathing_rate(saved.led.breathing_rate()))
+            }
+            Field::LedEnabled => draft.patch_led(LedPatch::new().enabled(saved.led.enabled())),
+            // Patches of values read from the device always validate.
+            Field::StabilizationTime => {
+                let time = saved.mouse_performance.stabilization_time();
+                let _ = draft.patch_mouse(MousePerfPatch::new().stabilization_time(time));
+            }
+            Field::MotionSync => {
+                let value = saved.mouse_performance.motion_sync();
+                let _ = draft.patch_mouse(MousePerfPatch::new().motion_sync(value));
+            }
+            Field::CloseLedTime => {
+                let time = saved.mouse_performance.close_led_time().convert();
+                let _ = draft.patch_mouse(MousePerfPatch::new().close_led_time(time));
+            }
+            Field::LinearCorrection => {
+                let value = saved.mouse_performance.linear_correction();
+                let _ = draft.patch_mouse(MousePerfPatch::new().linear_correction(value));
+            }
+            Field::RippleControl => {
+                let value = saved.mouse_performance.ripple_control();
+                let _ = draft.patch_mouse(MousePerfPatch::new().ripple_control(value));
+            }
+            Field::MoveCloseLed => {
+                let value = saved.sensor_performance.move_close_led();
+                let _ = draft.patch_sensor(SensorPerfPatch::new().move_close_led(value));
+            }
+            Field::SensorSleep => {
+                let value = saved.sensor_performance.sensor_sleep();
+                let _ = draft.patch_sensor(SensorPerfPatch::new().sensor_sleep(value));
+            }
+            Field::SensorSleepTime => {
+                let time = saved.sensor_performance.sensor_sleep_time().convert();
+                let _ = draft.patch_sensor(SensorPerfPatch::new().sensor_sleep_time(time));
+            }
+            Field::PerformanceMode => {
+                let value = saved.sensor_performance.performance_mode();
+                let _ = draft.patch_sensor(SensorPerfPatch::new().performance_mode(value));
+            }
+            Field::RfTxTime => {
+                let time = saved.sensor_performance.rf_tx_time();
+                let _ = draft.patch_sensor(SensorPerfPatch::new().rf_tx_time(time));
+            }
+            Field::SilentHeight => draft.silent_height = saved.silent_height,
+            Field::FarDistanceMode => draft.far_distance_mode = saved.far_distance_mode,
+        }
+    }
+
+    /// Drops every pending change.
+    pub fn revert(&mut self) {
+        self.draft = self.saved.clone();
+        self.info("Reverted pending changes");
+    }
+
+    /// Reads the configuration from the device again, dropping pending changes.
+    pub fn reload(&mut self) {
+        match self.manager.load_profile() {
+            Ok(()) => {
+                let profile = self.manager.profile().clone();
+                self.saved = Settings::from(&profile);
+                self.draft = self.saved.clone();
+                self.polling_rate = profile.mouse_info().poll_rate();
+                self.info("Reloaded from the device");
+            }
+            Err(e) => self.error(e.to_string()),
+        }
+    }
+
+    /// Writes every pending change with the manager's setters, one block at a time.
+    ///
+    /// Stops at the first failure; changes written before it are kept, the rest stay pending.
+    pub fn apply(&mut self) {
+        let pending = self.pending().len();
+        if pending == 0 {
+            self.info("Nothing to apply");
+            return;
+        }
+        if self.status.online == Some(false) {
+            self.error("Mouse is dormant, move it to wake it up before applying");
+            return;
+        }
+
+        let result = self.write();
+        self.saved = Settings::from(&*self.manager.profile());
+
+        match result {
+            Ok(()) => {
+                // The device's response may normalize values, show what it stored.
+                self.draft = self.saved.clone();
+                self.info(format!("Applied {} changes", pending));
+            }
+            Err(e) => self.error(format!("Apply failed: {}", e)),
+        }
+    }
+
+    fn write(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let manager = &self.manager;
+        let (saved, draft) = (&self.saved, &self.draft);
+
+        for preset in PRESETS {
+            let (before, after) = (saved.gear(preset), draft.gear(preset));
+            if before.dpi() != after.dpi() {
+                manager.set_dpi_profile_dpi(preset, after.dpi())?;
+            }
+            if before.color() != after.color() {
+                manager.set_dpi_profile_color(preset, after.color())?;
+            }
+        }
+        if saved.led != draft.led {
+            manager.set_dpi_led_settings(draft.led_patch())?;
+        }
+        if saved.mouse_performance != draft.mouse_performance {
+            manager.set_mouse_performance_settings(draft.mouse_patch())?;
+        }
+        if saved.sensor_performance != draft.sensor_performance {
+            manager.set_sensor_performance_settings(draft.sensor_patch())?;
+        }
+        if saved.silent_height != draft.silent_height {
+            manager.set_silent_height(draft.silent_height)?;
+        }
+        if saved.far_distance_mode != draft.far_distance_mode {
+            manager.set_far_distance_mode(draft.far_distance_mode)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::simulated::SimulatedMouse;
+
+    fn app() -> App {
+        App::new(Arc::new(
+            MouseManager::new(SimulatedMouse::default()).unwrap(),
+        ))
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::from(code));
+    }
+
+    fn type_value(app: &mut App, text: &str) {
+        press(app, KeyCode::Enter);
+        for c in text.chars() {
+            press(app, KeyCode::Char(c));
+        }
+        press(app, KeyCode::Enter);
+    }
+
+    fn select(app: &mut App, field: Field) {
+        while app.selected_field() != field {
+            press(app, KeyCode::Down);
+        }
+    }
+
+    #[test]
+    fn edits_stay_pending_until_reverted() {
+        let mut app = app();
+
+        press(&mut app, KeyCode::Right);
+        select(&mut app, Field::MotionSync);
+        press(&mut app, KeyCode::Char(' '));
+
+        assert_eq!(
+            app.pending(),
+            [Field::Dpi(Preset::Preset1), Field::MotionSync]
+        );
+        assert_eq!(app.draft.value(Field::Dpi(Preset::Preset1)), "450");
+
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(app.pending(), [Field::Dpi(Preset::Preset1)]);
+
+        press(&mut app, KeyCode::Char('r'));
+        assert!(app.pending().is_empty());
+    }
+
+    #[test]
+    fn apply_writes_pending_changes() {
+        let mut app = app();
+
+        type_value(&mut app, "1200");
+        press(&mut app, KeyCode::Down);
+        type_value(&mut app, "orange");
+        select(&mut app, Field::CloseLedTime);
+        type_value(&mut app, "2m");
+        select(&mut app, Field::SilentHeight);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('a'));
+
+        assert!(app.pending().is_empty(), "{:?}", app.message);
+        app.reload();
+        let settings = &app.saved;
+        assert_eq!(settings.value(Field::Dpi(Preset::Preset1)), "1200");
+        assert_eq!(settings.value(Field::Color(Preset::Preset1)), "#ffa500");
+        assert_eq!(settings.value(Field::CloseLedTime), "120s");
+        assert_eq!(settings.silent_height, SilentHeightMode::OneMm);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let mut app = app();
+
+        type_value(&mut app, "825");
+        select(&mut app, Field::SensorSleepTime);
+        type_value(&mut app, "15s");
+
+        assert!(app.pending().is_empty());
+        assert!(app.message.as_ref().is_some_and(|m| m.is_error));
+    }
+
+    #[test]
+    fn quitting_with_pending_changes_asks_again() {
+        let mut app = app();
+
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(!app.quit);
+        press(&mut app, KeyCode::Char('q'));
+        assert!(app.quit);
+    }
+
+    #[test]
+    fn dormant_mouse_is_not_written() {
+        let mut app = app();
+
+        app.handle_event(Event::Dormant);
+        press(&mut app, KeyCode::Right);
+        press(&mut app, KeyCode::Char('a'));
+
+        assert_eq!(app.pending().len(), 1);
+        assert!(app.message.as_ref().is_some_and(|m| m.is_error));
+    }
+}
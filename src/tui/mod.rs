@@ -0,0 +1,53 @@
+//! Interactive terminal configurator.
+//!
+//! Shows the cached profile and the device status, and writes edits with the
+//! [`MouseManager`] setters once applied.
+
+mod app;
+mod ui;
+
+use std::{sync::Arc, time::Duration};
+
+use ratatui::crossterm::event::{self, Event as TermEvent, KeyEventKind};
+
+use crate::manager::MouseManager;
+use app::App;
+
+/// How long to wait for a key press before checking for device events again.
+const INPUT_POLL: Duration = Duration::from_millis(100);
+
+/// Runs the configurator until the user quits, polling the device every `interval`.
+pub fn run(
+    manager: MouseManager,
+    interval: Duration,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // Loaded before taking over the terminal, waiting for a dormant mouse prints a prompt.
+    manager.load_profile()?;
+
+    let manager = Arc::new(manager);
+    let events = manager.subscribe();
+    let _watcher = manager.watch(interval);
+    let mut app = App::new(Arc::clone(&manager));
+
+    let mut terminal = ratatui::init();
+    let result = (|| -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        while !app.quit {
+            terminal.draw(|frame| ui::draw(frame, &app))?;
+
+            if event::poll(INPUT_POLL)? {
+                if let TermEvent::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        app.handle_key(key);
+                    }
+                }
+            }
+            for event in events.try_iter() {
+                app.handle_event(event);
+            }
+        }
+        Ok(())
+    })();
+    ratatui::restore();
+
+    result
+}
@@ -0,0 +1,239 @@
+use ratatui::{
+    layout::{Constraint, Layout},
+    style::{Color as TermColor, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Cell, Paragraph, Row, Table},
+    Frame,
+};
+
+use super::app::{App, Field, PRESETS, SECTIONS};
+
+const HELP: &str =
+    "↑↓ select  ←→ change (shift ×10)  enter edit  del revert  a apply  r revert all  R reload  q quit";
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [status, body, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(0),
+        Constraint::Length(2),
+    ])
+    .areas(frame.area());
+    let [presets, settings] =
+        Layout::horizontal([Constraint::Length(40), Constraint::Min(0)]).areas(body);
+
+    frame.render_widget(status_line(app), status);
+    frame.render_widget(preset_table(app), presets);
+    frame.render_widget(settings_table(app), settings);
+    frame.render_widget(footer_lines(app), footer);
+}
+
+fn status_line(app: &App) -> Paragraph<'static> {
+    let status = &app.status;
+    let separator = || Span::raw(" | ");
+
+    let online = match status.online {
+        Some(true) => Span::styled("● Online", Style::new().fg(TermColor::Green)),
+        Some(false) => Span::styled("● Dormant", Style::new().fg(TermColor::Red)),
+        None => Span::styled("● Unknown", Style::new().fg(TermColor::DarkGray)),
+    };
+    let battery = match status.battery {
+        Some((level, charge)) => format!("Battery: {}% | Charge: {}", level, charge),
+        None => "Battery: -".to_string(),
+    };
+    let connection = match status.connection {
+        Some(connection) => format!("Connection: {:?}", connection),
+        None => "Connection: -".to_string(),
+    };
+
+    let mut spans = vec![
+        online,
+        separator(),
+        Span::raw(battery),
+        separator(),
+        Span::raw(connection),
+        separator(),
+        Span::raw(format!("Polling rate: {}", app.polling_rate)),
+    ];
+
+    let pending = app.pending().len();
+    if pending > 0 {
+        spans.push(separator());
+        spans.push(Span::styled(
+            format!("{} pending", pending),
+            Style::new().fg(TermColor::Yellow),
+        ));
+    }
+
+    Paragraph::new(Line::from(spans))
+}
+
+/// The drafted value of `field`, marked when it differs from the device and when selected.
+fn value_cell(app: &App, field: Field) -> Cell<'static> {
+    let mut text = app.draft.value(field);
+    let mut style = Style::new();
+
+    if app.is_pending(field) {
+        text.push('*');
+        style = style.fg(TermColor::Yellow).add_modifier(Modifier::BOLD);
+    }
+    if app.selected_field() == field {
+        style = style.add_modifier(Modifier::REVERSED);
+        if let Some(input) = &app.input {
+            text = format!("{}▏", input);
+        }
+    }
+
+    Cell::from(text).style(style)
+}
+
+fn preset_table(app: &App) -> Table<'static> {
+    let rows = PRESETS
+        .iter()
+        .enumerate()
+        .filter(|(_, &preset)| app.shows(Field::Dpi(preset)))
+        .map(|(index, &preset)| {
+            let active = app.status.active_preset == Some(preset);
+            let color = app.draft.gears[index].color();
+            let swatch = Style::new().bg(TermColor::Rgb(color.red(), color.green(), color.blue()));
+
+            Row::new(vec![
+                Cell::from(if active { "▶" } else { " " }),
+                Cell::from(format!("Preset {}", index + 1)),
+                value_cell(app, Field::Dpi(preset)),
+                Cell::from("    ").style(swatch),
+                value_cell(app, Field::Color(preset)),
+            ])
+        });
+
+    Table::new(
+        rows,
+        [
+            Constraint::Length(1),
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Length(4),
+            Constraint::Length(9),
+        ],
+    )
+    .header(
+        Row::new(vec!["", "Preset", "DPI", "", "Colour"])
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::bordered().title(" DPI presets "))
+}
+
+fn settings_table(app: &App) -> Table<'static> {
+    let heading = Style::new().add_modifier(Modifier::BOLD);
+
+    let mut rows = Vec::new();
+    for (title, fields) in SECTIONS.iter() {
+        let fields: Vec<Field> = fields.iter().copied().filter(|&f| app.shows(f)).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if !rows.is_empty() {
+            rows.push(Row::new(vec![""]));
+        }
+        rows.push(Row::new(vec![Cell::from(*title).style(heading)]));
+        for field in fields {
+            rows.push(Row::new(vec![
+                Cell::from(format!("  {}", field.label())),
+                value_cell(app, field),
+            ]));
+        }
+    }
+    rows.push(Row::new(vec![
+        Cell::from("  Polling rate"),
+        Cell::from(format!("{} (read only)", app.polling_rate))
+            .style(Style::new().fg(TermColor::DarkGray)),
+    ]));
+
+    Table::new(rows, [Constraint::Length(22), Constraint::Min(10)])
+        .block(Block::bordered().title(" Settings "))
+}
+
+fn footer_lines(app: &App) -> Paragraph<'static> {
+    let field = app.selected_field();
+
+    let first = if app.input.is_some() {
+        Line::from(format!(
+            "{}: type a value, enter to set, esc to cancel",
+            field.label()
+        ))
+    } else if let Some(message) = &app.message {
+        let style = match message.is_error {
+            true => Style::new().fg(TermColor::Red),
+            false => Style::new().fg(TermColor::Green),
+        };
+        Line::styled(message.text.clone(), style)
+    } else if app.is_pending(field) {
+        Line::from(format!(
+            "{} on the device: {}",
+            field.label(),
+            app.saved.value(field)
+        ))
+    } else {
+        Line::default()
+    };
+
+    Paragraph::new(vec![
+        first,
+        Line::styled(HELP, Style::new().fg(TermColor::DarkGray)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::prelude::*,
+        manager::{events::Event, MouseManager},
+        transport::simulated::SimulatedMouse,
+    };
+    use ratatui::{
+        backend::TestBackend,
+        crossterm::event::{KeyCode, KeyEvent},
+        Terminal,
+    };
+    use std::sync::Arc;
+
+    fn render(app: &App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.height)
+            .map(|y| {
+                (0..buffer.area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn shows_profile_status_and_pending_changes() {
+        let manager = Arc::new(MouseManager::new(SimulatedMouse::default()).unwrap());
+        let mut app = App::new(manager);
+        app.handle_event(Event::Battery {
+            level: 80,
+            charge: 0,
+        });
+        app.handle_event(Event::ActivePreset(Preset::Preset2));
+
+        let screen = render(&app);
+        assert!(screen.contains("Battery: 80%"), "{}", screen);
+        assert!(screen.contains("▶ Preset 2"), "{}", screen);
+        assert!(screen.contains("26000"), "{}", screen);
+        assert!(screen.contains("Far distance mode"), "{}", screen);
+        assert!(screen.contains("Polling rate: 1000Hz"), "{}", screen);
+        assert!(!screen.contains("pending"), "{}", screen);
+
+        app.handle_key(KeyEvent::from(KeyCode::Right));
+        let screen = render(&app);
+        assert!(screen.contains("450*"), "{}", screen);
+        assert!(screen.contains("1 pending"), "{}", screen);
+        assert!(screen.contains("DPI on the device: 400"), "{}", screen);
+    }
+}
@@ -1,6 +1,9 @@
+use libatk_rs::prelude::*;
+
 pub struct Milliseconds;
 pub struct Seconds;
 pub struct Decaseconds;
+pub struct Minutes;
 
 pub trait TimeUnit {
     const FACTOR: u32;
@@ -22,6 +25,22 @@ impl TimeUnit for Decaseconds {
     const LABEL: &'static str = "ds";
 }
 
+impl TimeUnit for Minutes {
+    const FACTOR: u32 = 60000;
+    const LABEL: &'static str = "m";
+}
+
+/// Suffixes accepted when parsing a duration, along with their factor in milliseconds.
+static UNIT_SUFFIXES: &[(&str, u32)] = &[
+    ("ms", Milliseconds::FACTOR),
+    ("s", Seconds::FACTOR),
+    ("sec", Seconds::FACTOR),
+    ("ds", Decaseconds::FACTOR),
+    ("m", Minutes::FACTOR),
+    ("min", Minutes::FACTOR),
+    ("h", 60 * Minutes::FACTOR),
+];
+
 pub struct Duration<T: TimeUnit> {
     value: u32,
     marker: std::marker::PhantomData<T>,
@@ -29,10 +48,7 @@ pub struct Duration<T: TimeUnit> {
 
 impl<T: TimeUnit> Clone for Duration<T> {
     fn clone(&self) -> Self {
-        Duration {
-            value: self.value,
-            marker: std::marker::PhantomData,
-        }
+        *self
     }
 }
 
@@ -47,9 +63,29 @@ impl<T: TimeUnit> Default for Duration<T> {
     }
 }
 
+impl<T: TimeUnit> PartialEq for Duration<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: TimeUnit> Eq for Duration<T> {}
+
+impl<T: TimeUnit> PartialOrd for Duration<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: TimeUnit> Ord for Duration<T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
 impl<T: TimeUnit> std::fmt::Debug for Duration<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "Duration<{}>({}ms)", T::LABEL, self.value)
     }
 }
 
@@ -59,6 +95,73 @@ impl<T: TimeUnit> std::fmt::Display for Duration<T> {
     }
 }
 
+/// Parses durations such as `8ms`, `30s`, `2m` or `1m30s`.
+///
+/// A bare number is interpreted in the unit `T`. Fractions are allowed as long as the
+/// result is a whole number of milliseconds, e.g. `1.5s`.
+impl<T: TimeUnit> std::str::FromStr for Duration<T> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::ParseError(format!("Duration: Invalid duration: {}", s));
+
+        let input = s.trim().to_ascii_lowercase();
+        if input.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut rest = input.as_str();
+        let mut millis = 0f64;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let (number, tail) = rest.split_at(number_len);
+            let unit_len = tail
+                .find(|c: char| c.is_ascii_digit() || c == '.')
+                .unwrap_or(tail.len());
+            let (unit, tail) = tail.split_at(unit_len);
+
+            let number: f64 = number.parse().map_err(|_| invalid())?;
+            let factor = match unit.trim() {
+                "" if number_len == input.len() => T::FACTOR,
+                unit => UNIT_SUFFIXES
+                    .iter()
+                    .find(|(suffix, _)| *suffix == unit)
+                    .map(|(_, factor)| *factor)
+                    .ok_or_else(invalid)?,
+            };
+
+            millis += number * factor as f64;
+            rest = tail;
+        }
+
+        if millis.fract() != 0.0 || millis > u32::MAX as f64 {
+            return Err(invalid());
+        }
+
+        Ok(Duration::from_millis(millis as u32))
+    }
+}
+
+impl<T: TimeUnit> From<Duration<T>> for std::time::Duration {
+    fn from(duration: Duration<T>) -> Self {
+        std::time::Duration::from_millis(duration.as_millis() as u64)
+    }
+}
+
+impl<T: TimeUnit> TryFrom<std::time::Duration> for Duration<T> {
+    type Error = Error;
+
+    fn try_from(duration: std::time::Duration) -> Result<Self, Self::Error> {
+        u32::try_from(duration.as_millis())
+            .map(Duration::from_millis)
+            .map_err(|_| {
+                Error::ParseError(format!("Duration: {:?} does not fit in u32 ms", duration))
+            })
+    }
+}
+
 impl<T: TimeUnit> Duration<T> {
     pub fn new(value: u32) -> Self {
         Duration {
@@ -67,10 +170,26 @@ impl<T: TimeUnit> Duration<T> {
         }
     }
 
+    pub fn from_millis(millis: u32) -> Self {
+        Duration {
+            value: millis,
+            marker: std::marker::PhantomData,
+        }
+    }
+
     pub fn as_unit(&self) -> u32 {
         self.value / T::FACTOR
     }
 
+    pub fn as_millis(&self) -> u32 {
+        self.value
+    }
+
+    /// Returns `true` if the duration is a whole number of `T`.
+    pub fn is_whole(&self) -> bool {
+        self.value.is_multiple_of(T::FACTOR)
+    }
+
     pub fn convert<U: TimeUnit>(self) -> Duration<U> {
         Duration {
             value: self.value,
@@ -78,3 +197,270 @@ impl<T: TimeUnit> Duration<T> {
         }
     }
 }
+
+/// Formats a number of milliseconds in seconds when that is exact, milliseconds otherwise.
+fn humanize(millis: u32) -> String {
+    if millis.is_multiple_of(Seconds::FACTOR) {
+        format!("{}s", millis / Seconds::FACTOR)
+    } else {
+        format!("{}ms", millis)
+    }
+}
+
+/// The inclusive range of durations a setting accepts, counted in whole units of `T` from
+/// `min` in steps of `step` units.
+///
+/// The device stores these settings in a single byte, so the widest range is `0..=255`
+/// units. That is all the crate knows, models accepting less narrow it with
+/// [`DurationRange::narrow`] from their capabilities.
+pub struct DurationRange<T: TimeUnit> {
+    name: &'static str,
+    min: u32,
+    max: u32,
+    step: u32,
+    marker: std::marker::PhantomData<T>,
+}
+
+impl<T: TimeUnit> Clone for DurationRange<T> {
+    fn clone(&self) -> Self {
+        DurationRange::new(self.name, self.min, self.max).with_step(self.step)
+    }
+}
+
+impl<T: TimeUnit> std::fmt::Debug for DurationRange<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "DurationRange({}: {:?}..={:?} step {:?})",
+            self.name,
+            self.min(),
+            self.max(),
+            self.step()
+        )
+    }
+}
+
+impl<T: TimeUnit> PartialEq for DurationRange<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.min, self.max, self.step) == (other.min, other.max, other.step)
+    }
+}
+
+impl<T: TimeUnit> DurationRange<T> {
+    pub const fn new(name: &'static str, min: u32, max: u32) -> Self {
+        DurationRange {
+            name,
+            min,
+            max,
+            step: 1,
+            marker: std::marker::PhantomData,
+        }
+    }
+
+    /// The same range, only accepting every `step` units counted from the minimum.
+    pub const fn with_step(mut self, step: u32) -> Self {
+        self.step = if step == 0 { 1 } else { step };
+        self
+    }
+
+    /// The part of this range a model accepts, given in milliseconds.
+    ///
+    /// Fails if a bound or the step is not a whole number of `T`, or the narrowed range
+    /// does not fit inside this one.
+    pub fn narrow(&self, min_ms: u32, max_ms: u32, step_ms: u32) -> Result<Self, Error> {
+        let (min, max, step) = (
+            Duration::<T>::from_millis(min_ms),
+            Duration::<T>::from_millis(max_ms),
+            Duration::<T>::from_millis(step_ms),
+        );
+        let whole = min.is_whole() && max.is_whole() && step.is_whole();
+        let inside = self.contains(min) && self.contains(max) && min <= max;
+
+        if !whole || !inside || step_ms == 0 {
+            return Err(Error::ParseError(format!(
+                "{}: Invalid limits {}..={} in steps of {}, must be whole {} within {}..={}",
+                self.name,
+                humanize(min_ms),
+                humanize(max_ms),
+                humanize(step_ms),
+                T::LABEL,
+                humanize(self.min().as_millis()),
+                humanize(self.max().as_millis()),
+            )));
+        }
+
+        Ok(DurationRange::new(self.name, min.as_unit(), max.as_unit()).with_step(step.as_unit()))
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn min(&self) -> Duration<T> {
+        Duration::new(self.min)
+    }
+
+    pub fn max(&self) -> Duration<T> {
+        Duration::new(self.max)
+    }
+
+    pub fn step(&self) -> Duration<T> {
+        Duration::new(self.step)
+    }
+
+    pub fn contains(&self, value: Duration<T>) -> bool {
+        let unit = value.as_unit();
+        value.is_whole()
+            && (self.min..=self.max).contains(&unit)
+            && (unit - self.min).is_multiple_of(self.step)
+    }
+
+    pub fn check(&self, value: Duration<T>) -> Result<Duration<T>, Error> {
+        if self.contains(value) {
+            return Ok(value);
+        }
+
+        let unit = value.as_unit();
+        let problem = if !(self.min..=self.max).contains(&unit) {
+            "is out of range"
+        } else {
+            "is not a whole step"
+        };
+
+        Err(Error::ParseError(format!(
+            "{}: {} {}, allowed {}..={} in steps of {}",
+            self.name,
+            humanize(value.as_millis()),
+            problem,
+            humanize(self.min().as_millis()),
+            humanize(self.max().as_millis()),
+            humanize(self.step().as_millis()),
+        )))
+    }
+
+    /// Validates `value` and returns it as the single byte stored on the device.
+    pub fn encode(&self, value: Duration<T>) -> Result<u8, Error> {
+        self.check(value).map(|value| value.as_unit() as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse<T: TimeUnit>(input: &str) -> Result<u32, Error> {
+        input
+            .parse::<Duration<T>>()
+            .map(|duration| duration.as_millis())
+    }
+
+    #[test]
+    fn durations_parse_with_units() {
+        assert_eq!(parse::<Milliseconds>("8ms").unwrap(), 8);
+        assert_eq!(parse::<Milliseconds>(" 30S ").unwrap(), 30_000);
+        assert_eq!(parse::<Milliseconds>("30 sec").unwrap(), 30_000);
+        assert_eq!(parse::<Milliseconds>("2m").unwrap(), 120_000);
+        assert_eq!(parse::<Milliseconds>("1min").unwrap(), 60_000);
+        assert_eq!(parse::<Milliseconds>("1h").unwrap(), 3_600_000);
+        assert_eq!(parse::<Milliseconds>("3ds").unwrap(), 30_000);
+        assert_eq!(parse::<Milliseconds>("1m30s").unwrap(), 90_000);
+        assert_eq!(parse::<Milliseconds>("1m 30s 5ms").unwrap(), 90_005);
+    }
+
+    #[test]
+    fn bare_numbers_are_in_the_unit_of_the_setting() {
+        assert_eq!(parse::<Milliseconds>("8").unwrap(), 8);
+        assert_eq!(parse::<Seconds>("8").unwrap(), 8_000);
+        assert_eq!(parse::<Decaseconds>("8").unwrap(), 80_000);
+        assert_eq!(parse::<Minutes>("2").unwrap(), 120_000);
+
+        // Only a lone number takes the default unit.
+        assert!(parse::<Seconds>("1m30").is_err());
+    }
+
+    #[test]
+    fn fractions_must_be_whole_milliseconds() {
+        assert_eq!(parse::<Milliseconds>("1.5s").unwrap(), 1_500);
+        assert_eq!(parse::<Milliseconds>("0.25m").unwrap(), 15_000);
+        assert_eq!(parse::<Seconds>("2.5").unwrap(), 2_500);
+        assert!(parse::<Milliseconds>("1.5ms").is_err());
+        assert!(parse::<Milliseconds>("0.0001s").is_err());
+    }
+
+    #[test]
+    fn malformed_durations_are_rejected() {
+        for input in [
+            "",
+            "ms",
+            "8 parsecs",
+            "8mss",
+            "-8ms",
+            "1..5s",
+            "8ms!",
+            "99999999h",
+        ] {
+            let error = parse::<Milliseconds>(input).unwrap_err();
+            assert!(
+                error.to_string().contains("Invalid duration"),
+                "{:?}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn std_durations_convert() {
+        let duration = Duration::<Seconds>::new(3);
+        assert_eq!(
+            std::time::Duration::from(duration),
+            std::time::Duration::from_secs(3)
+        );
+        assert_eq!(
+            Duration::<Seconds>::try_from(std::time::Duration::from_millis(1500)).unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert!(Duration::<Seconds>::try_from(std::time::Duration::from_secs(u64::MAX)).is_err());
+        assert_eq!(format!("{:?}", duration), "Duration<s>(3000ms)");
+    }
+
+    #[test]
+    fn ranges_reject_values_out_of_range_or_off_step() {
+        let range = DurationRange::<Decaseconds>::new("Sleep time", 0, 255);
+        assert_eq!(range.encode(Duration::from_millis(2_550_000)).unwrap(), 255);
+        assert!(range.check(Duration::from_millis(2_560_000)).is_err());
+        assert!(range
+            .check(Duration::from_millis(15_000))
+            .unwrap_err()
+            .to_string()
+            .contains("not a whole step"));
+
+        let stepped = DurationRange::<Milliseconds>::new("Delay", 2, 20).with_step(2);
+        assert!(stepped.contains(Duration::new(2)));
+        assert!(stepped.contains(Duration::new(20)));
+        assert!(!stepped.contains(Duration::new(3)));
+        assert!(!stepped.contains(Duration::new(0)));
+        assert_eq!(
+            stepped.check(Duration::new(22)).unwrap_err().to_string(),
+            "Delay: 22ms is out of range, allowed 2ms..=20ms in steps of 2ms"
+        );
+    }
+
+    #[test]
+    fn ranges_narrow_to_whole_units_inside_them() {
+        let range = DurationRange::<Decaseconds>::new("Sleep time", 0, 255);
+
+        let narrowed = range.narrow(10_000, 600_000, 30_000).unwrap();
+        assert_eq!(
+            narrowed,
+            range.clone().narrow(10_000, 600_000, 30_000).unwrap()
+        );
+        assert!(narrowed.contains(Duration::from_millis(40_000)));
+        assert!(!narrowed.contains(Duration::from_millis(30_000)));
+        assert!(!narrowed.contains(Duration::from_millis(630_000)));
+
+        assert!(range.narrow(5_000, 600_000, 10_000).is_err());
+        assert!(range.narrow(10_000, 3_000_000, 10_000).is_err());
+        assert!(range.narrow(600_000, 10_000, 10_000).is_err());
+        assert!(range.narrow(10_000, 600_000, 0).is_err());
+    }
+}
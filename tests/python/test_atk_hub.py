@@ -0,0 +1,128 @@
+"""Tests for the atk_hub module against the simulated mouse.
+
+Run by the `python::tests` Rust test with `cargo test --features python`, or directly (or
+with pytest) against an installed wheel.
+"""
+
+import contextlib
+
+import atk_hub
+from atk_hub import (
+    Color,
+    Dpi,
+    LedBreathingRate,
+    LedBrightnessLevel,
+    LedEffectMode,
+    LedSettings,
+    MouseManager,
+    PollingRate,
+    Preset,
+    SilentHeightMode,
+)
+
+
+@contextlib.contextmanager
+def raises(exception):
+    try:
+        yield
+    except exception:
+        return
+    raise AssertionError(f"{exception.__name__} not raised")
+
+
+def test_reads_profile():
+    profile = MouseManager.simulated().profile
+
+    assert profile.polling_rate == PollingRate.Hz1000
+    assert profile.active_preset == Preset.Preset1
+    assert [dpi.value for dpi in profile.dpi] == [400, 800, 1600, 3200, 4800, 6400, 12800, 26000]
+    assert len(set(profile.colors)) == 8
+    assert profile.led.enabled
+    assert not profile.far_distance_mode
+
+
+def test_setters_round_trip():
+    mouse = MouseManager.simulated()
+
+    mouse.set_dpi(Preset.Preset3, 1200)
+    mouse.set_dpi(Preset.Preset4, Dpi(1250))
+    mouse.set_color(Preset.Preset8, "red")
+    mouse.led = LedSettings(
+        LedEffectMode.Breathing, LedBrightnessLevel.Medium, LedBreathingRate.Fast, True
+    )
+    perf = mouse.mouse_performance
+    perf.motion_sync = not perf.motion_sync
+    perf.close_led_time_ms = 60_000
+    mouse.mouse_performance = perf
+    mouse.far_distance_mode = True
+    mouse.silent_height = SilentHeightMode.TwoMm
+
+    mouse.load_profile()
+    profile = mouse.profile
+    assert profile.dpi[2] == Dpi(1200)
+    assert profile.dpi[3] == Dpi(1250)
+    assert profile.colors[7] == Color(0xFF, 0, 0)
+    assert profile.led.mode == LedEffectMode.Breathing
+    assert profile.led.breathing_rate == LedBreathingRate.Fast
+    assert profile.mouse_performance == perf
+    assert profile.far_distance_mode
+    assert mouse.silent_height == SilentHeightMode.TwoMm
+
+
+def test_apply_copies_profile():
+    source = MouseManager.simulated()
+    source.set_dpi(Preset.Preset1, 3000)
+    source.far_distance_mode = True
+
+    target = MouseManager.simulated()
+    written = target.apply(source.profile)
+
+    assert written
+    target.load_profile()
+    assert target.profile.dpi == source.profile.dpi
+    assert target.profile.colors == source.profile.colors
+    assert target.far_distance_mode
+
+
+def test_battery():
+    battery = MouseManager.simulated().battery
+
+    assert battery.level == 100
+    assert battery.voltage > 0
+
+
+def test_values():
+    assert Color.parse("#ff8800") == Color(0xFF, 0x88, 0)
+    assert str(Color(1, 2, 3)) == "#010203"
+    assert repr(Dpi(800)) == "Dpi(800)"
+    assert int(Dpi(800)) == 800
+
+    with raises(ValueError):
+        Dpi(825)
+    with raises(ValueError):
+        Color.parse("not a colour")
+
+
+def test_errors():
+    mouse = MouseManager.simulated()
+
+    with raises(ValueError):
+        mouse.set_dpi(Preset.Preset1, 825)
+
+    perf = mouse.sensor_performance
+    perf.sensor_sleep_time_ms = 12_345
+    with raises(atk_hub.InvalidValueError):
+        mouse.sensor_performance = perf
+
+    with raises(FileNotFoundError):
+        MouseManager.replay("/nonexistent/trace.jsonl")
+
+    assert issubclass(atk_hub.DeviceError, atk_hub.AtkError)
+    assert issubclass(atk_hub.TransactionError, atk_hub.AtkError)
+
+
+if __name__ == "__main__":
+    for name, test in list(globals().items()):
+        if name.startswith("test_"):
+            test()
+            print(f"{name} ok")